@@ -0,0 +1,45 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::texture::{Texture, UvMapping};
+use crate::tuple::{color, Tuple};
+
+/// What the rays see when they miss all the shapes of the world
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Background {
+    Solid(Tuple),
+    /// Blends between the colors depending on how far up or down the ray
+    /// points, horizontal rays get the average of the two
+    Gradient {
+        bottom: Tuple,
+        top: Tuple,
+    },
+    /// Wraps the image around the scene with its center towards +z
+    Equirectangular(Texture),
+    /// Surrounds the scene with a cube whose faces are laid out in the same
+    /// cross as for the cube texture mapping
+    CubeMap(Texture),
+}
+
+impl Background {
+    /// Returns the color seen in the given direction
+    pub fn color_at(&self, direction: Tuple) -> Tuple {
+        match self {
+            Background::Solid(c) => *c,
+            Background::Gradient { bottom, top } => {
+                let t = 0.5 * (direction.normalized().y() + 1.0);
+                *bottom * (1.0 - t) + *top * t
+            }
+            Background::Equirectangular(texture) => {
+                texture.sample_mapped(UvMapping::Spherical, direction)
+            }
+            Background::CubeMap(texture) => texture.sample_mapped(UvMapping::Cube, direction),
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Background {
+        Background::Solid(color(0.0, 0.0, 0.0))
+    }
+}
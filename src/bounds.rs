@@ -0,0 +1,176 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::matrix::Matrix;
+use crate::ray::Ray;
+use crate::tuple::{point, Tuple};
+
+/// Axis-aligned bounding box
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bounds {
+    min: Tuple,
+    max: Tuple,
+}
+
+impl Bounds {
+    pub fn new(min: Tuple, max: Tuple) -> Bounds {
+        Bounds { min, max }
+    }
+
+    /// A box containing nothing; merging anything with it gives the other box
+    pub fn empty() -> Bounds {
+        Bounds {
+            min: point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn infinite() -> Bounds {
+        Bounds {
+            min: point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            max: point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        }
+    }
+
+    pub fn from_points(pts: &[Tuple]) -> Bounds {
+        let mut b = Bounds::empty();
+        for pt in pts.iter() {
+            b.add_point(*pt);
+        }
+        b
+    }
+
+    pub fn min(&self) -> Tuple {
+        self.min
+    }
+
+    pub fn max(&self) -> Tuple {
+        self.max
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|i| self.min.at(i) > self.max.at(i))
+    }
+
+    pub fn is_finite(&self) -> bool {
+        (0..3).all(|i| self.min.at(i).is_finite() && self.max.at(i).is_finite())
+    }
+
+    pub fn add_point(&mut self, pt: Tuple) {
+        for i in 0..3 {
+            self.min.set(i, self.min.at(i).min(pt.at(i)));
+            self.max.set(i, self.max.at(i).max(pt.at(i)));
+        }
+    }
+
+    pub fn merged(&self, other: &Bounds) -> Bounds {
+        let mut b = *self;
+        b.add_point(other.min);
+        b.add_point(other.max);
+        b
+    }
+
+    pub fn contains_point(&self, pt: Tuple) -> bool {
+        (0..3).all(|i| self.min.at(i) <= pt.at(i) && pt.at(i) <= self.max.at(i))
+    }
+
+    pub fn centroid(&self) -> Tuple {
+        let mut c = point(0.0, 0.0, 0.0);
+        for i in 0..3 {
+            c.set(i, (self.min.at(i) + self.max.at(i)) / 2.0);
+        }
+        c
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let d = self.max - self.min;
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
+    /// Returns the box bounding this box after transformation. Transforming
+    /// an unbounded box may mix the infinite extents with the finite ones, so
+    /// we give up and return an infinite box in that case.
+    pub fn transformed(&self, transform: Matrix) -> Bounds {
+        if self.is_empty() {
+            return *self;
+        }
+
+        if !self.is_finite() {
+            return Bounds::infinite();
+        }
+
+        let mut b = Bounds::empty();
+        for x in [self.min.x(), self.max.x()].iter() {
+            for y in [self.min.y(), self.max.y()].iter() {
+                for z in [self.min.z(), self.max.z()].iter() {
+                    b.add_point(transform * point(*x, *y, *z));
+                }
+            }
+        }
+        b
+    }
+
+    /// Returns the box grown by the margin in every direction
+    pub fn padded(&self, margin: f64) -> Bounds {
+        let mut b = *self;
+        for i in 0..3 {
+            b.min.set(i, self.min.at(i) - margin);
+            b.max.set(i, self.max.at(i) + margin);
+        }
+        b
+    }
+
+    /// Checks whether the line of the ray crosses the box using the slab
+    /// method: https://en.wikipedia.org/wiki/Slab_method
+    ///
+    /// The boxes behind the origin of the ray count as well because the
+    /// negative intersections matter when computing the refraction indices.
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        self.slab_range(ray).is_some()
+    }
+
+    /// Checks whether the ray crosses the box at a distance in the range of
+    /// (0, max_t)
+    pub fn intersects_within(&self, ray: &Ray, max_t: f64) -> bool {
+        matches!(self.slab_range(ray), Some((tmin, tmax)) if tmax > 0.0 && tmin < max_t)
+    }
+
+    /// Returns the distances at which the line of the ray enters and leaves
+    /// the box
+    fn slab_range(&self, ray: &Ray) -> Option<(f64, f64)> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut tmin = f64::NEG_INFINITY;
+        let mut tmax = f64::INFINITY;
+        for i in 0..3 {
+            let origin = ray.origin().at(i);
+            let direction = ray.direction().at(i);
+
+            // The ray is parallel to the slab, so it either is within it or
+            // misses the box altogether
+            if direction == 0.0 {
+                if origin < self.min.at(i) || origin > self.max.at(i) {
+                    return None;
+                }
+                continue;
+            }
+
+            let mut t0 = (self.min.at(i) - origin) / direction;
+            let mut t1 = (self.max.at(i) - origin) / direction;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            tmin = tmin.max(t0);
+            tmax = tmax.min(t1);
+            if tmin > tmax {
+                return None;
+            }
+        }
+        Some((tmin, tmax))
+    }
+}
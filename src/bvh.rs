@@ -0,0 +1,254 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::bounds::Bounds;
+use crate::constants::EPSILON;
+use crate::intersections::Intersection;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::shape::Shape;
+
+/// Maximum number of shapes in a leaf that we never try to split
+const BVH_MIN_SPLIT: usize = 2;
+
+/// Cost of visiting a node relative to intersecting a shape
+const BVH_TRAVERSAL_COST: f64 = 0.125;
+
+struct BvhNode {
+    bounds: Bounds,
+    // Leaves refer to the range of `indices`; interior nodes have the left
+    // child right after themselves and the right child at `right`
+    start: usize,
+    count: usize,
+    right: usize,
+}
+
+struct BvhItem {
+    index: usize,
+    bounds: Bounds,
+    centroid: [f64; 3],
+}
+
+/// Bounding volume hierarchy over a list of shapes
+///
+/// The hierarchy stores the indices of the shapes, so it needs to be rebuilt
+/// whenever the shapes are transformed or removed. The shapes appended after
+/// the hierarchy has been built are checked one by one, and if some of the
+/// shapes have been removed, all of them are.
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    indices: Vec<usize>,
+    unbounded: Vec<usize>,
+    count: usize,
+}
+
+impl Bvh {
+    pub fn new(shapes: &[Box<dyn Shape>]) -> Bvh {
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            indices: Vec::new(),
+            unbounded: Vec::new(),
+            count: shapes.len(),
+        };
+
+        // Planes and the like would end up in every node, so we keep them
+        // aside
+        let mut items = Vec::new();
+        for (index, s) in shapes.iter().enumerate() {
+            let bounds = s.bounds();
+            if !bounds.is_finite() {
+                bvh.unbounded.push(index);
+                continue;
+            }
+            let c = bounds.centroid();
+            items.push(BvhItem {
+                index,
+                bounds,
+                centroid: [c.x(), c.y(), c.z()],
+            });
+        }
+
+        if !items.is_empty() {
+            bvh.build(&mut items);
+        }
+        bvh
+    }
+
+    /// Number of the nodes in the hierarchy
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn build(&mut self, items: &mut [BvhItem]) -> usize {
+        let bounds = items
+            .iter()
+            .fold(Bounds::empty(), |acc, it| acc.merged(&it.bounds));
+        let node_idx = self.nodes.len();
+
+        // The shapes may report hits slightly outside of their bounds due to
+        // floating-point inaccuracies, so we give them some room
+        self.nodes.push(BvhNode {
+            bounds: bounds.padded(EPSILON),
+            start: self.indices.len(),
+            count: 0,
+            right: 0,
+        });
+
+        match self.find_split(items, &bounds) {
+            None => {
+                self.nodes[node_idx].count = items.len();
+                self.indices.extend(items.iter().map(|it| it.index));
+            }
+            Some((axis, split)) => {
+                sort_by_axis(items, axis);
+                let (left, right) = items.split_at_mut(split);
+                self.build(left);
+                let right_idx = self.build(right);
+                self.nodes[node_idx].right = right_idx;
+            }
+        }
+        node_idx
+    }
+
+    /// Finds the axis and the position to split the items at minimizing the
+    /// surface area heuristic:
+    /// https://en.wikipedia.org/wiki/Bounding_volume_hierarchy#Construction
+    fn find_split(&self, items: &mut [BvhItem], bounds: &Bounds) -> Option<(usize, usize)> {
+        let n = items.len();
+        if n <= BVH_MIN_SPLIT {
+            return None;
+        }
+
+        let area = bounds.surface_area();
+        if area == 0.0 {
+            // All the shapes are degenerate and in the same place, so there's
+            // nothing to gain by splitting the space
+            return None;
+        }
+
+        let mut best: Option<(f64, usize, usize)> = None;
+        let mut right_areas = vec![0.0; n];
+        for axis in 0..3 {
+            sort_by_axis(items, axis);
+
+            let mut acc = Bounds::empty();
+            for i in (1..n).rev() {
+                acc = acc.merged(&items[i].bounds);
+                right_areas[i] = acc.surface_area();
+            }
+
+            let mut acc = Bounds::empty();
+            for i in 1..n {
+                acc = acc.merged(&items[i - 1].bounds);
+                let cost = BVH_TRAVERSAL_COST
+                    + (acc.surface_area() * i as f64 + right_areas[i] * (n - i) as f64) / area;
+                if best.is_none() || cost < best.unwrap().0 {
+                    best = Some((cost, axis, i));
+                }
+            }
+        }
+
+        match best {
+            Some((cost, axis, split)) if cost < n as f64 => Some((axis, split)),
+            _ => None,
+        }
+    }
+
+    /// Returns the intersections of the ray with the shapes in the order of
+    /// the shapes, so that the result is exactly the same as if we
+    /// intersected the shapes one by one
+    pub fn intersect<'a>(&self, shapes: &'a [Box<dyn Shape>], ray: &Ray) -> Vec<Intersection<'a>> {
+        // The indices don't match the shapes anymore
+        if shapes.len() < self.count {
+            return shapes.iter().flat_map(|s| s.intersect(ray)).collect();
+        }
+
+        let mut hits = Vec::new();
+        let mut check = |index: usize| {
+            let xs = shapes[index].intersect(ray);
+            if !xs.is_empty() {
+                hits.push((index, xs));
+            }
+        };
+
+        for index in self.unbounded.iter() {
+            check(*index);
+        }
+
+        for index in self.count..shapes.len() {
+            check(index);
+        }
+
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            if !node.bounds.intersects(ray) {
+                continue;
+            }
+
+            if node.count != 0 {
+                for index in self.indices[node.start..node.start + node.count].iter() {
+                    check(*index);
+                }
+                continue;
+            }
+
+            stack.push(node.right);
+            stack.push(node_idx + 1);
+        }
+
+        hits.sort_by_key(|h| h.0);
+        hits.into_iter().flat_map(|h| h.1).collect()
+    }
+
+    /// Checks whether the ray hits any of the shapes at a distance in the
+    /// range of (0, max_t) with a qualifying material; it stops at the first
+    /// such hit and skips the nodes beyond the distance
+    pub fn any_hit(
+        &self,
+        shapes: &[Box<dyn Shape>],
+        ray: &Ray,
+        max_t: f64,
+        qualifies: &dyn Fn(&Material) -> bool,
+    ) -> bool {
+        if shapes.len() < self.count {
+            return shapes.iter().any(|s| s.any_hit(ray, max_t, qualifies));
+        }
+
+        let check = |index: &usize| shapes[*index].any_hit(ray, max_t, qualifies);
+        if self.unbounded.iter().any(check) || (self.count..shapes.len()).any(|i| check(&i)) {
+            return true;
+        }
+        !self.nodes.is_empty() && self.any_hit_node(0, shapes, ray, max_t, qualifies)
+    }
+
+    fn any_hit_node(
+        &self,
+        node_idx: usize,
+        shapes: &[Box<dyn Shape>],
+        ray: &Ray,
+        max_t: f64,
+        qualifies: &dyn Fn(&Material) -> bool,
+    ) -> bool {
+        let node = &self.nodes[node_idx];
+        if !node.bounds.intersects_within(ray, max_t) {
+            return false;
+        }
+
+        if node.count != 0 {
+            return self.indices[node.start..node.start + node.count]
+                .iter()
+                .any(|index| shapes[*index].any_hit(ray, max_t, qualifies));
+        }
+
+        self.any_hit_node(node_idx + 1, shapes, ray, max_t, qualifies)
+            || self.any_hit_node(node.right, shapes, ray, max_t, qualifies)
+    }
+}
+
+fn sort_by_axis(items: &mut [BvhItem], axis: usize) {
+    items.sort_by(|a, b| a.centroid[axis].partial_cmp(&b.centroid[axis]).unwrap());
+}
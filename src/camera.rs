@@ -2,20 +2,137 @@
 // Licensed under the MIT license, see the LICENSE file for details.
 
 use crate::canvas::Canvas;
+use crate::constants::EPSILON;
 use crate::matrix::Matrix;
 use crate::ray::Ray;
-use crate::tuple::point;
+use crate::rng::Rng;
+use crate::sampling::{concentric_disk, AdaptiveSampling, ReconstructionFilter, SamplingPattern};
+use crate::tuple::{color, point, vector, Tuple};
 use crate::world::World;
 
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+pub const DEFAULT_TILE_SIZE: usize = 32;
+pub const DEFAULT_MAX_DEPTH: u8 = 5;
+
+/// A rectangular block of pixels rendered as a single unit of work
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Tile {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+/// A rectangle of the image's pixels given by its top-left corner and its
+/// size
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Region {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Region {
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Tells whether the region lies entirely within an image of the given
+    /// size
+    pub fn fits(&self, width: usize, height: usize) -> bool {
+        let right = self.x.checked_add(self.width);
+        let bottom = self.y.checked_add(self.height);
+        right.is_some_and(|r| r <= width) && bottom.is_some_and(|b| b <= height)
+    }
+
+    /// Returns the part of the region lying within an image of the given
+    /// size, which is empty when the region is entirely outside of it
+    pub fn clipped(&self, width: usize, height: usize) -> Region {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        Region {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        }
+    }
+}
+
+/// Statistics of a single render
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct RenderStats {
+    pub pixels: usize,
+    /// Number of the rays cast from the camera, not counting the reflected,
+    /// refracted and shadow rays
+    pub rays: usize,
+}
+
+impl RenderStats {
+    pub fn rays_per_pixel(&self) -> f64 {
+        if self.pixels == 0 {
+            return 0.0;
+        }
+        self.rays as f64 / self.pixels as f64
+    }
+}
+
+/// How the points of the image map to the rays cast into the scene
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Projection {
+    /// The rays spread out from a single point through a flat image; the
+    /// field of view spans the longer side of the image
+    #[default]
+    Perspective,
+    /// The rays run in parallel, so the objects keep their size regardless
+    /// of the distance; the longer side of the image spans the given width
+    /// in the world's units
+    Orthographic { width: f64 },
+    /// The distance from the center of the image is proportional to the
+    /// angle off the view direction, with the field of view spanning the
+    /// longer side of the image; the points outside of the field of view
+    /// stay black, leaving a circle on a square image
+    Fisheye,
+    /// The full sphere around the camera, longitude running along the width
+    /// and latitude along the height with the view direction in the center;
+    /// the field of view is ignored and the image is best twice as wide as
+    /// it is tall
+    Equirectangular,
+}
+
+#[derive(Clone)]
 pub struct Camera {
     hres: usize,
     vres: usize,
     fov: f64,
+    projection: Projection,
     transform: Matrix,
     transform_inv: Matrix,
     pixel_size: f64,
+    pixel_height: f64,
     half_width: f64,
     half_height: f64,
+    threads: usize,
+    tile_size: usize,
+    samples: usize,
+    sampling: SamplingPattern,
+    filter: ReconstructionFilter,
+    seed: u64,
+    adaptive: Option<AdaptiveSampling>,
+    aperture: f64,
+    focal_distance: f64,
+    shutter: (f64, f64),
+    shift: (f64, f64),
+    max_depth: u8,
 }
 
 impl Camera {
@@ -24,26 +141,66 @@ impl Camera {
             hres,
             vres,
             fov,
+            projection: Projection::Perspective,
             transform: Matrix::one(),
             transform_inv: Matrix::one(),
             pixel_size: 0.0,
+            pixel_height: 0.0,
             half_width: 0.0,
             half_height: 0.0,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            tile_size: DEFAULT_TILE_SIZE,
+            samples: 1,
+            sampling: SamplingPattern::Regular,
+            filter: ReconstructionFilter::Box,
+            seed: 0,
+            adaptive: None,
+            aperture: 0.0,
+            focal_distance: 1.0,
+            shutter: (0.0, 0.0),
+            shift: (0.0, 0.0),
+            max_depth: DEFAULT_MAX_DEPTH,
         };
+        c.compute_pixel_size();
+        c
+    }
 
-        let aspect = hres as f64 / vres as f64;
-        let half_view = (fov / 2.0).tan();
+    fn compute_pixel_size(&mut self) {
+        // The extents are in the units of the image plane: the tangent of
+        // the angle for the perspective, the world's units for the
+        // orthographic projection, and the angle for the panoramic ones
+        if self.projection == Projection::Equirectangular {
+            self.half_width = PI;
+            self.half_height = PI / 2.0;
+            self.pixel_size = 2.0 * PI / self.hres as f64;
+            self.pixel_height = PI / self.vres as f64;
+            return;
+        }
+
+        let aspect = self.hres as f64 / self.vres as f64;
+        let half_view = match self.projection {
+            Projection::Orthographic { width } => width / 2.0,
+            Projection::Fisheye => self.fov / 2.0,
+            _ => (self.fov / 2.0).tan(),
+        };
 
         if aspect >= 1.0 {
-            c.half_width = half_view;
-            c.half_height = half_view / aspect;
+            self.half_width = half_view;
+            self.half_height = half_view / aspect;
         } else {
-            c.half_width = half_view * aspect;
-            c.half_height = half_view;
+            self.half_width = half_view * aspect;
+            self.half_height = half_view;
         }
-        c.pixel_size = (2.0 * c.half_width) / c.hres as f64;
+        self.pixel_size = (2.0 * self.half_width) / self.hres as f64;
+        self.pixel_height = self.pixel_size;
+    }
 
-        c
+    /// Changes the size of the image keeping the field of view and the
+    /// transform
+    pub fn set_resolution(&mut self, hres: usize, vres: usize) {
+        self.hres = hres;
+        self.vres = vres;
+        self.compute_pixel_size();
     }
 
     pub fn hres(&self) -> usize {
@@ -58,43 +215,609 @@ impl Camera {
         self.fov
     }
 
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.compute_pixel_size();
+    }
+
     pub fn set_transform(&mut self, transform: Matrix) {
         self.transform = transform;
         self.transform_inv = self.transform.inverted();
     }
 
+    pub fn transform(&self) -> &Matrix {
+        &self.transform
+    }
+
     pub fn pixel_size(&self) -> f64 {
         self.pixel_size
     }
 
+    /// Number of threads rendering the tiles; defaults to the number of
+    /// available CPUs
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    pub fn tile_size(&self) -> usize {
+        self.tile_size
+    }
+
+    pub fn set_tile_size(&mut self, tile_size: usize) {
+        self.tile_size = tile_size.max(1);
+    }
+
+    /// Number of samples along each side of a pixel, so that every pixel
+    /// combines `samples * samples` rays
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn set_samples(&mut self, samples: usize) {
+        self.samples = samples.max(1);
+    }
+
+    pub fn sampling(&self) -> SamplingPattern {
+        self.sampling
+    }
+
+    pub fn set_sampling(&mut self, sampling: SamplingPattern) {
+        self.sampling = sampling;
+    }
+
+    /// Filter weighing the samples when they are combined into a pixel
+    pub fn filter(&self) -> ReconstructionFilter {
+        self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: ReconstructionFilter) {
+        self.filter = filter;
+    }
+
+    /// Seed of the random samples; every pixel draws from its own stream, so
+    /// the same seed gives the same image regardless of the threads and tiles
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Adaptive anti-aliasing; when enabled, it replaces the sampling
+    /// settings and the reconstruction filter
+    pub fn adaptive(&self) -> Option<AdaptiveSampling> {
+        self.adaptive
+    }
+
+    pub fn set_adaptive(&mut self, adaptive: Option<AdaptiveSampling>) {
+        self.adaptive = adaptive;
+    }
+
+    /// Radius of the lens; zero makes a pinhole camera with everything in
+    /// focus, otherwise only the objects at the focal distance are sharp;
+    /// the panoramic projections are always pinholes
+    pub fn aperture(&self) -> f64 {
+        self.aperture
+    }
+
+    pub fn set_aperture(&mut self, aperture: f64) {
+        self.aperture = aperture.max(0.0);
+    }
+
+    /// Distance from the camera to the plane in focus
+    pub fn focal_distance(&self) -> f64 {
+        self.focal_distance
+    }
+
+    pub fn set_focal_distance(&mut self, focal_distance: f64) {
+        self.focal_distance = focal_distance.max(EPSILON);
+    }
+
+    /// The times at which the shutter opens and closes; the rays are spread
+    /// evenly over the interval, smearing the moving shapes whose keyframes
+    /// lie at the times 0 and 1
+    pub fn shutter(&self) -> (f64, f64) {
+        self.shutter
+    }
+
+    pub fn set_shutter(&mut self, open: f64, close: f64) {
+        self.shutter = (open, close.max(open));
+    }
+
+    /// Lens shift of the image plane in pixels
+    pub fn shift(&self) -> (f64, f64) {
+        self.shift
+    }
+
+    /// Slides the image plane by the given number of pixels without turning
+    /// the camera, like a shift lens; the positive values move the view
+    /// right and down
+    pub fn set_shift(&mut self, dx: f64, dy: f64) {
+        self.shift = (dx, dy);
+    }
+
+    /// Maximum recursion depth of the reflected and refracted rays
+    pub fn max_depth(&self) -> u8 {
+        self.max_depth
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: u8) {
+        self.max_depth = max_depth;
+    }
+
     pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
         // we aix at the middle of the pixel
-        let xoffset = (px as f64 + 0.5) * self.pixel_size;
-        let yoffset = (py as f64 + 0.5) * self.pixel_size;
+        self.ray_for_subpixel(px, py, 0.5, 0.5)
+    }
+
+    /// Casts a ray through a point given by the offsets from the pixel's
+    /// top-left corner; the offsets in the range of [0, 1) stay within the
+    /// pixel, the ones outside of it reach into the neighbors
+    pub fn ray_for_subpixel(&self, px: usize, py: usize, dx: f64, dy: f64) -> Ray {
+        let (origin, direction) = self.local_ray(px, py, dx, dy);
+        Ray::new(
+            self.transform_inv * origin,
+            (self.transform_inv * direction).normalized(),
+        )
+    }
+
+    /// Casts a ray like `ray_for_subpixel`, but from a point on the lens
+    /// given by its coordinates within a unit square; all the rays through
+    /// a pixel meet at the focal distance
+    pub fn ray_through_lens(
+        &self,
+        px: usize,
+        py: usize,
+        dx: f64,
+        dy: f64,
+        lens: (f64, f64),
+    ) -> Ray {
+        if self.pinhole() {
+            return self.ray_for_subpixel(px, py, dx, dy);
+        }
+
+        // The ray through the center of the lens goes straight, so the focal
+        // point lies along it at the focal distance from the lens
+        let (center, direction) = self.local_ray(px, py, dx, dy);
+        let focus = self.transform_inv * (center + direction * self.focal_distance);
+        let (lx, ly) = concentric_disk(lens.0, lens.1);
+        let offset = vector(lx * self.aperture, ly * self.aperture, 0.0);
+        let origin = self.transform_inv * (center + offset);
+        Ray::new(origin, (focus - origin).normalized())
+    }
+
+    /// Tells whether a point of the image, given like for
+    /// `ray_for_subpixel`, sees the scene at all; only the fisheye leaves
+    /// some of the image out
+    pub fn covers(&self, px: usize, py: usize, dx: f64, dy: f64) -> bool {
+        if self.projection != Projection::Fisheye {
+            return true;
+        }
+        let (x, y) = self.image_point(px, py, dx, dy);
+        x.hypot(y) <= self.fov / 2.0
+    }
 
+    fn pinhole(&self) -> bool {
+        match self.projection {
+            Projection::Perspective | Projection::Orthographic { .. } => self.aperture == 0.0,
+            _ => true,
+        }
+    }
+
+    /// Returns the coordinates of a point on the image plane with the
+    /// center of the image at the origin
+    fn image_point(&self, px: usize, py: usize, dx: f64, dy: f64) -> (f64, f64) {
         // camera looks towards -z, so +x is on the left
-        let x_w = self.half_width - xoffset;
-        let y_w = self.half_height - yoffset;
+        let x = self.half_width - (px as f64 + dx + self.shift.0) * self.pixel_size;
+        let y = self.half_height - (py as f64 + dy + self.shift.1) * self.pixel_height;
+        (x, y)
+    }
+
+    /// Returns the origin and the direction of a ray in the camera's frame
+    /// of reference; the planar projections return the directions reaching
+    /// the plane at z = -1, so that the focal plane lies at the focal
+    /// distance along them
+    fn local_ray(&self, px: usize, py: usize, dx: f64, dy: f64) -> (Tuple, Tuple) {
+        let (x, y) = self.image_point(px, py, dx, dy);
+        match self.projection {
+            Projection::Perspective => (point(0.0, 0.0, 0.0), vector(x, y, -1.0)),
+            Projection::Orthographic { .. } => (point(x, y, 0.0), vector(0.0, 0.0, -1.0)),
+            Projection::Fisheye => {
+                let theta = x.hypot(y);
+                let phi = y.atan2(x);
+                let direction = vector(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    -theta.cos(),
+                );
+                (point(0.0, 0.0, 0.0), direction)
+            }
+            Projection::Equirectangular => {
+                let direction = vector(x.sin() * y.cos(), y.sin(), -x.cos() * y.cos());
+                (point(0.0, 0.0, 0.0), direction)
+            }
+        }
+    }
 
-        // transform the canvas point and the origin; canvas is at z = -1
-        let pixel = self.transform_inv * point(x_w, y_w, -1.0);
-        let origin = self.transform_inv * point(0.0, 0.0, 0.0);
-        let direction = (pixel - origin).normalized();
+    fn still(&self) -> bool {
+        self.shutter.0 == self.shutter.1
+    }
 
-        Ray::new(origin, direction)
+    /// Returns the time at the given fraction of the shutter interval
+    fn shutter_time(&self, fraction: f64) -> f64 {
+        self.shutter.0 + (self.shutter.1 - self.shutter.0) * fraction
     }
 
+    /// Returns the color seen through a point of the image, black where the
+    /// projection doesn't cover it
+    fn trace(
+        &self,
+        world: &World,
+        px: usize,
+        py: usize,
+        dx: f64,
+        dy: f64,
+        exposure: &Exposure,
+    ) -> Tuple {
+        if !self.covers(px, py, dx, dy) {
+            return color(0.0, 0.0, 0.0);
+        }
+        let ray = self
+            .ray_through_lens(px, py, dx, dy, exposure.lens)
+            .with_time(exposure.time);
+        world.color_at(&ray, self.max_depth)
+    }
+
+    /// Renders the world; every pixel is computed independently of the
+    /// others, so the result is the same regardless of the number of threads
     pub fn render(&self, world: &World) -> Canvas {
-        let mut img = Canvas::new(self.hres, self.vres);
+        self.render_with_progress(world, &|_, _| {})
+    }
+
+    /// Renders the world calling the progress callback with the number of
+    /// the finished tiles and the total number of tiles whenever a tile is
+    /// done; the callback is called from the rendering threads
+    pub fn render_with_progress(
+        &self,
+        world: &World,
+        progress: &(dyn Fn(usize, usize) + Sync),
+    ) -> Canvas {
+        self.render_with_stats(world, progress).0
+    }
+
+    /// Renders the world like `render_with_progress` and counts the rays
+    /// cast from the camera
+    pub fn render_with_stats(
+        &self,
+        world: &World,
+        progress: &(dyn Fn(usize, usize) + Sync),
+    ) -> (Canvas, RenderStats) {
+        let region = Region::new(0, 0, self.hres, self.vres);
+        self.render_region_with_stats(world, &region, progress)
+    }
+
+    /// Renders only the given region of the image, clipped to the image,
+    /// into a canvas of its size; the pixels come out the same as in the
+    /// full image
+    pub fn render_region(&self, world: &World, region: &Region) -> Canvas {
+        self.render_region_with_stats(world, region, &|_, _| {}).0
+    }
+
+    /// Renders the given region of the image into a canvas of the image's
+    /// size, leaving the pixels outside of the region untouched; the pixels
+    /// that don't fit a smaller canvas are dropped
+    pub fn render_region_into(&self, world: &World, region: &Region, img: &mut Canvas) {
+        let region = region
+            .clipped(self.hres, self.vres)
+            .clipped(img.width(), img.height());
+        img.paste(&self.render_region(world, &region), region.x, region.y);
+    }
+
+    /// Renders the given region of the image like `render_with_stats`; the
+    /// progress callback counts only the tiles of the region, and the parts
+    /// of the region outside of the image are clipped
+    pub fn render_region_with_stats(
+        &self,
+        world: &World,
+        region: &Region,
+        progress: &(dyn Fn(usize, usize) + Sync),
+    ) -> (Canvas, RenderStats) {
+        let region = &region.clipped(self.hres, self.vres);
+        let mut img = Canvas::new(region.width, region.height);
+        let mut stats = RenderStats {
+            pixels: region.width * region.height,
+            rays: 0,
+        };
+        let tiles = self.tiles(region);
+
+        if self.threads == 1 {
+            for (i, tile) in tiles.iter().enumerate() {
+                let (colors, rays) = self.render_tile(world, tile);
+                write_tile(&mut img, region, tile, &colors);
+                stats.rays += rays;
+                progress(i + 1, tiles.len());
+            }
+            return (img, stats);
+        }
+
+        // The threads pick the tiles from a shared counter and send the
+        // rendered pixels back when they run out of work
+        let next_tile = AtomicUsize::new(0);
+        let done_tiles = AtomicUsize::new(0);
+        thread::scope(|s| {
+            let mut handles = Vec::new();
+            for _ in 0..self.threads.min(tiles.len()) {
+                handles.push(s.spawn(|| {
+                    let mut rendered = Vec::new();
+                    loop {
+                        let idx = next_tile.fetch_add(1, Ordering::Relaxed);
+                        if idx >= tiles.len() {
+                            break;
+                        }
+                        rendered.push((idx, self.render_tile(world, &tiles[idx])));
+                        progress(done_tiles.fetch_add(1, Ordering::Relaxed) + 1, tiles.len());
+                    }
+                    rendered
+                }));
+            }
+
+            for handle in handles {
+                for (idx, (colors, rays)) in handle.join().unwrap() {
+                    write_tile(&mut img, region, &tiles[idx], &colors);
+                    stats.rays += rays;
+                }
+            }
+        });
+
+        (img, stats)
+    }
+
+    fn tiles(&self, region: &Region) -> Vec<Tile> {
+        let (right, bottom) = (region.x + region.width, region.y + region.height);
+        let mut tiles = Vec::new();
+        for y in (region.y..bottom).step_by(self.tile_size) {
+            for x in (region.x..right).step_by(self.tile_size) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: self.tile_size.min(right - x),
+                    height: self.tile_size.min(bottom - y),
+                });
+            }
+        }
+        tiles
+    }
+
+    /// Returns the colors of the tile's pixels row by row and the number of
+    /// the rays cast
+    fn render_tile(&self, world: &World, tile: &Tile) -> (Vec<Tuple>, usize) {
+        if let Some(adaptive) = self.adaptive {
+            return self.render_tile_adaptive(world, tile, &adaptive);
+        }
+
+        let mut colors = Vec::with_capacity(tile.width * tile.height);
+        let mut rays = 0;
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                let (color, pixel_rays) = self.render_pixel(world, x, y);
+                colors.push(color);
+                rays += pixel_rays;
+            }
+        }
+        (colors, rays)
+    }
+
+    fn render_tile_adaptive(
+        &self,
+        world: &World,
+        tile: &Tile,
+        adaptive: &AdaptiveSampling,
+    ) -> (Vec<Tuple>, usize) {
+        // The neighboring pixels of the tile share the corners
+        let stride = tile.width + 1;
+        let mut corners = Vec::with_capacity(stride * (tile.height + 1));
+        for y in tile.y..=tile.y + tile.height {
+            for x in tile.x..=tile.x + tile.width {
+                corners.push(self.adaptive_trace(world, x, y, 0.0, 0.0));
+            }
+        }
+
+        let mut colors = Vec::with_capacity(tile.width * tile.height);
+        let mut rays = corners.len();
+        for j in 0..tile.height {
+            for i in 0..tile.width {
+                let c = [
+                    corners[j * stride + i],
+                    corners[j * stride + i + 1],
+                    corners[(j + 1) * stride + i],
+                    corners[(j + 1) * stride + i + 1],
+                ];
+                let sub = Subpixel {
+                    px: tile.x + i,
+                    py: tile.y + j,
+                    dx: 0.0,
+                    dy: 0.0,
+                    size: 1.0,
+                };
+                colors.push(self.subdivide(world, adaptive, &sub, c, 0, &mut rays));
+            }
+        }
+        (colors, rays)
+    }
+
+    /// Traces a ray for the adaptive sampling; the point on the lens and the
+    /// time are drawn from a stream keyed by the position of the sample, so
+    /// that the pixel corners on the edges of the tiles, traced by both of
+    /// the tiles, come out the same regardless of the tiling
+    ///
+    /// Only the corners of the pixels are traced once and shared; the
+    /// subdivisions trace the points on the edges between the neighboring
+    /// squares separately for each of the squares
+    fn adaptive_trace(&self, world: &World, px: usize, py: usize, dx: f64, dy: f64) -> Tuple {
+        let mut exposure = Exposure {
+            lens: (0.5, 0.5),
+            time: self.shutter.0,
+        };
+        if !self.pinhole() || !self.still() {
+            let keys = [px as u64, py as u64, dx.to_bits(), dy.to_bits()];
+            let mut rng = Rng::from_keys(self.seed, &keys);
+            exposure.lens = (rng.next_f64(), rng.next_f64());
+            exposure.time = self.shutter_time(rng.next_f64());
+        }
+        self.trace(world, px, py, dx, dy, &exposure)
+    }
+
+    /// Returns the color of a square within a pixel given the colors at its
+    /// corners: top-left, top-right, bottom-left and bottom-right
+    fn subdivide(
+        &self,
+        world: &World,
+        adaptive: &AdaptiveSampling,
+        sub: &Subpixel,
+        corners: [Tuple; 4],
+        depth: u8,
+        rays: &mut usize,
+    ) -> Tuple {
+        let average = (corners[0] + corners[1] + corners[2] + corners[3]) / 4.0;
+        if depth >= adaptive.max_depth || contrast(&corners) <= adaptive.threshold {
+            return average;
+        }
+
+        let half = sub.size / 2.0;
+        let mut cast = |dx: f64, dy: f64| {
+            *rays += 1;
+            self.adaptive_trace(world, sub.px, sub.py, sub.dx + dx, sub.dy + dy)
+        };
+        let top = cast(half, 0.0);
+        let left = cast(0.0, half);
+        let center = cast(half, half);
+        let right = cast(sub.size, half);
+        let bottom = cast(half, sub.size);
+
+        let quadrants = [
+            (0.0, 0.0, [corners[0], top, left, center]),
+            (half, 0.0, [top, corners[1], center, right]),
+            (0.0, half, [left, center, corners[2], bottom]),
+            (half, half, [center, right, bottom, corners[3]]),
+        ];
+        let mut sum = color(0.0, 0.0, 0.0);
+        for (dx, dy, c) in quadrants {
+            let quadrant = Subpixel {
+                dx: sub.dx + dx,
+                dy: sub.dy + dy,
+                size: half,
+                ..*sub
+            };
+            sum = sum + self.subdivide(world, adaptive, &quadrant, c, depth + 1, rays);
+        }
+        sum / 4.0
+    }
+
+    fn render_pixel(&self, world: &World, x: usize, y: usize) -> (Tuple, usize) {
+        let pinhole = self.pinhole();
+        let still = self.still();
+        let center = Exposure {
+            lens: (0.5, 0.5),
+            time: self.shutter.0,
+        };
+        if self.samples == 1 && self.sampling == SamplingPattern::Regular && pinhole && still {
+            return (self.trace(world, x, y, 0.5, 0.5, &center), 1);
+        }
+
+        // The samples cover the whole support of the filter centered at the
+        // middle of the pixel
+        let mut rng = Rng::from_keys(self.seed, &[x as u64, y as u64]);
+        let radius = self.filter.radius();
+        let mut sum = color(0.0, 0.0, 0.0);
+        let mut average = color(0.0, 0.0, 0.0);
+        let mut total_weight = 0.0;
+        let samples = self.sampling.samples(self.samples, &mut rng);
+
+        // The lens and the shutter interval are always sampled at random,
+        // but stratified, with the strata paired randomly with the ones of
+        // the pixel
+        let mut exposures = vec![center; samples.len()];
+        if !pinhole {
+            let mut lens = SamplingPattern::Jittered.samples(self.samples, &mut rng);
+            rng.shuffle(&mut lens);
+            for (e, l) in exposures.iter_mut().zip(lens) {
+                e.lens = l;
+            }
+        }
+        if !still {
+            let n = exposures.len() as f64;
+            let mut times: Vec<f64> = (0..exposures.len())
+                .map(|i| self.shutter_time((i as f64 + rng.next_f64()) / n))
+                .collect();
+            rng.shuffle(&mut times);
+            for (e, t) in exposures.iter_mut().zip(times) {
+                e.time = t;
+            }
+        }
+
+        for ((sx, sy), e) in samples.iter().zip(exposures.iter()) {
+            let dx = (sx - 0.5) * 2.0 * radius;
+            let dy = (sy - 0.5) * 2.0 * radius;
+            let c = self.trace(world, x, y, dx + 0.5, dy + 0.5, e);
+            let weight = self.filter.weight(dx, dy);
+            sum = sum + c * weight;
+            average = average + c;
+            total_weight += weight;
+        }
+
+        // The weights of the jittered samples may cancel out
+        if total_weight.abs() < EPSILON {
+            return (average / samples.len() as f64, samples.len());
+        }
+        (sum / total_weight, samples.len())
+    }
+}
 
-        for x in 0..self.hres {
-            for y in 0..self.vres {
-                let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at(&ray, 5);
-                img.set(x, y, &color);
+/// Where on the lens and when within the shutter interval a camera ray
+/// starts
+#[derive(Debug, Copy, Clone)]
+struct Exposure {
+    lens: (f64, f64),
+    time: f64,
+}
+
+/// A square within a pixel given by the offset of its top-left corner and
+/// the length of its side, both relative to the pixel's size
+#[derive(Debug, Copy, Clone)]
+struct Subpixel {
+    px: usize,
+    py: usize,
+    dx: f64,
+    dy: f64,
+    size: f64,
+}
+
+/// Returns the largest difference between the colors in any channel
+fn contrast(colors: &[Tuple]) -> f64 {
+    let mut max: f64 = 0.0;
+    for (i, a) in colors.iter().enumerate() {
+        for b in colors[i + 1..].iter() {
+            for c in 0..3 {
+                max = max.max((a.at(c) - b.at(c)).abs());
             }
         }
+    }
+    max
+}
 
-        img
+/// Writes the tile's pixels into the image of the rendered region
+fn write_tile(img: &mut Canvas, region: &Region, tile: &Tile, colors: &[Tuple]) {
+    let (x, y) = (tile.x - region.x, tile.y - region.y);
+    for (i, color) in colors.iter().enumerate() {
+        img.set(x + i % tile.width, y + i / tile.width, color);
     }
 }
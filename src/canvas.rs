@@ -1,9 +1,48 @@
 // Copyright 2022 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
+use crate::png::{png_decode, png_encode, PngBitDepth, PNG_SIGNATURE};
 use crate::tuple::{color, Tuple};
 
+use std::fmt;
 use std::fs;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(std::io::Error),
+    /// The data does not start with a known signature
+    BadMagic,
+    /// Malformed or invalid header, like the wrong dimensions
+    BadHeader(String),
+    /// The data ends before all the pixels have been read
+    Truncated,
+    /// Malformed pixel data
+    Corrupt(String),
+    /// A valid feature of the format that we don't handle
+    Unsupported(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageError::Io(err) => write!(f, "{}", err),
+            ImageError::BadMagic => write!(f, "unknown image format"),
+            ImageError::BadHeader(message) => write!(f, "bad header: {}", message),
+            ImageError::Truncated => write!(f, "truncated image data"),
+            ImageError::Corrupt(message) => write!(f, "corrupt image data: {}", message),
+            ImageError::Unsupported(message) => write!(f, "unsupported: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<std::io::Error> for ImageError {
+    fn from(err: std::io::Error) -> ImageError {
+        ImageError::Io(err)
+    }
+}
 
 pub struct Canvas {
     width: usize,
@@ -57,6 +96,18 @@ impl Canvas {
         self.data[x][y]
     }
 
+    /// Copies another canvas over this one with its top-left corner at the
+    /// given pixel; the pixels falling outside of this canvas are dropped
+    pub fn paste(&mut self, other: &Canvas, x: usize, y: usize) {
+        let width = other.width.min(self.width.saturating_sub(x));
+        let height = other.height.min(self.height.saturating_sub(y));
+        for j in 0..height {
+            for i in 0..width {
+                self.set(x + i, y + j, &other.at(i, j));
+            }
+        }
+    }
+
     pub fn ppm(&self) -> String {
         let mut ppm = String::new();
         // Header
@@ -81,10 +132,174 @@ impl Canvas {
         ppm
     }
 
+    /// Encodes the canvas as a binary (P6) PPM image
+    pub fn ppm_binary(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for j in 0..self.height {
+            for i in 0..self.width {
+                let pixel = self.at(i, j);
+                ppm.push((pixel.r() * 256.0) as u8);
+                ppm.push((pixel.g() * 256.0) as u8);
+                ppm.push((pixel.b() * 256.0) as u8);
+            }
+        }
+        ppm
+    }
+
+    /// Reads a plain (P3) or a binary (P6) PPM image
+    pub fn from_ppm(data: &[u8]) -> Result<Canvas, ImageError> {
+        let mut pos = 0;
+        let binary = match next_token(data, &mut pos) {
+            Some(b"P3") => false,
+            Some(b"P6") => true,
+            _ => return Err(ImageError::BadMagic),
+        };
+
+        let mut header_value = |name: &str| -> Result<usize, ImageError> {
+            let token = next_token(data, &mut pos).ok_or(ImageError::Truncated)?;
+            parse_number(token).ok_or_else(|| ImageError::BadHeader(format!("invalid {}", name)))
+        };
+        let width = header_value("width")?;
+        let height = header_value("height")?;
+        let maxval = header_value("maximum color value")?;
+        if width == 0 || height == 0 {
+            return Err(ImageError::BadHeader(format!(
+                "invalid dimensions: {}x{}",
+                width, height
+            )));
+        }
+        if maxval == 0 || maxval > 65535 {
+            return Err(ImageError::BadHeader(format!(
+                "invalid maximum color value: {}",
+                maxval
+            )));
+        }
+
+        let count = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| {
+                ImageError::BadHeader(format!("image too large: {}x{}", width, height))
+            })?;
+
+        // The header values are not trusted for the allocations until the
+        // data proves to be large enough
+        let mut samples = Vec::new();
+        if binary {
+            // Exactly one whitespace character separates the header from the
+            // raster
+            pos += 1;
+            let sample_size = if maxval < 256 { 1 } else { 2 };
+            let size = count.checked_mul(sample_size).ok_or_else(|| {
+                ImageError::BadHeader(format!("image too large: {}x{}", width, height))
+            })?;
+            if pos > data.len() || data.len() - pos < size {
+                return Err(ImageError::Truncated);
+            }
+            samples.reserve_exact(count);
+            for s in data[pos..pos + size].chunks(sample_size) {
+                samples.push(match sample_size {
+                    1 => s[0] as usize,
+                    _ => u16::from_be_bytes([s[0], s[1]]) as usize,
+                });
+            }
+        } else {
+            for _ in 0..count {
+                let token = next_token(data, &mut pos).ok_or(ImageError::Truncated)?;
+                let value = parse_number(token).ok_or_else(|| {
+                    ImageError::Corrupt(format!(
+                        "invalid sample: {}",
+                        String::from_utf8_lossy(token)
+                    ))
+                })?;
+                samples.push(value);
+            }
+        }
+
+        if let Some(value) = samples.iter().find(|v| **v > maxval) {
+            return Err(ImageError::Corrupt(format!(
+                "sample {} exceeds the maximum of {}",
+                value, maxval
+            )));
+        }
+
+        let mut c = Canvas::new(width, height);
+        let max = maxval as f64;
+        for (i, rgb) in samples.chunks(3).enumerate() {
+            let pixel = color(
+                rgb[0] as f64 / max,
+                rgb[1] as f64 / max,
+                rgb[2] as f64 / max,
+            );
+            c.set(i % width, i / width, &pixel);
+        }
+        Ok(c)
+    }
+
+    /// Reads a PNG image dropping the alpha channel if present
+    pub fn from_png(data: &[u8]) -> Result<Canvas, ImageError> {
+        let img = png_decode(data)?;
+        let mut c = Canvas::new(img.width, img.height);
+        for (i, rgb) in img.pixels.iter().enumerate() {
+            c.set(i % img.width, i / img.width, &color(rgb[0], rgb[1], rgb[2]));
+        }
+        Ok(c)
+    }
+
+    /// Reads a PNG or a PPM image detecting the format by its signature
+    pub fn load(file_path: &str) -> Result<Canvas, ImageError> {
+        let data = fs::read(file_path)?;
+        if data.starts_with(&PNG_SIGNATURE) {
+            return Canvas::from_png(&data);
+        }
+        Canvas::from_ppm(&data)
+    }
+
+    /// Encodes the canvas as a PNG image; the colors are clamped the same way
+    /// as in the PPM output
+    pub fn png(&self, depth: PngBitDepth) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(self.width * self.height * depth.pixel_size());
+        for j in 0..self.height {
+            for i in 0..self.width {
+                let pixel = self.at(i, j);
+                for c in [pixel.r(), pixel.g(), pixel.b()].iter() {
+                    match depth {
+                        PngBitDepth::Eight => pixels.push((c * 256.0) as u8),
+                        PngBitDepth::Sixteen => {
+                            pixels.extend_from_slice(&((c * 65536.0) as u16).to_be_bytes())
+                        }
+                    }
+                }
+            }
+        }
+        png_encode(self.width, self.height, depth, &pixels)
+    }
+
+    /// Saves the canvas in the format matching the file's extension: PNG for
+    /// `.png` files and PPM for everything else
     pub fn save(&self, file_path: &str) -> std::io::Result<()> {
+        if has_extension(file_path, "png") {
+            return self.save_png(file_path, PngBitDepth::Eight);
+        }
         fs::write(file_path, self.ppm().as_bytes())?;
         Ok(())
     }
+
+    pub fn save_ppm_binary(&self, file_path: &str) -> std::io::Result<()> {
+        fs::write(file_path, self.ppm_binary())?;
+        Ok(())
+    }
+
+    pub fn save_png(&self, file_path: &str, depth: PngBitDepth) -> std::io::Result<()> {
+        fs::write(file_path, self.png(depth))?;
+        Ok(())
+    }
+}
+
+fn has_extension(file_path: &str, ext: &str) -> bool {
+    Path::new(file_path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(ext))
 }
 
 fn add_pixel_data(mut ppm: String, row: &mut String, color: f64) -> String {
@@ -103,3 +318,33 @@ fn add_pixel_data(mut ppm: String, row: &mut String, color: f64) -> String {
 
     ppm
 }
+
+/// Returns the next whitespace-separated token of a PPM header skipping the
+/// comments
+fn next_token<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        while *pos < data.len() && data[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if *pos < data.len() && data[*pos] == b'#' {
+            while *pos < data.len() && data[*pos] != b'\n' {
+                *pos += 1;
+            }
+            continue;
+        }
+        break;
+    }
+
+    let start = *pos;
+    while *pos < data.len() && !data[*pos].is_ascii_whitespace() && data[*pos] != b'#' {
+        *pos += 1;
+    }
+    if start == *pos {
+        return None;
+    }
+    Some(&data[start..*pos])
+}
+
+fn parse_number(token: &[u8]) -> Option<usize> {
+    std::str::from_utf8(token).ok()?.parse().ok()
+}
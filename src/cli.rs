@@ -0,0 +1,254 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::camera::{Camera, Region};
+use crate::sampling::{ReconstructionFilter, SamplingPattern};
+
+use std::path::Path;
+
+pub const RENDER_USAGE: &str = "\
+Usage: ray-tracer render <scene> [options]
+
+Options:
+  -o, --output <file>   output image, PNG for .png files and PPM otherwise
+                        (default: the name of the scene with .ppm)
+  --width <pixels>      width of the image (default: from the scene)
+  --height <pixels>     height of the image (default: from the scene)
+  --samples <n>         render n x n samples per pixel (default: 1)
+  --sampling <pattern>  placement of the samples: regular or jittered
+                        (default: regular)
+  --filter <filter>     reconstruction filter: box, tent, gaussian or
+                        mitchell (default: box)
+  --seed <n>            seed of the random samples (default: 0)
+  --adaptive <contrast> subdivide the pixels whose corners differ by more
+                        than the contrast instead of the fixed sampling
+  --adaptive-depth <n>  maximum number of the subdivisions (default: 3)
+  --aperture <radius>   radius of the lens, 0 for a pinhole camera
+                        (default: from the scene)
+  --focal-distance <d>  distance to the plane in focus (default: from the
+                        scene)
+  --region <x,y,w,h>    render only the rectangle of the given top-left
+                        corner, width and height in pixels
+  --onto <image>        paste the region into a copy of the full image,
+                        eg. a previous render, instead of saving it alone
+  --threads <n>         number of rendering threads (default: all CPUs)
+  --depth <n>           maximum depth of reflections and refractions
+  -q, --quiet           don't report the progress
+  -h, --help            print this message";
+
+/// Options of the render command
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    pub scene: String,
+    pub output: String,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub samples: Option<usize>,
+    pub sampling: Option<SamplingPattern>,
+    pub filter: Option<ReconstructionFilter>,
+    pub seed: Option<u64>,
+    pub adaptive: Option<f64>,
+    pub adaptive_depth: Option<u8>,
+    pub aperture: Option<f64>,
+    pub focal_distance: Option<f64>,
+    pub region: Option<Region>,
+    pub onto: Option<String>,
+    pub threads: Option<usize>,
+    pub depth: Option<u8>,
+    pub quiet: bool,
+    pub help: bool,
+}
+
+impl RenderOptions {
+    /// Parses the arguments following the command name
+    pub fn parse(args: &[String]) -> Result<RenderOptions, String> {
+        let mut opts = RenderOptions {
+            scene: String::new(),
+            output: String::new(),
+            width: None,
+            height: None,
+            samples: None,
+            sampling: None,
+            filter: None,
+            seed: None,
+            adaptive: None,
+            adaptive_depth: None,
+            aperture: None,
+            focal_distance: None,
+            region: None,
+            onto: None,
+            threads: None,
+            depth: None,
+            quiet: false,
+            help: false,
+        };
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let mut value = |name: &str| {
+                iter.next()
+                    .cloned()
+                    .ok_or_else(|| format!("{} needs a value", name))
+            };
+            match arg.as_str() {
+                "-h" | "--help" => opts.help = true,
+                "-q" | "--quiet" => opts.quiet = true,
+                "-o" | "--output" => opts.output = value(arg)?,
+                "--width" => opts.width = Some(positive(arg, &value(arg)?)?),
+                "--height" => opts.height = Some(positive(arg, &value(arg)?)?),
+                "--samples" => opts.samples = Some(positive(arg, &value(arg)?)?),
+                "--sampling" => {
+                    let v = value(arg)?;
+                    opts.sampling = Some(match v.as_str() {
+                        "regular" => SamplingPattern::Regular,
+                        "jittered" => SamplingPattern::Jittered,
+                        _ => return Err(format!("unknown sampling pattern: {}", v)),
+                    });
+                }
+                "--filter" => {
+                    let v = value(arg)?;
+                    opts.filter = Some(match v.as_str() {
+                        "box" => ReconstructionFilter::Box,
+                        "tent" => ReconstructionFilter::Tent,
+                        "gaussian" => ReconstructionFilter::Gaussian,
+                        "mitchell" => ReconstructionFilter::Mitchell,
+                        _ => return Err(format!("unknown filter: {}", v)),
+                    });
+                }
+                "--seed" => {
+                    let v = value(arg)?;
+                    opts.seed =
+                        Some(v.parse().map_err(|_| {
+                            format!("{} needs a non-negative number, got {}", arg, v)
+                        })?);
+                }
+                "--adaptive" => {
+                    let v = value(arg)?;
+                    match v.parse::<f64>() {
+                        Ok(t) if t >= 0.0 => opts.adaptive = Some(t),
+                        _ => return Err(format!("{} needs a non-negative number, got {}", arg, v)),
+                    }
+                }
+                "--adaptive-depth" => {
+                    let v = value(arg)?;
+                    opts.adaptive_depth = Some(
+                        v.parse()
+                            .map_err(|_| format!("{} needs a number of 0-255, got {}", arg, v))?,
+                    );
+                }
+                "--aperture" => {
+                    let v = value(arg)?;
+                    match v.parse::<f64>() {
+                        Ok(a) if a >= 0.0 => opts.aperture = Some(a),
+                        _ => return Err(format!("{} needs a non-negative number, got {}", arg, v)),
+                    }
+                }
+                "--focal-distance" => {
+                    let v = value(arg)?;
+                    match v.parse::<f64>() {
+                        Ok(d) if d > 0.0 => opts.focal_distance = Some(d),
+                        _ => return Err(format!("{} needs a positive number, got {}", arg, v)),
+                    }
+                }
+                "--region" => opts.region = Some(region(arg, &value(arg)?)?),
+                "--onto" => opts.onto = Some(value(arg)?),
+                "--threads" => opts.threads = Some(positive(arg, &value(arg)?)?),
+                "--depth" => {
+                    let v = value(arg)?;
+                    opts.depth = Some(
+                        v.parse()
+                            .map_err(|_| format!("{} needs a number of 0-255, got {}", arg, v))?,
+                    );
+                }
+                _ if arg.starts_with('-') && arg.len() > 1 => {
+                    return Err(format!("unknown option: {}", arg))
+                }
+                _ if opts.scene.is_empty() => opts.scene = arg.clone(),
+                _ => return Err(format!("unexpected argument: {}", arg)),
+            }
+        }
+
+        if opts.help {
+            return Ok(opts);
+        }
+        if opts.scene.is_empty() {
+            return Err("missing the scene file".to_string());
+        }
+        if opts.onto.is_some() && opts.region.is_none() {
+            return Err("--onto needs a --region".to_string());
+        }
+        if opts.output.is_empty() {
+            let stem = Path::new(&opts.scene)
+                .file_stem()
+                .map_or("out".into(), |s| s.to_string_lossy());
+            opts.output = format!("{}.ppm", stem);
+        }
+        Ok(opts)
+    }
+
+    /// Overrides the settings of the camera with the ones given on the
+    /// command line; giving only one of the dimensions keeps the aspect ratio
+    pub fn apply(&self, camera: &mut Camera) {
+        let (hres, vres) = (camera.hres(), camera.vres());
+        match (self.width, self.height) {
+            (Some(w), Some(h)) => camera.set_resolution(w, h),
+            (Some(w), None) => camera.set_resolution(w, (w * vres / hres).max(1)),
+            (None, Some(h)) => camera.set_resolution((h * hres / vres).max(1), h),
+            (None, None) => {}
+        }
+        if let Some(samples) = self.samples {
+            camera.set_samples(samples);
+        }
+        if let Some(sampling) = self.sampling {
+            camera.set_sampling(sampling);
+        }
+        if let Some(filter) = self.filter {
+            camera.set_filter(filter);
+        }
+        if let Some(seed) = self.seed {
+            camera.set_seed(seed);
+        }
+        if self.adaptive.is_some() || self.adaptive_depth.is_some() {
+            let mut adaptive = camera.adaptive().unwrap_or_default();
+            if let Some(threshold) = self.adaptive {
+                adaptive.threshold = threshold;
+            }
+            if let Some(depth) = self.adaptive_depth {
+                adaptive.max_depth = depth;
+            }
+            camera.set_adaptive(Some(adaptive));
+        }
+        if let Some(aperture) = self.aperture {
+            camera.set_aperture(aperture);
+        }
+        if let Some(distance) = self.focal_distance {
+            camera.set_focal_distance(distance);
+        }
+        if let Some(threads) = self.threads {
+            camera.set_threads(threads);
+        }
+        if let Some(depth) = self.depth {
+            camera.set_max_depth(depth);
+        }
+    }
+}
+
+fn positive(name: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(v) if v > 0 => Ok(v),
+        _ => Err(format!("{} needs a positive number, got {}", name, value)),
+    }
+}
+
+fn region(name: &str, value: &str) -> Result<Region, String> {
+    let err = || format!("{} needs x,y,width,height in pixels, got {}", name, value);
+    let v = value
+        .split(',')
+        .map(|v| v.trim().parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| err())?;
+    match v[..] {
+        [x, y, w, h] if w > 0 && h > 0 => Ok(Region::new(x, y, w, h)),
+        _ => Err(err()),
+    }
+}
@@ -1,11 +1,12 @@
 // Copyright 2023 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
+use crate::bounds::Bounds;
 use crate::constants::EPSILON;
 use crate::matrix::Matrix;
 use crate::ray::Ray;
 use crate::shape::{LocalShape, Shape, ShapeImpl};
-use crate::tuple::{vector, Tuple};
+use crate::tuple::{point, vector, Tuple};
 use crate::utils::{check_cap, filter_min_max, solve_quadratic};
 
 pub struct Cone {
@@ -99,6 +100,12 @@ impl LocalShape for Cone {
         println!("{ret:?} - {pt:?}");
         ret
     }
+
+    fn local_bounds(&self) -> Bounds {
+        // The radius of the cone at any height is equal to the height
+        let r = self.minimum.abs().max(self.maximum.abs());
+        Bounds::new(point(-r, self.minimum, -r), point(r, self.maximum, r))
+    }
 }
 
 pub fn cone_unit() -> Box<dyn Shape> {
@@ -0,0 +1,196 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::bounds::Bounds;
+use crate::intersections::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::ray::Ray;
+use crate::shape::{LocalShape, Shape};
+use crate::tuple::{vector, Tuple};
+
+use std::ops::Deref;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    /// Decides whether an intersection is a part of the combined surface
+    ///
+    /// # Arguments
+    ///
+    /// * `lhit` - The intersection is with the left shape
+    /// * `inl` - The intersection is inside of the left shape
+    /// * `inr` - The intersection is inside of the right shape
+    pub fn allows(&self, lhit: bool, inl: bool, inr: bool) -> bool {
+        match self {
+            CsgOperation::Union => (lhit && !inr) || (!lhit && !inl),
+            CsgOperation::Intersection => (lhit && inr) || (!lhit && inl),
+            CsgOperation::Difference => (lhit && !inr) || (!lhit && inl),
+        }
+    }
+}
+
+/// Constructive solid geometry: a boolean combination of two shapes
+///
+/// Like with groups, the transform is pushed down to the children and the
+/// intersections refer to the children, so the normals and the materials
+/// come from the shape that was actually hit. The CSG shape itself has no
+/// surface, so its normal is the zero vector.
+pub struct Csg {
+    operation: CsgOperation,
+    transform: Matrix,
+    transform_inv: Matrix,
+    material: Material,
+    left: Box<dyn Shape>,
+    right: Box<dyn Shape>,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Box<dyn Shape>, right: Box<dyn Shape>) -> Csg {
+        Csg {
+            operation,
+            transform: Matrix::one(),
+            transform_inv: Matrix::one(),
+            material: Material::new(),
+            left,
+            right,
+        }
+    }
+
+    pub fn operation(&self) -> CsgOperation {
+        self.operation
+    }
+
+    pub fn left(&self) -> &dyn Shape {
+        self.left.as_ref()
+    }
+
+    pub fn right(&self) -> &dyn Shape {
+        self.right.as_ref()
+    }
+
+    /// Keeps only the intersections that lie on the surface of the combined
+    /// shape; the intersections need to be sorted
+    pub fn filter_intersections<'a>(&self, xs: Vec<Intersection<'a>>) -> Vec<Intersection<'a>> {
+        let mut inl = false;
+        let mut inr = false;
+        let mut res = Vec::new();
+
+        for x in xs.into_iter() {
+            let lhit = self.left.includes(x.shape());
+            if self.operation.allows(lhit, inl, inr) {
+                res.push(x);
+            }
+
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+        res
+    }
+}
+
+impl LocalShape for Csg {
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        // The children live in the world's frame of reference
+        let r = ray.transformed(self.transform);
+        self.intersect(&r).iter().map(|x| x.t()).collect()
+    }
+
+    fn local_normal_at(&self, _pt: Tuple) -> Tuple {
+        vector(0.0, 0.0, 0.0)
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.bounds().transformed(self.transform_inv)
+    }
+}
+
+impl Shape for Csg {
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = self.left.intersect(ray);
+        xs.append(&mut self.right.intersect(ray));
+        xs.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
+        self.filter_intersections(xs)
+    }
+
+    fn transform(&mut self, transform: Matrix) {
+        self.transform = transform * self.transform;
+        self.transform_inv = self.transform.inverted();
+        self.left.transform(transform);
+        self.right.transform(transform);
+    }
+
+    fn current_transform(&self) -> &Matrix {
+        &self.transform
+    }
+
+    fn current_inverse_transform(&self) -> &Matrix {
+        &self.transform_inv
+    }
+
+    /// Moves both operands like the children of a group
+    fn set_motion(&mut self, end: Matrix) {
+        let change = end * self.transform_inv;
+        let left_end = change * self.left.transform_at(1.0);
+        let right_end = change * self.right.transform_at(1.0);
+        self.left.set_motion(left_end);
+        self.right.set_motion(right_end);
+    }
+
+    fn normal_at(&self, pt: Tuple) -> Tuple {
+        self.local_normal_at(pt)
+    }
+
+    fn normal_at_hit(&self, pt: Tuple, _hit: &Intersection) -> Tuple {
+        self.local_normal_at(pt)
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Gives access to the CSG's own material; the hits use the materials of
+    /// the children
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn set_material(&mut self, material: &Material) {
+        self.material = material.clone();
+    }
+
+    fn bounds(&self) -> Bounds {
+        match self.operation {
+            CsgOperation::Difference => self.left.bounds(),
+            _ => self.left.bounds().merged(&self.right.bounds()),
+        }
+    }
+
+    fn build_bvh(&mut self) {
+        self.left.build_bvh();
+        self.right.build_bvh();
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        self.left.includes(other) || self.right.includes(other)
+    }
+}
+
+impl Deref for Csg {
+    type Target = dyn LocalShape;
+    fn deref(&self) -> &Self::Target {
+        self
+    }
+}
+
+pub fn csg(operation: CsgOperation, left: Box<dyn Shape>, right: Box<dyn Shape>) -> Box<dyn Shape> {
+    Box::new(Csg::new(operation, left, right))
+}
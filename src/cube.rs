@@ -1,10 +1,11 @@
 // Copyright 2022 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
+use crate::bounds::Bounds;
 use crate::matrix::Matrix;
 use crate::ray::Ray;
 use crate::shape::{LocalShape, Shape, ShapeImpl};
-use crate::tuple::{vector, Tuple};
+use crate::tuple::{point, vector, Tuple};
 
 pub struct Cube {}
 
@@ -34,6 +35,16 @@ impl LocalShape for Cube {
         vec![tmin, tmax]
     }
 
+    fn local_any_hit(&self, ray: &Ray, max_t: f64) -> bool {
+        let (xtmin, xtmax) = check_axis(ray.origin().x(), ray.direction().x());
+        let (ytmin, ytmax) = check_axis(ray.origin().y(), ray.direction().y());
+        let (ztmin, ztmax) = check_axis(ray.origin().z(), ray.direction().z());
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        tmin <= tmax && ((tmin > 0.0 && tmin < max_t) || (tmax > 0.0 && tmax < max_t))
+    }
+
     fn local_normal_at(&self, pt: Tuple) -> Tuple {
         let max = pt.x().abs().max(pt.y().abs()).max(pt.z().abs());
         if max == pt.x().abs() {
@@ -43,6 +54,10 @@ impl LocalShape for Cube {
         }
         vector(0.0, 0.0, pt.z())
     }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0))
+    }
 }
 
 pub fn cube_unit() -> Box<dyn Shape> {
@@ -1,11 +1,12 @@
 // Copyright 2022 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
+use crate::bounds::Bounds;
 use crate::constants::EPSILON;
 use crate::matrix::Matrix;
 use crate::ray::Ray;
 use crate::shape::{LocalShape, Shape, ShapeImpl};
-use crate::tuple::{vector, Tuple};
+use crate::tuple::{point, vector, Tuple};
 use crate::utils::{check_cap, filter_min_max, solve_quadratic};
 
 pub struct Cylinder {
@@ -84,6 +85,13 @@ impl LocalShape for Cylinder {
         }
         vector(pt.x(), 0.0, pt.z())
     }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(
+            point(-1.0, self.minimum, -1.0),
+            point(1.0, self.maximum, 1.0),
+        )
+    }
 }
 
 pub fn cylinder_unit() -> Box<dyn Shape> {
@@ -3,14 +3,21 @@
 
 use ray_tracer::{
     checker_pattern_color, color, plane_unit, point, point_light, rotation_y, rotation_z, scaling,
-    sphere_glass, sphere_unit, stripe_pattern_color, translation, vector, view_transform, Camera,
-    Material, Matrix, Tuple, World,
+    sphere_glass, sphere_unit, stripe_pattern_color, translation, vector, view_transform,
+    Background, Camera, Material, Matrix, Tuple, World,
 };
 
 use std::f64::consts::PI;
 use std::io::{self, Write};
 use std::time::Instant;
 
+fn setup_sky(world: &mut World) {
+    world.background = Background::Gradient {
+        bottom: color(0.9, 0.9, 0.95),
+        top: color(0.35, 0.55, 0.9),
+    };
+}
+
 fn setup_floor(world: &mut World) {
     let mut m = Material::new();
     m.pattern = Some(checker_pattern_color(
@@ -91,6 +98,8 @@ pub fn demo11_reflection_scene() {
     let now = Instant::now();
 
     let mut world = World::empty();
+    world.transparent_shadows = true;
+    setup_sky(&mut world);
     setup_floor(&mut world);
     setup_right_wall(&mut world);
     setup_left_wall(&mut world);
@@ -121,6 +130,8 @@ pub fn demo11_refraction_scene() {
     let now = Instant::now();
 
     let mut world = World::empty();
+    world.transparent_shadows = true;
+    setup_sky(&mut world);
     setup_floor(&mut world);
     setup_right_wall(&mut world);
     setup_left_wall(&mut world);
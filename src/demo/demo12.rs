@@ -3,8 +3,8 @@
 
 use ray_tracer::{
     checker_pattern_color, color, cube, noise_pattern_unit, point, point_light, rotation_y,
-    scaling, stripe_pattern_color, translation, vector, view_transform, Camera, Material, Matrix,
-    World,
+    scaling, stripe_pattern_color, translation, vector, view_transform, Camera, Group, Material,
+    Matrix, Shape, World,
 };
 
 use std::f64::consts::PI;
@@ -37,6 +37,7 @@ fn setup_walls(world: &mut World, transform: Matrix, pattern_transform: Matrix)
 
     m.specular = 0.1;
     m.reflective = 0.05;
+    m.casts_shadow = false;
 
     let mut obj = cube(transform * scaling(5.001, 5.0, 5.0));
     obj.set_material(&m);
@@ -61,19 +62,21 @@ fn setup_table(world: &mut World) {
     m.specular = 0.3;
     m.shininess = 5.0;
 
-    let mut obj1 = cube(translation(0.0, 1.0, 0.0) * scaling(1.0, 0.05, 2.0));
-    obj1.set_material(&m);
-    world.shapes.push(obj1);
+    let mut table = Group::new();
+    table.set_propagate_material(true);
+    table.add_child(cube(translation(0.0, 0.5, 0.0) * scaling(1.0, 0.05, 2.0)));
 
     let mut leg = |transform| {
-        let mut obj2 = cube(transform * scaling(0.05, 0.5, 0.05));
-        obj2.set_material(&m);
-        world.shapes.push(obj2);
+        table.add_child(cube(transform * scaling(0.05, 0.5, 0.05)));
     };
-    leg(translation(0.9, 0.5, -1.9));
-    leg(translation(-0.9, 0.5, -1.9));
-    leg(translation(0.9, 0.5, 1.9));
-    leg(translation(-0.9, 0.5, 1.9));
+    leg(translation(0.9, 0.0, -1.9));
+    leg(translation(-0.9, 0.0, -1.9));
+    leg(translation(0.9, 0.0, 1.9));
+    leg(translation(-0.9, 0.0, 1.9));
+
+    table.set_material(&m);
+    table.transform(translation(0.0, 0.5, 0.0));
+    world.shapes.push(Box::new(table));
 }
 
 fn setup_paintings(world: &mut World) {
@@ -184,6 +187,7 @@ pub fn demo12() {
 
     let l = point_light(point(-1.0, 4.5, -4.5), color(1.0, 1.0, 1.0));
     world.lights.push(l);
+    world.build_bvh();
 
     let mut camera = Camera::new(1280, 620, PI / 3.0);
     camera.set_transform(view_transform(
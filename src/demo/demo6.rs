@@ -38,11 +38,11 @@ pub fn demo6() {
                 let eyev = -ray.direction();
                 let c = hit.shape().material().lighting(
                     sphere.as_ref(),
-                    &light,
+                    light.as_ref(),
                     &point,
                     &eyev,
                     &normalv,
-                    false,
+                    1.0,
                 );
                 canvas.set(i, j, &c);
             } else {
@@ -0,0 +1,216 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::bounds::Bounds;
+use crate::bvh::Bvh;
+use crate::intersections::Intersection;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::ray::Ray;
+use crate::shape::{LocalShape, Shape};
+use crate::tuple::{vector, Tuple};
+
+use std::ops::Deref;
+
+/// A collection of shapes transformed together
+///
+/// The transform of the group is pushed down to the children, so that every
+/// shape carries the full chain of its parents' transforms. Converting points
+/// and normals between the world's and the shape's frame of reference is,
+/// therefore, done by the shape itself and the intersections refer to the
+/// children directly. The group itself has no surface, so its normal is the
+/// zero vector; the normals come from the children that were hit.
+pub struct Group {
+    transform: Matrix,
+    transform_inv: Matrix,
+    material: Material,
+    propagate_material: bool,
+    children: Vec<Box<dyn Shape>>,
+    bvh: Option<Bvh>,
+}
+
+impl Group {
+    pub fn new() -> Group {
+        Group {
+            transform: Matrix::one(),
+            transform_inv: Matrix::one(),
+            material: Material::new(),
+            propagate_material: false,
+            children: Vec::new(),
+            bvh: None,
+        }
+    }
+
+    /// Adds a child to the group applying the group's transform on top of the
+    /// child's own one
+    pub fn add_child(&mut self, mut child: Box<dyn Shape>) {
+        child.transform(self.transform);
+        if self.propagate_material {
+            child.set_material(&self.material);
+        }
+        self.children.push(child);
+        self.bvh = None;
+    }
+
+    pub fn children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// When enabled, the materials set on the group override the materials of
+    /// the children
+    pub fn set_propagate_material(&mut self, propagate: bool) {
+        self.propagate_material = propagate;
+    }
+
+    pub fn propagates_material(&self) -> bool {
+        self.propagate_material
+    }
+}
+
+impl Default for Group {
+    fn default() -> Group {
+        Group::new()
+    }
+}
+
+impl LocalShape for Group {
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        // The children live in the world's frame of reference
+        let r = ray.transformed(self.transform);
+        let mut xs: Vec<f64> = self.intersect(&r).iter().map(|x| x.t()).collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        xs
+    }
+
+    fn local_normal_at(&self, _pt: Tuple) -> Tuple {
+        vector(0.0, 0.0, 0.0)
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        self.bounds().transformed(self.transform_inv)
+    }
+}
+
+impl Shape for Group {
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut xs = Vec::new();
+        match self.bvh.as_ref() {
+            Some(bvh) => xs = bvh.intersect(&self.children, ray),
+            None => {
+                for c in self.children.iter() {
+                    xs.append(&mut c.intersect(ray));
+                }
+            }
+        }
+        xs.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
+        xs
+    }
+
+    fn transform(&mut self, transform: Matrix) {
+        self.transform = transform * self.transform;
+        self.transform_inv = self.transform.inverted();
+        for c in self.children.iter_mut() {
+            c.transform(transform);
+        }
+        self.bvh = None;
+    }
+
+    fn current_transform(&self) -> &Matrix {
+        &self.transform
+    }
+
+    fn current_inverse_transform(&self) -> &Matrix {
+        &self.transform_inv
+    }
+
+    /// Moves every child by the same change of the group's transform; the
+    /// children interpolate their own transforms, so they travel along
+    /// straight lines rather than arcs around the group's origin
+    fn set_motion(&mut self, end: Matrix) {
+        let change = end * self.transform_inv;
+        for c in self.children.iter_mut() {
+            let child_end = change * c.transform_at(1.0);
+            c.set_motion(child_end);
+        }
+        self.bvh = None;
+    }
+
+    fn normal_at(&self, pt: Tuple) -> Tuple {
+        self.local_normal_at(pt)
+    }
+
+    fn normal_at_hit(&self, pt: Tuple, _hit: &Intersection) -> Tuple {
+        self.local_normal_at(pt)
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Gives access to the group's own material only, the changes are not
+    /// propagated to the children
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn set_material(&mut self, material: &Material) {
+        self.material = material.clone();
+        if self.propagate_material {
+            for c in self.children.iter_mut() {
+                c.set_material(material);
+            }
+        }
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.children
+            .iter()
+            .fold(Bounds::empty(), |acc, c| acc.merged(&c.bounds()))
+    }
+
+    fn build_bvh(&mut self) {
+        for c in self.children.iter_mut() {
+            c.build_bvh();
+        }
+        self.bvh = Some(Bvh::new(&self.children));
+    }
+
+    fn any_hit(&self, ray: &Ray, max_t: f64, qualifies: &dyn Fn(&Material) -> bool) -> bool {
+        match self.bvh.as_ref() {
+            Some(bvh) => bvh.any_hit(&self.children, ray, max_t, qualifies),
+            None => self
+                .children
+                .iter()
+                .any(|c| c.any_hit(ray, max_t, qualifies)),
+        }
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        self.children.iter().any(|c| c.includes(other))
+    }
+}
+
+impl Deref for Group {
+    type Target = dyn LocalShape;
+    fn deref(&self) -> &Self::Target {
+        self
+    }
+}
+
+pub fn group_unit(children: Vec<Box<dyn Shape>>) -> Box<dyn Shape> {
+    let mut g = Group::new();
+    for c in children.into_iter() {
+        g.add_child(c);
+    }
+    Box::new(g)
+}
+
+pub fn group(children: Vec<Box<dyn Shape>>, transform: Matrix) -> Box<dyn Shape> {
+    let mut g = group_unit(children);
+    g.transform(transform);
+    g
+}
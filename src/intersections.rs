@@ -13,30 +13,72 @@ use std::fmt;
 #[derive(Copy, Clone)]
 pub struct Intersection<'a> {
     t: f64,
+    u: f64,
+    v: f64,
+    time: f64,
     shape: &'a dyn Shape,
 }
 
 impl<'a> Intersection<'a> {
-    pub fn new(t: f64, shape: &dyn Shape) -> Intersection {
-        Intersection { t, shape }
+    pub fn new(t: f64, shape: &dyn Shape) -> Intersection<'_> {
+        Intersection::new_uv(t, shape, 0.0, 0.0)
+    }
+
+    /// Creates an intersection recording the surface coordinates of the hit,
+    /// ie. the barycentric coordinates for triangles
+    pub fn new_uv(t: f64, shape: &dyn Shape, u: f64, v: f64) -> Intersection<'_> {
+        Intersection {
+            t,
+            u,
+            v,
+            time: 0.0,
+            shape,
+        }
+    }
+
+    /// Records the time of the ray, so that the moving shapes can compute
+    /// the normals where they were when hit
+    pub fn with_time(self, time: f64) -> Intersection<'a> {
+        Intersection { time, ..self }
     }
 
     pub fn t(&self) -> f64 {
         self.t
     }
 
+    pub fn u(&self) -> f64 {
+        self.u
+    }
+
+    pub fn v(&self) -> f64 {
+        self.v
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
     pub fn shape(&self) -> &dyn Shape {
         self.shape
     }
 
-    pub fn properties(&self, ray: &Ray, xs: &Intersections) -> IntersectionProperties {
+    pub fn properties(&self, ray: &Ray, xs: &Intersections) -> IntersectionProperties<'a> {
         let point = ray.position(self.t);
-        let mut normalv = self.shape.normal_at(point);
+        let mut geometric_normalv = self.shape.normal_at_hit(point, self);
+        let mut normalv = match self.shape.material().normal_perturbation.as_ref() {
+            None => geometric_normalv,
+            Some(perturbation) => {
+                perturbation.perturb_at(self.shape, point, geometric_normalv, ray.time())
+            }
+        };
         let eyev = -ray.direction();
         let mut inside = false;
 
-        if normalv.dot(&eyev) < 0.0 {
+        // The perturbed normal may tilt away from the eye even when we're
+        // outside, so the side is decided by the actual surface
+        if geometric_normalv.dot(&eyev) < 0.0 {
             inside = true;
+            geometric_normalv = -geometric_normalv;
             normalv = -normalv;
         }
 
@@ -49,10 +91,11 @@ impl<'a> Intersection<'a> {
             eyev,
             normalv,
             inside,
-            over_point: point + normalv * EPSILON,
-            under_point: point - normalv * EPSILON,
+            over_point: point + geometric_normalv * EPSILON,
+            under_point: point - geometric_normalv * EPSILON,
             reflectv: ray.direction().reflected(&normalv),
             refraction_indices: (n1, n2),
+            time: ray.time(),
         }
     }
 
@@ -121,6 +164,8 @@ pub struct IntersectionProperties<'a> {
     pub under_point: Tuple,
     pub reflectv: Tuple,
     pub refraction_indices: (f64, f64),
+    /// Time of the ray, passed on to the reflected, refracted and shadow rays
+    pub time: f64,
 }
 
 impl<'a> fmt::Debug for IntersectionProperties<'a> {
@@ -174,11 +219,11 @@ impl<'a> Intersections<'a> {
         Intersections { xs: Vec::new() }
     }
 
-    pub fn from_vector(xs: Vec<Intersection>) -> Intersections {
+    pub fn from_vector(xs: Vec<Intersection<'a>>) -> Intersections<'a> {
         Intersections { xs }
     }
 
-    pub fn at(&self, i: usize) -> &Intersection {
+    pub fn at(&self, i: usize) -> &Intersection<'a> {
         &self.xs[i]
     }
 
@@ -202,7 +247,7 @@ impl<'a> Intersections<'a> {
         self.xs.is_empty()
     }
 
-    pub fn hit(&self) -> Option<&Intersection> {
+    pub fn hit(&self) -> Option<&Intersection<'a>> {
         let mut res = None;
         for x in self.xs.iter() {
             if x.t() > 0.0 {
@@ -215,11 +260,5 @@ impl<'a> Intersections<'a> {
 }
 
 pub fn intersect<'a>(shape: &'a dyn Shape, ray: &Ray) -> Vec<Intersection<'a>> {
-    let inx = shape.intersect(ray);
-    let mut res = Vec::new();
-
-    for x in inx.iter() {
-        res.push(Intersection::new(*x, shape));
-    }
-    res
+    shape.intersect(ray)
 }
@@ -1,62 +1,102 @@
 // Copyright 2022 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
+pub use crate::background::*;
+pub use crate::bounds::*;
+pub use crate::bvh::*;
 pub use crate::camera::*;
 pub use crate::canvas::*;
+pub use crate::cli::*;
 pub use crate::cone::*;
 pub use crate::constants::*;
+pub use crate::csg::*;
 pub use crate::cube::*;
 pub use crate::cylinder::*;
+pub use crate::group::*;
 pub use crate::intersections::*;
 pub use crate::light::*;
 pub use crate::material::*;
 pub use crate::matrix::*;
+pub use crate::motion::*;
 pub use crate::noise::*;
+pub use crate::obj::*;
 pub use crate::pattern::*;
 pub use crate::pattern_blended::*;
 pub use crate::pattern_checker::*;
 pub use crate::pattern_dummy::*;
 pub use crate::pattern_gradient::*;
+pub use crate::pattern_image::*;
 pub use crate::pattern_noise::*;
 pub use crate::pattern_radial_gradient::*;
 pub use crate::pattern_ring::*;
 pub use crate::pattern_solid::*;
 pub use crate::pattern_stripe::*;
+pub use crate::perturbation::*;
 pub use crate::plane::*;
+pub use crate::png::*;
 pub use crate::ray::*;
+pub use crate::rng::*;
+pub use crate::sampling::*;
+pub use crate::scene::*;
 pub use crate::shape::*;
+pub use crate::smooth_triangle::*;
 pub use crate::sphere::*;
+pub use crate::stereo::*;
+pub use crate::texture::*;
 pub use crate::transformations::*;
+pub use crate::triangle::*;
 pub use crate::tuple::*;
 pub use crate::utils::*;
 pub use crate::world::*;
+pub use crate::yaml::*;
+pub use crate::zlib::*;
 
+pub mod background;
+pub mod bounds;
+pub mod bvh;
 pub mod camera;
 pub mod canvas;
+pub mod cli;
 pub mod cone;
 pub mod constants;
+pub mod csg;
 pub mod cube;
 pub mod cylinder;
+pub mod group;
 pub mod intersections;
 pub mod light;
 pub mod material;
 pub mod matrix;
+pub mod motion;
 pub mod noise;
+pub mod obj;
 pub mod pattern;
 pub mod pattern_blended;
 pub mod pattern_checker;
 pub mod pattern_dummy;
 pub mod pattern_gradient;
+pub mod pattern_image;
 pub mod pattern_noise;
 pub mod pattern_radial_gradient;
 pub mod pattern_ring;
 pub mod pattern_solid;
 pub mod pattern_stripe;
+pub mod perturbation;
 pub mod plane;
+pub mod png;
 pub mod ray;
+pub mod rng;
+pub mod sampling;
+pub mod scene;
 pub mod shape;
+pub mod smooth_triangle;
 pub mod sphere;
+pub mod stereo;
+pub mod texture;
 pub mod transformations;
+pub mod triangle;
 pub mod tuple;
 pub mod utils;
 pub mod world;
+pub mod yaml;
+pub mod zlib;
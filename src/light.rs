@@ -1,16 +1,443 @@
 // Copyright 2022 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
-use crate::tuple::Tuple;
+use crate::rng::Rng;
+use crate::sampling::concentric_disk;
+use crate::tuple::{color, vector, Tuple};
 
-pub struct Light {
-    pub position: Tuple,
+use std::any::Any;
+use std::fmt;
+
+/// The light reaching a point from a single point on the light source
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LightSample {
+    /// Unit vector pointing from the lit point towards the light
+    pub direction: Tuple,
+    /// Distance to the light; infinite for the directional lights
+    pub distance: f64,
+    /// Intensity of the light arriving at the point
     pub intensity: Tuple,
 }
 
-pub fn point_light(position: Tuple, intensity: Tuple) -> Light {
-    Light {
-        position,
+pub trait Light: fmt::Debug + Send + Sync {
+    /// Nominal intensity of the light; it's what the ambient term uses
+    fn intensity(&self) -> Tuple;
+
+    /// Returns the samples of the light as seen from the given point; the
+    /// lighting averages them and the shadows check them one by one
+    fn samples(&self, pt: &Tuple) -> Vec<LightSample>;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Model of the falloff of the light's intensity with the distance
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Attenuation {
+    /// The intensity is the same at any distance
+    #[default]
+    None,
+    /// The intensity falls off with the square of the distance
+    InverseSquare,
+    /// The intensity is divided by `constant + linear * d + quadratic * d^2`
+    Polynomial {
+        constant: f64,
+        linear: f64,
+        quadratic: f64,
+    },
+}
+
+impl Attenuation {
+    /// Returns the factor scaling the intensity at the given distance
+    pub fn factor(&self, distance: f64) -> f64 {
+        match self {
+            Attenuation::None => 1.0,
+            Attenuation::InverseSquare => 1.0 / (distance * distance),
+            Attenuation::Polynomial {
+                constant,
+                linear,
+                quadratic,
+            } => 1.0 / (constant + linear * distance + quadratic * distance * distance),
+        }
+    }
+}
+
+/// Returns the sample of the light coming from the given position; the
+/// intensity is attenuated and cut off beyond the range
+fn sample_from(
+    position: &Tuple,
+    pt: &Tuple,
+    intensity: Tuple,
+    attenuation: &Attenuation,
+    range: f64,
+) -> LightSample {
+    let v = *position - *pt;
+    let distance = v.norm();
+    let intensity = match (attenuation, distance > range) {
+        (_, true) => color(0.0, 0.0, 0.0),
+        (Attenuation::None, false) => intensity,
+        (_, false) => attenuation.factor(distance) * intensity,
+    };
+    LightSample {
+        direction: v.normalized(),
+        distance,
         intensity,
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointLight {
+    pub position: Tuple,
+    pub intensity: Tuple,
+    pub attenuation: Attenuation,
+    /// Distance beyond which the light doesn't reach
+    pub range: f64,
+}
+
+impl PointLight {
+    pub fn new(position: Tuple, intensity: Tuple) -> PointLight {
+        PointLight {
+            position,
+            intensity,
+            attenuation: Attenuation::None,
+            range: f64::INFINITY,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn intensity(&self) -> Tuple {
+        self.intensity
+    }
+
+    fn samples(&self, pt: &Tuple) -> Vec<LightSample> {
+        vec![sample_from(
+            &self.position,
+            pt,
+            self.intensity,
+            &self.attenuation,
+            self.range,
+        )]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Extent of an area light
+#[derive(Debug, Clone, PartialEq)]
+pub enum LightArea {
+    /// A parallelogram centered at the position of the light and spanned by
+    /// the edge vectors
+    Rect { uvec: Tuple, vvec: Tuple },
+    /// A disk centered at the position of the light; the vectors are
+    /// perpendicular radii
+    Disk { uvec: Tuple, vvec: Tuple },
+    /// A sphere centered at the position of the light; it is sampled as the
+    /// disk of its silhouette as seen from the lit point
+    Sphere { radius: f64 },
+}
+
+/// A light sampled on a grid of `usteps * vsteps` cells, one point per cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct AreaLight {
+    pub position: Tuple,
+    pub intensity: Tuple,
+    pub area: LightArea,
+    pub usteps: usize,
+    pub vsteps: usize,
+    /// Places the samples randomly within the cells instead of the centers;
+    /// the random numbers are seeded with the lit point, so the renders are
+    /// reproducible
+    pub jitter: bool,
+    /// Attenuation and range of each of the samples
+    pub attenuation: Attenuation,
+    pub range: f64,
+}
+
+impl AreaLight {
+    /// Creates a rectangular light with the given corner and edges
+    pub fn rect(
+        corner: Tuple,
+        full_uvec: Tuple,
+        usteps: usize,
+        full_vvec: Tuple,
+        vsteps: usize,
+        intensity: Tuple,
+    ) -> AreaLight {
+        AreaLight {
+            position: corner + full_uvec / 2.0 + full_vvec / 2.0,
+            intensity,
+            area: LightArea::Rect {
+                uvec: full_uvec,
+                vvec: full_vvec,
+            },
+            usteps: usteps.max(1),
+            vsteps: vsteps.max(1),
+            jitter: true,
+            attenuation: Attenuation::None,
+            range: f64::INFINITY,
+        }
+    }
+
+    /// Creates a disk light facing the direction of the normal; it is
+    /// sampled on a grid of `steps * steps` cells
+    pub fn disk(
+        center: Tuple,
+        normal: Tuple,
+        radius: f64,
+        steps: usize,
+        intensity: Tuple,
+    ) -> AreaLight {
+        let (u, v) = perpendicular_basis(&normal);
+        AreaLight {
+            position: center,
+            intensity,
+            area: LightArea::Disk {
+                uvec: u * radius,
+                vvec: v * radius,
+            },
+            usteps: steps.max(1),
+            vsteps: steps.max(1),
+            jitter: true,
+            attenuation: Attenuation::None,
+            range: f64::INFINITY,
+        }
+    }
+
+    /// Creates a spherical light sampled on a grid of `steps * steps` cells
+    pub fn sphere(center: Tuple, radius: f64, steps: usize, intensity: Tuple) -> AreaLight {
+        AreaLight {
+            position: center,
+            intensity,
+            area: LightArea::Sphere { radius },
+            usteps: steps.max(1),
+            vsteps: steps.max(1),
+            jitter: true,
+            attenuation: Attenuation::None,
+            range: f64::INFINITY,
+        }
+    }
+
+    /// Number of the points sampled on the light
+    pub fn sample_count(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    /// Returns the points on the light that illuminate the given point
+    pub fn sample_points(&self, pt: &Tuple) -> Vec<Tuple> {
+        let (uvec, vvec, disk) = match &self.area {
+            LightArea::Rect { uvec, vvec } => (*uvec, *vvec, false),
+            LightArea::Disk { uvec, vvec } => (*uvec, *vvec, true),
+            LightArea::Sphere { radius } => {
+                let (u, v) = perpendicular_basis(&(*pt - self.position));
+                (u * *radius, v * *radius, true)
+            }
+        };
+
+        let mut rng = Rng::from_keys(0, &[pt.x().to_bits(), pt.y().to_bits(), pt.z().to_bits()]);
+        let mut points = Vec::with_capacity(self.sample_count());
+        for v in 0..self.vsteps {
+            for u in 0..self.usteps {
+                let (ju, jv) = match self.jitter {
+                    true => (rng.next_f64(), rng.next_f64()),
+                    false => (0.5, 0.5),
+                };
+                let s = (u as f64 + ju) / self.usteps as f64;
+                let t = (v as f64 + jv) / self.vsteps as f64;
+                let (a, b) = match disk {
+                    true => concentric_disk(s, t),
+                    false => (s - 0.5, t - 0.5),
+                };
+                points.push(self.position + uvec * a + vvec * b);
+            }
+        }
+        points
+    }
+}
+
+impl Light for AreaLight {
+    fn intensity(&self) -> Tuple {
+        self.intensity
+    }
+
+    fn samples(&self, pt: &Tuple) -> Vec<LightSample> {
+        self.sample_points(pt)
+            .iter()
+            .map(|p| sample_from(p, pt, self.intensity, &self.attenuation, self.range))
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A light infinitely far away, like the sun, casting parallel rays
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectionalLight {
+    /// Direction in which the light travels
+    pub direction: Tuple,
+    pub intensity: Tuple,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Tuple, intensity: Tuple) -> DirectionalLight {
+        DirectionalLight {
+            direction: direction.normalized(),
+            intensity,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn intensity(&self) -> Tuple {
+        self.intensity
+    }
+
+    fn samples(&self, _pt: &Tuple) -> Vec<LightSample> {
+        vec![LightSample {
+            direction: -self.direction,
+            distance: f64::INFINITY,
+            intensity: self.intensity,
+        }]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A point light shining within a cone; the intensity falls off with the
+/// cosine of the angle from the axis of the cone raised to the power of the
+/// falloff exponent
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotLight {
+    pub position: Tuple,
+    /// Axis of the cone
+    pub direction: Tuple,
+    /// Angle between the axis and the side of the cone
+    pub angle: f64,
+    pub falloff: f64,
+    pub intensity: Tuple,
+    pub attenuation: Attenuation,
+    pub range: f64,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Tuple,
+        direction: Tuple,
+        angle: f64,
+        falloff: f64,
+        intensity: Tuple,
+    ) -> SpotLight {
+        SpotLight {
+            position,
+            direction: direction.normalized(),
+            angle,
+            falloff,
+            intensity,
+            attenuation: Attenuation::None,
+            range: f64::INFINITY,
+        }
+    }
+
+    /// Returns the intensity of the light shining in the direction, not
+    /// counting the attenuation
+    pub fn intensity_towards(&self, direction: &Tuple) -> Tuple {
+        self.cone_factor(direction) * self.intensity
+    }
+
+    fn cone_factor(&self, direction: &Tuple) -> f64 {
+        let cos = direction.dot(&self.direction);
+        if cos < self.angle.cos() {
+            return 0.0;
+        }
+        cos.powf(self.falloff)
+    }
+}
+
+impl Light for SpotLight {
+    fn intensity(&self) -> Tuple {
+        self.intensity
+    }
+
+    fn samples(&self, pt: &Tuple) -> Vec<LightSample> {
+        let mut sample = sample_from(
+            &self.position,
+            pt,
+            self.intensity,
+            &self.attenuation,
+            self.range,
+        );
+        sample.intensity = self.cone_factor(&-sample.direction) * sample.intensity;
+        vec![sample]
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Returns two unit vectors perpendicular to the given one and to each other
+fn perpendicular_basis(n: &Tuple) -> (Tuple, Tuple) {
+    let n = match n.norm() > 0.0 {
+        true => n.normalized(),
+        false => vector(0.0, 1.0, 0.0),
+    };
+    let helper = match n.x().abs() > 0.9 {
+        true => vector(0.0, 1.0, 0.0),
+        false => vector(1.0, 0.0, 0.0),
+    };
+    let u = n.cross(&helper).normalized();
+    (u, n.cross(&u))
+}
+
+pub fn point_light(position: Tuple, intensity: Tuple) -> Box<dyn Light> {
+    Box::new(PointLight::new(position, intensity))
+}
+
+/// Creates a rectangular light with the given corner and edges
+pub fn area_light(
+    corner: Tuple,
+    full_uvec: Tuple,
+    usteps: usize,
+    full_vvec: Tuple,
+    vsteps: usize,
+    intensity: Tuple,
+) -> Box<dyn Light> {
+    Box::new(AreaLight::rect(
+        corner, full_uvec, usteps, full_vvec, vsteps, intensity,
+    ))
+}
+
+pub fn disk_light(
+    center: Tuple,
+    normal: Tuple,
+    radius: f64,
+    steps: usize,
+    intensity: Tuple,
+) -> Box<dyn Light> {
+    Box::new(AreaLight::disk(center, normal, radius, steps, intensity))
+}
+
+pub fn sphere_light(center: Tuple, radius: f64, steps: usize, intensity: Tuple) -> Box<dyn Light> {
+    Box::new(AreaLight::sphere(center, radius, steps, intensity))
+}
+
+pub fn directional_light(direction: Tuple, intensity: Tuple) -> Box<dyn Light> {
+    Box::new(DirectionalLight::new(direction, intensity))
+}
+
+pub fn spot_light(
+    position: Tuple,
+    direction: Tuple,
+    angle: f64,
+    falloff: f64,
+    intensity: Tuple,
+) -> Box<dyn Light> {
+    Box::new(SpotLight::new(
+        position, direction, angle, falloff, intensity,
+    ))
+}
@@ -21,10 +21,25 @@ mod demo {
     pub mod noise;
 }
 
+use ray_tracer::{Canvas, Region, RenderOptions, Scene, RENDER_USAGE};
+
 use std::collections::HashMap;
 use std::env;
+use std::io::{self, Write};
+use std::process::ExitCode;
+use std::time::Instant;
+
+const USAGE: &str = "\
+Usage: ray-tracer <command> [arguments]
+
+Commands:
+  render <scene> [options]   render a scene description file
+  demo [all | <name>...]     render the built-in demos; lists them if no
+                             names are given
+
+Run `ray-tracer render --help` for the rendering options.";
 
-pub fn main() {
+fn run_demos(args: &[String]) -> ExitCode {
     let demos: Vec<(&str, fn())> = vec![
         ("demo5", demo5),
         ("demo6", demo6),
@@ -37,21 +52,19 @@ pub fn main() {
         ("noise", noise),
     ];
 
-    let mut args: Vec<String> = env::args().collect();
-    args.remove(0);
     if args.is_empty() {
         println!("Available demos:");
         for demo in demos.iter() {
             println!("{}", demo.0);
         }
-        return;
+        return ExitCode::SUCCESS;
     }
 
     if args.len() == 1 && args[0] == "all" {
         for demo in demos.iter() {
             demo.1();
         }
-        return;
+        return ExitCode::SUCCESS;
     }
 
     let mut demos_map = HashMap::<&str, fn()>::new();
@@ -59,10 +72,164 @@ pub fn main() {
         demos_map.insert(demo.0, demo.1);
     }
 
+    let mut status = ExitCode::SUCCESS;
     for arg in args.iter() {
         match demos_map.get(arg.as_str()) {
-            None => println!("No such demo: {}", arg),
+            None => {
+                eprintln!("No such demo: {}", arg);
+                status = ExitCode::FAILURE;
+            }
             Some(func) => func(),
         }
     }
+    status
+}
+
+fn render(args: &[String]) -> ExitCode {
+    let opts = match RenderOptions::parse(args) {
+        Ok(opts) => opts,
+        Err(err) => {
+            eprintln!("Error: {}\n\n{}", err, RENDER_USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if opts.help {
+        println!("{}", RENDER_USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let now = Instant::now();
+    let mut scene = match Scene::load(&opts.scene) {
+        Ok(scene) => scene,
+        Err(err) => {
+            eprintln!("Error loading {}: {}", opts.scene, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    opts.apply(&mut scene.camera);
+    scene.world.build_bvh();
+    let loaded = now.elapsed();
+
+    let camera = &scene.camera;
+    let (hres, vres) = (camera.hres(), camera.vres());
+    let region = opts.region.unwrap_or(Region::new(0, 0, hres, vres));
+    if !region.fits(hres, vres) {
+        eprintln!(
+            "Error: the region {},{},{},{} is out of the {}x{} image",
+            region.x, region.y, region.width, region.height, hres, vres
+        );
+        return ExitCode::FAILURE;
+    }
+    let mut base = None;
+    if let Some(onto) = &opts.onto {
+        match Canvas::load(onto) {
+            Ok(img) if img.width() == hres && img.height() == vres => base = Some(img),
+            Ok(img) => {
+                eprintln!(
+                    "Error: {} is {}x{}, but the image is {}x{}",
+                    onto,
+                    img.width(),
+                    img.height(),
+                    hres,
+                    vres
+                );
+                return ExitCode::FAILURE;
+            }
+            Err(err) => {
+                eprintln!("Error loading {}: {}", onto, err);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if !opts.quiet {
+        eprintln!(
+            "Loaded {} in {:.2?}: {} shapes, {} lights",
+            opts.scene,
+            loaded,
+            scene.world.shapes.len(),
+            scene.world.lights.len()
+        );
+        let sampling = match camera.adaptive() {
+            Some(a) => format!(
+                "adaptive sampling (contrast {}, depth {})",
+                a.threshold, a.max_depth
+            ),
+            None => format!(
+                "{}x{} samples per pixel",
+                camera.samples(),
+                camera.samples()
+            ),
+        };
+        let lens = match camera.aperture() > 0.0 {
+            true => format!(
+                ", aperture {} focused at {:.2}",
+                camera.aperture(),
+                camera.focal_distance()
+            ),
+            false => String::new(),
+        };
+        let crop = match opts.region {
+            Some(r) => format!(" at {},{} of {}x{}", r.x, r.y, hres, vres),
+            None => String::new(),
+        };
+        eprintln!(
+            "Rendering {}x{} pixels{}, {}{}, {} threads",
+            region.width,
+            region.height,
+            crop,
+            sampling,
+            lens,
+            camera.threads()
+        );
+    }
+
+    let now = Instant::now();
+    let progress = |done: usize, total: usize| {
+        if !opts.quiet {
+            eprint!("\rProgress: {:3}%", done * 100 / total);
+            io::stderr().flush().unwrap();
+        }
+    };
+    let (mut canvas, stats) = camera.render_region_with_stats(&scene.world, &region, &progress);
+    let elapsed = now.elapsed();
+    if let Some(mut img) = base {
+        img.paste(&canvas, region.x, region.y);
+        canvas = img;
+    }
+
+    if let Err(err) = canvas.save(&opts.output) {
+        eprintln!("\nError saving {}: {}", opts.output, err);
+        return ExitCode::FAILURE;
+    }
+    if !opts.quiet {
+        eprintln!(
+            "\rRendered in {:.2?} with {} camera rays ({:.2} per pixel). Saved {}.",
+            elapsed,
+            stats.rays,
+            stats.rays_per_pixel(),
+            opts.output
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+pub fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|a| a.as_str()) {
+        Some("render") => render(&args[1..]),
+        Some("demo") => run_demos(&args[1..]),
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Some(cmd) => {
+            eprintln!("Unknown command: {}\n\n{}", cmd, USAGE);
+            ExitCode::from(2)
+        }
+        None => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
 }
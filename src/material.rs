@@ -1,8 +1,9 @@
 // Copyright 2022 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
-use crate::light::Light;
+use crate::light::{Light, LightSample};
 use crate::pattern::Pattern;
+use crate::perturbation::NormalPerturbation;
 use crate::tuple::{color, Tuple};
 use crate::utils::feq;
 use crate::Shape;
@@ -20,6 +21,10 @@ pub struct Material {
     pub reflective: f64,
     pub transparency: f64,
     pub refractive_index: f64,
+    /// Shapes that don't cast shadows are invisible to the shadow rays
+    pub casts_shadow: bool,
+    /// Tilts the normals of the shape to imitate the detail of the surface
+    pub normal_perturbation: Option<NormalPerturbation>,
 }
 
 impl Material {
@@ -34,48 +39,108 @@ impl Material {
             reflective: 0.0,
             transparency: 0.0,
             refractive_index: 1.0,
+            casts_shadow: true,
+            normal_perturbation: None,
         }
     }
 
-    /// Shade the material according to the Phong reflection model
+    /// Shade the material according to the Phong reflection model; the
+    /// visibility is the fraction of the light reaching the point, with 0
+    /// meaning that the point is fully in shadow
     pub fn lighting(
         &self,
         shape: &dyn Shape,
-        light: &Light,
+        light: &dyn Light,
         pt: &Tuple,
         eyev: &Tuple,
         normalv: &Tuple,
-        in_shadow: bool,
+        visibility: f64,
     ) -> Tuple {
-        // See: https://en.wikipedia.org/wiki/Phong_reflection_model
+        let transmittance = color(visibility, visibility, visibility);
+        self.lighting_with_transmittance(shape, light, pt, eyev, normalv, &transmittance)
+    }
 
-        let c = match self.pattern.as_ref() {
+    /// Shade the material like `lighting` with the light filtered by the
+    /// transmittance, that is, the fraction of each of the color channels
+    /// passing through the shadow casters
+    pub fn lighting_with_transmittance(
+        &self,
+        shape: &dyn Shape,
+        light: &dyn Light,
+        pt: &Tuple,
+        eyev: &Tuple,
+        normalv: &Tuple,
+        transmittance: &Tuple,
+    ) -> Tuple {
+        let c = self.color_at(shape, pt, 0.0);
+        self.lighting_with_color(&c, light, pt, eyev, normalv, transmittance)
+    }
+
+    /// Returns the color of the surface at a point in the world's frame of
+    /// reference, with the shape placed where it is at the given time
+    pub fn color_at(&self, shape: &dyn Shape, pt: &Tuple, time: f64) -> Tuple {
+        match self.pattern.as_ref() {
             None => self.color,
-            Some(pattern) => pattern.color_at(shape, *pt),
-        };
-        let effective_color = c.hadamard(&light.intensity);
+            Some(pattern) => pattern.shape_color_at(shape.inverse_transform_at(time) * *pt),
+        }
+    }
 
-        // Direction to the light source
-        let lightv = (light.position - *pt).normalized();
+    /// Shade the material like `lighting_with_transmittance` given the color
+    /// of the surface at the point
+    pub fn lighting_with_color(
+        &self,
+        c: &Tuple,
+        light: &dyn Light,
+        pt: &Tuple,
+        eyev: &Tuple,
+        normalv: &Tuple,
+        transmittance: &Tuple,
+    ) -> Tuple {
+        let samples = light.samples(pt);
+        let transmittances = vec![*transmittance; samples.len()];
+        self.lighting_with_samples(c, light, &samples, &transmittances, eyev, normalv)
+    }
 
-        // Ambient contribution
-        let ambient = self.ambient * effective_color;
+    /// Shade the material given the color of the surface and the samples of
+    /// the light, each of them filtered by its own transmittance, so that the
+    /// samples hidden by the shadow casters don't contribute
+    pub fn lighting_with_samples(
+        &self,
+        c: &Tuple,
+        light: &dyn Light,
+        samples: &[LightSample],
+        transmittances: &[Tuple],
+        eyev: &Tuple,
+        normalv: &Tuple,
+    ) -> Tuple {
+        // See: https://en.wikipedia.org/wiki/Phong_reflection_model
 
-        if in_shadow {
-            return ambient;
-        }
+        // Ambient contribution
+        let ambient = self.ambient * c.hadamard(&light.intensity());
 
         // Defuse and specular contributions are black by default
         let mut diffuse = color(0.0, 0.0, 0.0);
         let mut specular = color(0.0, 0.0, 0.0);
 
-        // Cosine of the angle between the ligt and normal vector
-        let ln_cos = lightv.dot(normalv);
+        // Area lights are averaged over their samples
+        for (sample, transmittance) in samples.iter().zip(transmittances.iter()) {
+            if transmittance.r() <= 0.0 && transmittance.g() <= 0.0 && transmittance.b() <= 0.0 {
+                continue;
+            }
+            let effective_color = c.hadamard(&sample.intensity);
+
+            // Direction to the light source
+            let lightv = sample.direction;
 
-        // Nevative cosine between the light and normal vectors means the light
-        // source is behind the surface
-        if ln_cos > 0.0 {
-            diffuse = self.diffuse * ln_cos * effective_color;
+            // Cosine of the angle between the ligt and normal vector
+            let ln_cos = lightv.dot(normalv);
+
+            // Nevative cosine between the light and normal vectors means the
+            // light source is behind the surface
+            if ln_cos <= 0.0 {
+                continue;
+            }
+            diffuse = diffuse + (self.diffuse * ln_cos * effective_color).hadamard(transmittance);
 
             // Cosine between the light reflection vector and and the eve vector
             let reflectedv = (-lightv).reflected(normalv);
@@ -83,11 +148,16 @@ impl Material {
 
             // Negative cosinus means the light reflects away from the eye
             if re_cos > 0.0 {
-                specular = re_cos.powf(self.shininess) * self.specular * light.intensity;
+                let highlight = re_cos.powf(self.shininess) * self.specular * sample.intensity;
+                specular = specular + highlight.hadamard(transmittance);
             }
         }
 
-        ambient + diffuse + specular
+        if samples.is_empty() {
+            return ambient;
+        }
+        let n = samples.len() as f64;
+        ambient + diffuse / n + specular / n
     }
 }
 
@@ -99,6 +169,8 @@ impl PartialEq for Material {
             && feq(self.specular, other.specular)
             && feq(self.shininess, other.shininess)
             && self.pattern == other.pattern
+            && self.casts_shadow == other.casts_shadow
+            && self.normal_perturbation == other.normal_perturbation
     }
 }
 
@@ -0,0 +1,328 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::bounds::Bounds;
+use crate::matrix::Matrix;
+use crate::transformations::translation;
+use crate::tuple::{point, Tuple};
+
+/// Number of the moments at which the motion is sampled to bound the shape
+const BOUNDS_STEPS: usize = 32;
+
+/// Maximum number of the iterations of the polar decomposition
+const POLAR_ITERATIONS: usize = 100;
+
+/// A unit quaternion representing a rotation
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Quaternion {
+        Quaternion { w, x, y, z }
+    }
+
+    /// Extracts the rotation from the upper-left 3x3 part of the matrix,
+    /// which needs to be orthonormal; see:
+    /// https://en.wikipedia.org/wiki/Rotation_matrix#Quaternion
+    pub fn from_matrix(m: &Matrix) -> Quaternion {
+        let trace = m.at(0, 0) + m.at(1, 1) + m.at(2, 2);
+        let q = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            Quaternion::new(
+                s / 4.0,
+                (m.at(2, 1) - m.at(1, 2)) / s,
+                (m.at(0, 2) - m.at(2, 0)) / s,
+                (m.at(1, 0) - m.at(0, 1)) / s,
+            )
+        } else if m.at(0, 0) > m.at(1, 1) && m.at(0, 0) > m.at(2, 2) {
+            let s = 2.0 * (1.0 + m.at(0, 0) - m.at(1, 1) - m.at(2, 2)).sqrt();
+            Quaternion::new(
+                (m.at(2, 1) - m.at(1, 2)) / s,
+                s / 4.0,
+                (m.at(0, 1) + m.at(1, 0)) / s,
+                (m.at(0, 2) + m.at(2, 0)) / s,
+            )
+        } else if m.at(1, 1) > m.at(2, 2) {
+            let s = 2.0 * (1.0 + m.at(1, 1) - m.at(0, 0) - m.at(2, 2)).sqrt();
+            Quaternion::new(
+                (m.at(0, 2) - m.at(2, 0)) / s,
+                (m.at(0, 1) + m.at(1, 0)) / s,
+                s / 4.0,
+                (m.at(1, 2) + m.at(2, 1)) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m.at(2, 2) - m.at(0, 0) - m.at(1, 1)).sqrt();
+            Quaternion::new(
+                (m.at(1, 0) - m.at(0, 1)) / s,
+                (m.at(0, 2) + m.at(2, 0)) / s,
+                (m.at(1, 2) + m.at(2, 1)) / s,
+                s / 4.0,
+            )
+        };
+        q.normalized()
+    }
+
+    /// Returns the rotation as a 4x4 matrix
+    pub fn matrix(&self) -> Matrix {
+        let Quaternion { w, x, y, z } = *self;
+        let mut m = Matrix::one();
+        m.set(0, 0, 1.0 - 2.0 * (y * y + z * z));
+        m.set(0, 1, 2.0 * (x * y - w * z));
+        m.set(0, 2, 2.0 * (x * z + w * y));
+        m.set(1, 0, 2.0 * (x * y + w * z));
+        m.set(1, 1, 1.0 - 2.0 * (x * x + z * z));
+        m.set(1, 2, 2.0 * (y * z - w * x));
+        m.set(2, 0, 2.0 * (x * z - w * y));
+        m.set(2, 1, 2.0 * (y * z + w * x));
+        m.set(2, 2, 1.0 - 2.0 * (x * x + y * y));
+        m
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn normalized(&self) -> Quaternion {
+        let n = self.dot(self).sqrt();
+        Quaternion::new(self.w / n, self.x / n, self.y / n, self.z / n)
+    }
+
+    /// Returns the angle between the rotations along the shorter arc
+    pub fn angle_to(&self, other: &Quaternion) -> f64 {
+        2.0 * self.dot(other).abs().clamp(0.0, 1.0).acos()
+    }
+
+    /// Interpolates between the rotations at a constant angular speed
+    /// along the shorter arc: https://en.wikipedia.org/wiki/Slerp
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        // The quaternion and its negation are the same rotation
+        let mut cos = self.dot(other);
+        let mut other = *other;
+        if cos < 0.0 {
+            cos = -cos;
+            other = Quaternion::new(-other.w, -other.x, -other.y, -other.z);
+        }
+
+        // Nearly parallel quaternions interpolate linearly to avoid dividing
+        // by the sine of a tiny angle
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos.acos();
+            let sin = theta.sin();
+            (((1.0 - t) * theta).sin() / sin, (t * theta).sin() / sin)
+        };
+        Quaternion::new(
+            a * self.w + b * other.w,
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z,
+        )
+        .normalized()
+    }
+}
+
+/// A transform split into a translation, a rotation and a stretch, ie. the
+/// scaling and the shearing, applied in the reverse order
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Decomposition {
+    translation: Tuple,
+    rotation: Quaternion,
+    stretch: Matrix,
+}
+
+impl Decomposition {
+    /// Decomposes an affine transform; the rotation comes from the polar
+    /// decomposition of the linear part, see: Shoemake and Duff, "Matrix
+    /// Animation and Polar Decomposition"
+    pub fn new(m: &Matrix) -> Decomposition {
+        let translation = point(m.at(0, 3), m.at(1, 3), m.at(2, 3));
+        let mut linear = *m;
+        for i in 0..3 {
+            linear.set(i, 3, 0.0);
+            linear.set(3, i, 0.0);
+        }
+        linear.set(3, 3, 1.0);
+
+        // Averaging the matrix with its inverse transpose converges to the
+        // closest orthonormal matrix
+        let mut rotation = linear;
+        for _ in 0..POLAR_ITERATIONS {
+            let inv_t = rotation.inverted().transposed();
+            let mut next = Matrix::one();
+            let mut change: f64 = 0.0;
+            for i in 0..3 {
+                for j in 0..3 {
+                    let v = 0.5 * (rotation.at(i, j) + inv_t.at(i, j));
+                    change = change.max((v - rotation.at(i, j)).abs());
+                    next.set(i, j, v);
+                }
+            }
+            rotation = next;
+            if change < 1e-12 {
+                break;
+            }
+        }
+
+        // A mirroring transform leaves a reflection, which the quaternions
+        // cannot express, so it goes to the stretch instead
+        if rotation.det() < 0.0 {
+            for i in 0..3 {
+                for j in 0..3 {
+                    rotation.set(i, j, -rotation.at(i, j));
+                }
+            }
+        }
+
+        Decomposition {
+            translation,
+            rotation: Quaternion::from_matrix(&rotation),
+            stretch: rotation.transposed() * linear,
+        }
+    }
+
+    pub fn translation(&self) -> Tuple {
+        self.translation
+    }
+
+    pub fn rotation(&self) -> Quaternion {
+        self.rotation
+    }
+
+    pub fn stretch(&self) -> &Matrix {
+        &self.stretch
+    }
+
+    /// Composes the parts back into a transform
+    pub fn matrix(&self) -> Matrix {
+        let t = self.translation;
+        translation(t.x(), t.y(), t.z()) * self.rotation.matrix() * self.stretch
+    }
+
+    /// Composes the inverted parts back into the inverse of the transform;
+    /// the rotation inverts by transposing and the stretch is only 3x3
+    pub fn inverse_matrix(&self) -> Matrix {
+        let t = self.translation;
+        inverted_3x3(&self.stretch)
+            * self.rotation.matrix().transposed()
+            * translation(-t.x(), -t.y(), -t.z())
+    }
+
+    /// Interpolates between the decompositions: linearly for the
+    /// translation and the stretch, and spherically for the rotation
+    pub fn interpolated(&self, other: &Decomposition, t: f64) -> Decomposition {
+        let mut stretch = Matrix::one();
+        for i in 0..3 {
+            for j in 0..3 {
+                let a = self.stretch.at(i, j);
+                stretch.set(i, j, a + (other.stretch.at(i, j) - a) * t);
+            }
+        }
+        Decomposition {
+            translation: self.translation + (other.translation - self.translation) * t,
+            rotation: self.rotation.slerp(&other.rotation, t),
+            stretch,
+        }
+    }
+}
+
+/// A transform changing over time between two keyframes, the first one at
+/// the time 0 and the second one at the time 1; the transforms applied on
+/// top of the motion, like the ones of the parent groups, stay fixed
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Motion {
+    start: Decomposition,
+    end: Decomposition,
+    outer: Matrix,
+    outer_inv: Matrix,
+}
+
+impl Motion {
+    pub fn new(start: Matrix, end: Matrix) -> Motion {
+        Motion {
+            start: Decomposition::new(&start),
+            end: Decomposition::new(&end),
+            outer: Matrix::one(),
+            outer_inv: Matrix::one(),
+        }
+    }
+
+    /// Applies a fixed transform on top of both keyframes
+    pub fn transform(&mut self, transform: Matrix) {
+        self.outer = transform * self.outer;
+        self.outer_inv = self.outer.inverted();
+    }
+
+    /// Returns the full transform at the given time; the times outside of
+    /// the [0, 1] range get the transform of the nearest keyframe
+    pub fn transform_at(&self, time: f64) -> Matrix {
+        let t = time.clamp(0.0, 1.0);
+        self.outer * self.start.interpolated(&self.end, t).matrix()
+    }
+
+    /// Returns the inverse of `transform_at` without inverting a 4x4 matrix
+    pub fn inverse_transform_at(&self, time: f64) -> Matrix {
+        let t = time.clamp(0.0, 1.0);
+        self.start.interpolated(&self.end, t).inverse_matrix() * self.outer_inv
+    }
+
+    /// Returns the box bounding the local box over the whole motion
+    pub fn bounds(&self, local: &Bounds) -> Bounds {
+        if local.is_empty() || !local.is_finite() {
+            return local.transformed(self.outer * self.start.matrix());
+        }
+
+        // The radius of the rotation is measured before the outer transform,
+        // which then stretches the bulges by at most its scale
+        let mut bounds = Bounds::empty();
+        let mut radius: f64 = 0.0;
+        for i in 0..=BOUNDS_STEPS {
+            let t = i as f64 / BOUNDS_STEPS as f64;
+            let inner = self.start.interpolated(&self.end, t).matrix();
+            let b = local.transformed(inner);
+            let center = inner * point(0.0, 0.0, 0.0);
+            for corner in [b.min(), b.max()] {
+                radius = radius.max((corner - center).norm());
+            }
+            bounds = bounds.merged(&local.transformed(self.outer * inner));
+        }
+
+        // Between the samples the rotating corners bulge out of the chords
+        // by at most the sagitta of the arc
+        let step = self.start.rotation.angle_to(&self.end.rotation) / BOUNDS_STEPS as f64;
+        let scale = max_scale(&self.outer);
+        bounds.padded(radius * scale * (1.0 - (step / 2.0).cos()))
+    }
+}
+
+/// Inverts the upper-left 3x3 part of the matrix using its adjugate
+fn inverted_3x3(m: &Matrix) -> Matrix {
+    let c = |i: usize, j: usize| {
+        let (i1, i2) = ((i + 1) % 3, (i + 2) % 3);
+        let (j1, j2) = ((j + 1) % 3, (j + 2) % 3);
+        m.at(i1, j1) * m.at(i2, j2) - m.at(i1, j2) * m.at(i2, j1)
+    };
+    let det = m.at(0, 0) * c(0, 0) + m.at(0, 1) * c(0, 1) + m.at(0, 2) * c(0, 2);
+    let mut inv = Matrix::one();
+    for i in 0..3 {
+        for j in 0..3 {
+            inv.set(i, j, c(j, i) / det);
+        }
+    }
+    inv
+}
+
+/// Returns an upper bound of how much the transform stretches the vectors
+fn max_scale(m: &Matrix) -> f64 {
+    let mut max: f64 = 0.0;
+    for i in 0..3 {
+        let row = (0..3).map(|j| m.at(i, j).abs()).sum::<f64>();
+        max = max.max(row);
+    }
+    max.max(1.0)
+}
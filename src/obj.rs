@@ -0,0 +1,323 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::group::Group;
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::shape::Shape;
+use crate::smooth_triangle::smooth_triangle;
+use crate::triangle::triangle;
+use crate::tuple::{point, vector, Tuple};
+
+use std::fmt;
+use std::fs;
+
+pub const OBJ_DEFAULT_GROUP: &str = "default";
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(err) => write!(f, "{}", err),
+            ObjError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<std::io::Error> for ObjError {
+    fn from(err: std::io::Error) -> ObjError {
+        ObjError::Io(err)
+    }
+}
+
+/// A triangle referring to the vertex data of the file by zero-based indices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjFace {
+    pub vertices: [usize; 3],
+    pub texture_coords: Option<[usize; 3]>,
+    pub normals: Option<[usize; 3]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjGroup {
+    pub name: String,
+    pub faces: Vec<ObjFace>,
+}
+
+/// Wavefront OBJ file
+/// See: https://en.wikipedia.org/wiki/Wavefront_.obj_file
+#[derive(Debug, Default)]
+pub struct ObjFile {
+    vertices: Vec<Tuple>,
+    normals: Vec<Tuple>,
+    texture_coords: Vec<(f64, f64)>,
+    groups: Vec<ObjGroup>,
+    ignored: usize,
+}
+
+impl ObjFile {
+    pub fn parse(input: &str) -> Result<ObjFile, ObjError> {
+        let mut obj = ObjFile {
+            groups: vec![ObjGroup {
+                name: OBJ_DEFAULT_GROUP.to_string(),
+                faces: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        let mut current = 0;
+
+        for (i, line) in input.lines().enumerate() {
+            let lineno = i + 1;
+
+            // The comments run from the hash to the end of the line
+            let line = line.split('#').next().unwrap_or_default();
+            let mut tokens = line.split_whitespace();
+            let keyword = match tokens.next() {
+                None => continue,
+                Some(keyword) => keyword,
+            };
+            let args: Vec<&str> = tokens.collect();
+
+            match keyword {
+                "v" => {
+                    let c = parse_floats(&args, 3, 4, lineno)?;
+                    obj.vertices.push(point(c[0], c[1], c[2]));
+                }
+                "vn" => {
+                    let c = parse_floats(&args, 3, 3, lineno)?;
+                    obj.normals.push(vector(c[0], c[1], c[2]));
+                }
+                "vt" => {
+                    let c = parse_floats(&args, 1, 3, lineno)?;
+                    obj.texture_coords
+                        .push((c[0], c.get(1).copied().unwrap_or(0.0)));
+                }
+                "f" => {
+                    let faces = obj.parse_face(&args, lineno)?;
+                    obj.groups[current].faces.extend(faces);
+                }
+                "g" | "o" => {
+                    let name = if args.is_empty() {
+                        OBJ_DEFAULT_GROUP.to_string()
+                    } else {
+                        args.join(" ")
+                    };
+                    current = match obj.groups.iter().position(|g| g.name == name) {
+                        Some(idx) => idx,
+                        None => {
+                            obj.groups.push(ObjGroup {
+                                name,
+                                faces: Vec::new(),
+                            });
+                            obj.groups.len() - 1
+                        }
+                    };
+                }
+                _ => obj.ignored += 1,
+            }
+        }
+        Ok(obj)
+    }
+
+    pub fn load(file_path: &str) -> Result<ObjFile, ObjError> {
+        let input = fs::read_to_string(file_path)?;
+        ObjFile::parse(input.as_str())
+    }
+
+    /// Number of lines with statements that are not supported and were skipped
+    pub fn ignored_lines(&self) -> usize {
+        self.ignored
+    }
+
+    pub fn vertices(&self) -> &[Tuple] {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &[Tuple] {
+        &self.normals
+    }
+
+    pub fn texture_coords(&self) -> &[(f64, f64)] {
+        &self.texture_coords
+    }
+
+    pub fn groups(&self) -> &[ObjGroup] {
+        &self.groups
+    }
+
+    pub fn group(&self, name: &str) -> Option<&ObjGroup> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+
+    /// Creates the triangles of a single group. Faces that come with vertex
+    /// normals become smooth triangles.
+    pub fn group_shapes(
+        &self,
+        group: &ObjGroup,
+        transform: Matrix,
+        material: &Material,
+    ) -> Vec<Box<dyn Shape>> {
+        let mut shapes = Vec::new();
+        for face in group.faces.iter() {
+            let [p1, p2, p3] = face.vertices.map(|i| self.vertices[i]);
+            let mut s = match face.normals {
+                None => triangle(p1, p2, p3),
+                Some(normals) => {
+                    let [n1, n2, n3] = normals.map(|i| self.normals[i]);
+                    smooth_triangle(p1, p2, p3, n1, n2, n3)
+                }
+            };
+            s.transform(transform);
+            s.set_material(material);
+            shapes.push(s);
+        }
+        shapes
+    }
+
+    /// Creates the triangles of all the groups sharing the same transform and
+    /// material
+    pub fn shapes(&self, transform: Matrix, material: &Material) -> Vec<Box<dyn Shape>> {
+        let mut shapes = Vec::new();
+        for group in self.groups.iter() {
+            shapes.append(&mut self.group_shapes(group, transform, material));
+        }
+        shapes
+    }
+
+    /// Creates a group holding a sub-group for every non-empty group of the
+    /// file; the material is propagated to all the triangles
+    pub fn to_group(&self, transform: Matrix, material: &Material) -> Box<dyn Shape> {
+        let mut g = Group::new();
+        g.set_propagate_material(true);
+        for group in self.groups.iter().filter(|g| !g.faces.is_empty()) {
+            let mut sub = Group::new();
+            sub.set_propagate_material(true);
+            for s in self.group_shapes(group, Matrix::one(), material) {
+                sub.add_child(s);
+            }
+            g.add_child(Box::new(sub));
+        }
+        g.set_material(material);
+        g.transform(transform);
+        Box::new(g)
+    }
+
+    fn parse_face(&self, args: &[&str], lineno: usize) -> Result<Vec<ObjFace>, ObjError> {
+        if args.len() < 3 {
+            return Err(parse_error(
+                lineno,
+                format!("face needs at least 3 vertices, got {}", args.len()),
+            ));
+        }
+
+        let mut vertices = Vec::new();
+        let mut texture_coords = Vec::new();
+        let mut normals = Vec::new();
+
+        // Each vertex is either v, v/vt, v//vn or v/vt/vn
+        for arg in args.iter() {
+            let parts: Vec<&str> = arg.split('/').collect();
+            if parts.len() > 3 || parts[0].is_empty() {
+                return Err(parse_error(
+                    lineno,
+                    format!("malformed face vertex: {}", arg),
+                ));
+            }
+            vertices.push(resolve_index(parts[0], self.vertices.len(), lineno)?);
+            if let Some(vt) = parts.get(1).filter(|vt| !vt.is_empty()) {
+                texture_coords.push(resolve_index(vt, self.texture_coords.len(), lineno)?);
+            }
+            if let Some(vn) = parts.get(2).filter(|vn| !vn.is_empty()) {
+                normals.push(resolve_index(vn, self.normals.len(), lineno)?);
+            }
+        }
+
+        let complete = |idx: &Vec<usize>, what: &str| -> Result<bool, ObjError> {
+            if idx.is_empty() {
+                return Ok(false);
+            }
+            if idx.len() != vertices.len() {
+                return Err(parse_error(
+                    lineno,
+                    format!("{} given for some of the face vertices only", what),
+                ));
+            }
+            Ok(true)
+        };
+        let has_texture_coords = complete(&texture_coords, "texture coordinates")?;
+        let has_normals = complete(&normals, "normals")?;
+
+        // Polygons are triangulated as a fan around the first vertex
+        let mut faces = Vec::new();
+        for i in 1..vertices.len() - 1 {
+            let fan = |idx: &Vec<usize>| [idx[0], idx[i], idx[i + 1]];
+            faces.push(ObjFace {
+                vertices: fan(&vertices),
+                texture_coords: has_texture_coords.then(|| fan(&texture_coords)),
+                normals: has_normals.then(|| fan(&normals)),
+            });
+        }
+        Ok(faces)
+    }
+}
+
+pub fn parse_obj(input: &str) -> Result<ObjFile, ObjError> {
+    ObjFile::parse(input)
+}
+
+pub fn load_obj(file_path: &str) -> Result<ObjFile, ObjError> {
+    ObjFile::load(file_path)
+}
+
+fn parse_error(line: usize, message: String) -> ObjError {
+    ObjError::Parse { line, message }
+}
+
+fn parse_floats(
+    args: &[&str],
+    min: usize,
+    max: usize,
+    lineno: usize,
+) -> Result<Vec<f64>, ObjError> {
+    if args.len() < min || args.len() > max {
+        return Err(parse_error(
+            lineno,
+            format!("expected {} to {} numbers, got {}", min, max, args.len()),
+        ));
+    }
+
+    let mut res = Vec::new();
+    for arg in args.iter() {
+        match arg.parse::<f64>() {
+            Ok(v) => res.push(v),
+            Err(_) => return Err(parse_error(lineno, format!("not a number: {}", arg))),
+        }
+    }
+    Ok(res)
+}
+
+/// Converts a one-based (or negative, relative to the end) OBJ index to a
+/// zero-based one
+fn resolve_index(arg: &str, len: usize, lineno: usize) -> Result<usize, ObjError> {
+    let idx = match arg.parse::<isize>() {
+        Ok(idx) => idx,
+        Err(_) => return Err(parse_error(lineno, format!("not an index: {}", arg))),
+    };
+
+    let resolved = if idx < 0 { len as isize + idx } else { idx - 1 };
+
+    if idx == 0 || resolved < 0 || resolved >= len as isize {
+        return Err(parse_error(
+            lineno,
+            format!("index {} out of range (have {})", idx, len),
+        ));
+    }
+    Ok(resolved as usize)
+}
@@ -8,7 +8,7 @@ use crate::tuple::Tuple;
 use std::any::Any;
 use std::fmt;
 
-pub trait Pattern: fmt::Debug {
+pub trait Pattern: fmt::Debug + Send + Sync {
     fn transform(&mut self, transform: Matrix);
     fn current_transform(&self) -> &Matrix;
 
@@ -31,7 +31,7 @@ pub trait Pattern: fmt::Debug {
     fn as_any(&self) -> &dyn Any;
 }
 
-pub trait LocalPattern: Clone + fmt::Debug + PartialEq + Eq {
+pub trait LocalPattern: Clone + fmt::Debug + PartialEq + Eq + Send + Sync {
     /// Returns the color for a point in the pattern's frame of reference
     ///
     /// # Arguments
@@ -0,0 +1,44 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::canvas::Canvas;
+use crate::matrix::Matrix;
+use crate::pattern::{LocalPattern, Pattern, PatternImpl};
+use crate::texture::{Texture, TextureWrap, UvMapping};
+use crate::tuple::Tuple;
+
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImagePattern {
+    texture: Texture,
+    mapping: UvMapping,
+}
+
+impl LocalPattern for ImagePattern {
+    fn local_color_at(&self, pt: Tuple) -> Tuple {
+        self.texture.sample_mapped(self.mapping, pt)
+    }
+}
+
+pub fn image_pattern_unit(
+    image: impl Into<Arc<Canvas>>,
+    mapping: UvMapping,
+    wrap: TextureWrap,
+) -> Box<dyn Pattern> {
+    Box::new(PatternImpl::new(ImagePattern {
+        texture: Texture::new(image, wrap),
+        mapping,
+    }))
+}
+
+pub fn image_pattern(
+    image: impl Into<Arc<Canvas>>,
+    mapping: UvMapping,
+    wrap: TextureWrap,
+    transform: Matrix,
+) -> Box<dyn Pattern> {
+    let mut p = image_pattern_unit(image, mapping, wrap);
+    p.transform(transform);
+    p
+}
@@ -0,0 +1,203 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::canvas::Canvas;
+use crate::matrix::Matrix;
+use crate::noise::Noise;
+use crate::shape::Shape;
+use crate::texture::{Texture, TextureWrap, UvMapping};
+use crate::tuple::{vector, Tuple};
+
+use std::sync::Arc;
+
+/// Step of the finite differences approximating the slope of the bumps
+const BUMP_STEP: f64 = 1e-4;
+
+/// Where the perturbed normals come from
+#[derive(Debug, Clone)]
+pub enum PerturbationSource {
+    /// An image whose colors encode the normals relative to the surface:
+    /// red points towards growing u, green towards growing v, and blue away
+    /// from the surface
+    NormalMap {
+        texture: Texture,
+        mapping: UvMapping,
+    },
+    /// The height of the bumps given by the Perlin noise
+    Bump {
+        noise: Noise,
+        amplitude: f64,
+        octaves: u8,
+    },
+}
+
+/// Alters the normals of the shapes to fake the detail of the surface without
+/// the extra geometry; like the patterns, it has its own transform relative to
+/// the shape
+#[derive(Debug, Clone)]
+pub struct NormalPerturbation {
+    source: PerturbationSource,
+    transform: Matrix,
+    transform_inv: Matrix,
+}
+
+impl NormalPerturbation {
+    pub fn new(source: PerturbationSource, transform: Matrix) -> NormalPerturbation {
+        NormalPerturbation {
+            source,
+            transform,
+            transform_inv: transform.inverted(),
+        }
+    }
+
+    /// Uses a tangent-space normal map, that is, the image of the normals
+    /// with the flat surface being (0.5, 0.5, 1)
+    pub fn normal_map(
+        image: impl Into<Arc<Canvas>>,
+        mapping: UvMapping,
+        transform: Matrix,
+    ) -> NormalPerturbation {
+        let texture = Texture::new(image, TextureWrap::Wrap);
+        NormalPerturbation::new(
+            PerturbationSource::NormalMap { texture, mapping },
+            transform,
+        )
+    }
+
+    /// Bumps the surface with the noise scaled by the amplitude; the transform
+    /// sets the size of the bumps
+    pub fn bump(amplitude: f64, octaves: u8, transform: Matrix) -> NormalPerturbation {
+        let source = PerturbationSource::Bump {
+            noise: Noise::new(),
+            amplitude,
+            octaves: octaves.max(1),
+        };
+        NormalPerturbation::new(source, transform)
+    }
+
+    pub fn source(&self) -> &PerturbationSource {
+        &self.source
+    }
+
+    pub fn current_transform(&self) -> &Matrix {
+        &self.transform
+    }
+
+    /// Returns the perturbed normal at a point in the world's frame of
+    /// reference
+    ///
+    /// # Arguments
+    ///
+    /// * `shape` - The shape the point lies on
+    /// * `pt_w` - A point in the world's frame of reference
+    /// * `normal_w` - The unperturbed normal at the point
+    pub fn perturb(&self, shape: &dyn Shape, pt_w: Tuple, normal_w: Tuple) -> Tuple {
+        self.perturb_at(shape, pt_w, normal_w, 0.0)
+    }
+
+    /// Returns the perturbed normal like `perturb`, but with the shape
+    /// placed where it is at the given time, so that the detail moves along
+    /// with the moving shapes
+    pub fn perturb_at(&self, shape: &dyn Shape, pt_w: Tuple, normal_w: Tuple, time: f64) -> Tuple {
+        let to_local = self.transform_inv * shape.inverse_transform_at(time);
+        let pt_p = to_local * pt_w;
+
+        match &self.source {
+            PerturbationSource::NormalMap { texture, mapping } => {
+                // Tangents follow the surface, so they go to the world's frame
+                // like the points do
+                let to_world = shape.transform_at(time) * self.transform;
+                let (u, v) = mapping.tangents(pt_p);
+                let (tangent, bitangent) = tangent_frame(to_world * u, to_world * v, normal_w);
+
+                let c = texture.sample_mapped(*mapping, pt_p);
+                let n = tangent * (2.0 * c.r() - 1.0)
+                    + bitangent * (2.0 * c.g() - 1.0)
+                    + normal_w * (2.0 * c.b() - 1.0);
+                if n.norm() == 0.0 {
+                    return normal_w;
+                }
+                n.normalized()
+            }
+            PerturbationSource::Bump {
+                noise,
+                amplitude,
+                octaves,
+            } => {
+                let height = |pt: Tuple| noise.octave_noise(pt, *octaves, 0.5);
+                let slope = |d: Tuple| {
+                    (height(pt_p + d * BUMP_STEP) - height(pt_p - d * BUMP_STEP))
+                        / (2.0 * BUMP_STEP)
+                };
+                let gradient_p = vector(
+                    slope(vector(1.0, 0.0, 0.0)),
+                    slope(vector(0.0, 1.0, 0.0)),
+                    slope(vector(0.0, 0.0, 1.0)),
+                );
+
+                // The gradient is a covector, so it goes to the world's frame
+                // like the normals do
+                let mut gradient_w = to_local.transposed() * gradient_p;
+                gradient_w.set(3, 0.0);
+
+                // Only the slope along the surface tilts the normal
+                let along = gradient_w - normal_w * gradient_w.dot(&normal_w);
+                (normal_w - along * *amplitude).normalized()
+            }
+        }
+    }
+}
+
+/// Makes the tangents orthogonal to the normal and to each other, making up
+/// the missing ones where the mapping is singular
+fn tangent_frame(u: Tuple, v: Tuple, normal: Tuple) -> (Tuple, Tuple) {
+    let mut tangent = u - normal * u.dot(&normal);
+    if tangent.norm() < 1e-9 {
+        let other = if normal.x().abs() < 0.9 {
+            vector(1.0, 0.0, 0.0)
+        } else {
+            vector(0.0, 1.0, 0.0)
+        };
+        tangent = other - normal * other.dot(&normal);
+    }
+    let tangent = tangent.normalized();
+
+    let bitangent = v - normal * v.dot(&normal) - tangent * v.dot(&tangent);
+    if bitangent.norm() < 1e-9 {
+        return (tangent, normal.cross(&tangent).normalized());
+    }
+    (tangent, bitangent.normalized())
+}
+
+/// The noise of the bumps is always the same, so it doesn't take part in the
+/// comparison
+impl PartialEq for PerturbationSource {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                PerturbationSource::NormalMap { texture, mapping },
+                PerturbationSource::NormalMap {
+                    texture: other_texture,
+                    mapping: other_mapping,
+                },
+            ) => texture == other_texture && mapping == other_mapping,
+            (
+                PerturbationSource::Bump {
+                    amplitude, octaves, ..
+                },
+                PerturbationSource::Bump {
+                    amplitude: other_amplitude,
+                    octaves: other_octaves,
+                    ..
+                },
+            ) => amplitude == other_amplitude && octaves == other_octaves,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for NormalPerturbation {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source && self.transform == other.transform
+    }
+}
@@ -1,10 +1,11 @@
 // Copyright 2022 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
+use crate::bounds::Bounds;
 use crate::matrix::Matrix;
 use crate::ray::Ray;
 use crate::shape::{LocalShape, Shape, ShapeImpl};
-use crate::tuple::{vector, Tuple};
+use crate::tuple::{point, vector, Tuple};
 use crate::utils::feq;
 
 pub struct Plane {}
@@ -23,10 +24,25 @@ impl LocalShape for Plane {
         vec![t]
     }
 
+    fn local_any_hit(&self, ray: &Ray, max_t: f64) -> bool {
+        if feq(ray.direction().y(), 0.0) {
+            return false;
+        }
+        let t = -ray.origin().y() / ray.direction().y();
+        t > 0.0 && t < max_t
+    }
+
     fn local_normal_at(&self, _pt: Tuple) -> Tuple {
         // We have an xz plane at origin, so normal is constant and points towards y
         vector(0.0, 1.0, 0.0)
     }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(
+            point(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            point(f64::INFINITY, 0.0, f64::INFINITY),
+        )
+    }
 }
 
 pub fn plane_unit() -> Box<dyn Shape> {
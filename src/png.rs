@@ -0,0 +1,407 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+//! PNG encoding of truecolor images and decoding of the non-interlaced ones
+//! See: https://www.w3.org/TR/png/
+
+use crate::canvas::ImageError;
+use crate::zlib::{zlib_compress, zlib_decompress, Compression, ZlibError};
+
+pub const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+const PNG_COLOR_TYPE_GRAY: u8 = 0;
+const PNG_COLOR_TYPE_RGB: u8 = 2;
+const PNG_COLOR_TYPE_PALETTE: u8 = 3;
+const PNG_COLOR_TYPE_GRAY_ALPHA: u8 = 4;
+const PNG_COLOR_TYPE_RGBA: u8 = 6;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PngBitDepth {
+    Eight,
+    Sixteen,
+}
+
+impl PngBitDepth {
+    pub fn bits(&self) -> u8 {
+        match self {
+            PngBitDepth::Eight => 8,
+            PngBitDepth::Sixteen => 16,
+        }
+    }
+
+    /// Number of bytes per RGB pixel
+    pub fn pixel_size(&self) -> usize {
+        3 * self.bits() as usize / 8
+    }
+}
+
+/// CRC-32 as used by the PNG chunks
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0_u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+
+    let mut crc = 0xffffffff_u32;
+    for byte in data.iter() {
+        crc = table[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xffffffff
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Predicts a byte from its left (a), upper (b) and upper-left (c)
+/// neighbors using one of the five standard filters
+fn predict(filter: u8, a: u8, b: u8, c: u8) -> u8 {
+    match filter {
+        0 => 0,
+        1 => a,
+        2 => b,
+        3 => ((a as u16 + b as u16) / 2) as u8,
+        _ => paeth(a, b, c),
+    }
+}
+
+fn filter_row(filter: u8, row: &[u8], prev: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    out.clear();
+    out.push(filter);
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        out.push(row[i].wrapping_sub(predict(filter, a, prev[i], c)));
+    }
+}
+
+/// Reverses the filter of a scanline in place
+fn unfilter_row(filter: u8, row: &mut [u8], prev: &[u8], bpp: usize) -> Result<(), ImageError> {
+    if filter > 4 {
+        return Err(ImageError::Corrupt(format!("unknown filter: {}", filter)));
+    }
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        row[i] = row[i].wrapping_add(predict(filter, a, prev[i], c));
+    }
+    Ok(())
+}
+
+/// Decoded PNG image with the samples normalized to the [0, 1] range
+#[derive(Debug, Clone, PartialEq)]
+pub struct PngImage {
+    pub width: usize,
+    pub height: usize,
+    /// RGB triples stored row by row; the alpha channel is dropped
+    pub pixels: Vec<[f64; 3]>,
+}
+
+struct PngHeader {
+    width: usize,
+    height: usize,
+    depth: u8,
+    color_type: u8,
+}
+
+impl PngHeader {
+    fn parse(data: &[u8]) -> Result<PngHeader, ImageError> {
+        if data.len() != 13 {
+            return Err(ImageError::BadHeader("IHDR has a wrong size".to_string()));
+        }
+        let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let (depth, color_type) = (data[8], data[9]);
+        if width == 0 || height == 0 {
+            return Err(ImageError::BadHeader(format!(
+                "invalid dimensions: {}x{}",
+                width, height
+            )));
+        }
+
+        let valid_depths: &[u8] = match color_type {
+            PNG_COLOR_TYPE_GRAY => &[1, 2, 4, 8, 16],
+            PNG_COLOR_TYPE_PALETTE => &[1, 2, 4, 8],
+            PNG_COLOR_TYPE_RGB | PNG_COLOR_TYPE_GRAY_ALPHA | PNG_COLOR_TYPE_RGBA => &[8, 16],
+            _ => {
+                return Err(ImageError::BadHeader(format!(
+                    "unknown color type: {}",
+                    color_type
+                )))
+            }
+        };
+        if !valid_depths.contains(&depth) {
+            return Err(ImageError::BadHeader(format!(
+                "invalid bit depth {} for color type {}",
+                depth, color_type
+            )));
+        }
+        if data[10] != 0 || data[11] != 0 {
+            return Err(ImageError::Unsupported(
+                "unknown compression or filter method".to_string(),
+            ));
+        }
+        if data[12] != 0 {
+            return Err(ImageError::Unsupported("interlaced images".to_string()));
+        }
+        let header = PngHeader {
+            width,
+            height,
+            depth,
+            color_type,
+        };
+
+        // The sizes of the raw data and of the pixels need to be
+        // representable before we trust them for any allocation
+        let too_large = || ImageError::BadHeader(format!("image too large: {}x{}", width, height));
+        let stride = header.stride().ok_or_else(too_large)?;
+        (stride + 1).checked_mul(height).ok_or_else(too_large)?;
+        width.checked_mul(height).ok_or_else(too_large)?;
+        Ok(header)
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            PNG_COLOR_TYPE_RGB => 3,
+            PNG_COLOR_TYPE_GRAY_ALPHA => 2,
+            PNG_COLOR_TYPE_RGBA => 4,
+            _ => 1,
+        }
+    }
+
+    /// Number of bytes of a scanline without the filter byte, if it fits
+    fn stride(&self) -> Option<usize> {
+        let bits = self
+            .width
+            .checked_mul(self.channels() * self.depth as usize)?;
+        Some(bits.div_ceil(8))
+    }
+
+    /// Distance in bytes to the corresponding byte of the previous pixel, at
+    /// least one for the sub-byte depths
+    fn bpp(&self) -> usize {
+        ((self.channels() * self.depth as usize) / 8).max(1)
+    }
+}
+
+/// Returns the `index`-th sample of the scanline
+fn sample(row: &[u8], depth: u8, index: usize) -> u16 {
+    match depth {
+        16 => u16::from_be_bytes([row[2 * index], row[2 * index + 1]]),
+        8 => row[index] as u16,
+        _ => {
+            let per_byte = 8 / depth as usize;
+            let shift = 8 - depth as usize * (index % per_byte + 1);
+            ((row[index / per_byte] >> shift) & ((1 << depth) - 1)) as u16
+        }
+    }
+}
+
+/// Decodes a non-interlaced PNG file of any color type and bit depth
+pub fn png_decode(data: &[u8]) -> Result<PngImage, ImageError> {
+    if data.len() < PNG_SIGNATURE.len() || data[..PNG_SIGNATURE.len()] != PNG_SIGNATURE {
+        return Err(ImageError::BadMagic);
+    }
+
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut idat = Vec::new();
+    let mut pos = PNG_SIGNATURE.len();
+    loop {
+        if pos + 8 > data.len() {
+            return Err(ImageError::Truncated);
+        }
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        let len = len as usize;
+        let kind = &data[pos + 4..pos + 8];
+        if pos + 12 + len > data.len() {
+            return Err(ImageError::Truncated);
+        }
+        let body = &data[pos + 8..pos + 8 + len];
+        let crc = &data[pos + 8 + len..pos + 12 + len];
+        if u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]])
+            != crc32(&data[pos + 4..pos + 8 + len])
+        {
+            return Err(ImageError::Corrupt(format!(
+                "CRC mismatch in chunk {}",
+                String::from_utf8_lossy(kind)
+            )));
+        }
+        pos += 12 + len;
+
+        match kind {
+            b"IHDR" => header = Some(PngHeader::parse(body)?),
+            b"PLTE" => palette = body.to_vec(),
+            b"IDAT" => idat.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {
+                // Bit 5 of the first byte marks the ancillary chunks that we
+                // may safely skip
+                if kind[0] & 0x20 == 0 {
+                    return Err(ImageError::Unsupported(format!(
+                        "critical chunk {}",
+                        String::from_utf8_lossy(kind)
+                    )));
+                }
+            }
+        }
+    }
+
+    let header = match header {
+        Some(header) => header,
+        None => return Err(ImageError::BadHeader("missing IHDR chunk".to_string())),
+    };
+    if header.color_type == PNG_COLOR_TYPE_PALETTE && (palette.is_empty() || palette.len() % 3 != 0)
+    {
+        return Err(ImageError::Corrupt(
+            "missing or invalid palette".to_string(),
+        ));
+    }
+
+    let mut raw = zlib_decompress(&idat).map_err(|err| match err {
+        ZlibError::Truncated => ImageError::Truncated,
+        err => ImageError::Corrupt(err.to_string()),
+    })?;
+    // The header has checked that the sizes don't overflow
+    let stride = header.stride().unwrap_or_default();
+    if raw.len() < (stride + 1) * header.height {
+        return Err(ImageError::Truncated);
+    }
+
+    let max = ((1_u32 << header.depth) - 1) as f64;
+    let channels = header.channels();
+    let mut pixels = Vec::with_capacity(header.width * header.height);
+    let mut prev = vec![0_u8; stride];
+    for y in 0..header.height {
+        let start = y * (stride + 1);
+        let filter = raw[start];
+        let row = &mut raw[start + 1..start + 1 + stride];
+        unfilter_row(filter, row, &prev, header.bpp())?;
+
+        for x in 0..header.width {
+            let s = |c: usize| sample(row, header.depth, x * channels + c);
+            let pixel = match header.color_type {
+                PNG_COLOR_TYPE_PALETTE => {
+                    let index = s(0) as usize * 3;
+                    if index + 3 > palette.len() {
+                        return Err(ImageError::Corrupt(format!(
+                            "palette index out of range: {}",
+                            index / 3
+                        )));
+                    }
+                    [
+                        palette[index] as f64 / 255.0,
+                        palette[index + 1] as f64 / 255.0,
+                        palette[index + 2] as f64 / 255.0,
+                    ]
+                }
+                PNG_COLOR_TYPE_GRAY | PNG_COLOR_TYPE_GRAY_ALPHA => {
+                    let v = s(0) as f64 / max;
+                    [v, v, v]
+                }
+                _ => [s(0) as f64 / max, s(1) as f64 / max, s(2) as f64 / max],
+            };
+            pixels.push(pixel);
+        }
+        prev.copy_from_slice(row);
+    }
+
+    Ok(PngImage {
+        width: header.width,
+        height: header.height,
+        pixels,
+    })
+}
+
+/// Encodes the raw RGB scanlines as a PNG file; the samples of 16-bit images
+/// need to be big-endian
+///
+/// Each scanline is filtered with the filter minimizing the sum of absolute
+/// differences, which is the heuristic recommended by the specification.
+pub fn png_encode(width: usize, height: usize, depth: PngBitDepth, pixels: &[u8]) -> Vec<u8> {
+    let stride = width * depth.pixel_size();
+    if pixels.len() != stride * height {
+        panic!(
+            "Pixel data size mismatch: got {} bytes, expected {} for {}x{}",
+            pixels.len(),
+            stride * height,
+            width,
+            height
+        );
+    }
+
+    let bpp = depth.pixel_size();
+    let zero = vec![0_u8; stride];
+    let mut filtered = Vec::with_capacity((stride + 1) * height);
+    let mut best = Vec::with_capacity(stride + 1);
+    let mut candidate = Vec::with_capacity(stride + 1);
+    for y in 0..height {
+        let row = &pixels[y * stride..(y + 1) * stride];
+        let prev = if y == 0 {
+            &zero[..]
+        } else {
+            &pixels[(y - 1) * stride..y * stride]
+        };
+
+        let mut best_score = u64::MAX;
+        for filter in 0..5 {
+            filter_row(filter, row, prev, bpp, &mut candidate);
+            let score = candidate[1..]
+                .iter()
+                .map(|v| (*v as i8).unsigned_abs() as u64)
+                .sum();
+            if score < best_score {
+                best_score = score;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        filtered.extend_from_slice(&best);
+    }
+
+    let mut png = Vec::new();
+    png.extend_from_slice(&PNG_SIGNATURE);
+
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+    ihdr.push(depth.bits());
+    ihdr.push(PNG_COLOR_TYPE_RGB);
+    ihdr.push(0); // compression: deflate
+    ihdr.push(0); // filter method: adaptive
+    ihdr.push(0); // no interlace
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(
+        &mut png,
+        b"IDAT",
+        &zlib_compress(&filtered, Compression::Fixed),
+    );
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
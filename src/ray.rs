@@ -8,11 +8,26 @@ use crate::tuple::Tuple;
 pub struct Ray {
     origin: Tuple,
     direction: Tuple,
+    time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Tuple, direction: Tuple) -> Ray {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            time: 0.0,
+        }
+    }
+
+    /// Returns the same ray cast at the given time; the moving shapes are
+    /// intersected where they are at that time
+    pub fn with_time(&self, time: f64) -> Ray {
+        Ray {
+            origin: self.origin,
+            direction: self.direction,
+            time,
+        }
     }
 
     pub fn origin(&self) -> Tuple {
@@ -23,6 +38,10 @@ impl Ray {
         self.direction
     }
 
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
     pub fn position(&self, t: f64) -> Tuple {
         self.origin + (t * self.direction)
     }
@@ -31,6 +50,7 @@ impl Ray {
         Ray {
             origin: transform * self.origin,
             direction: transform * self.direction,
+            time: self.time,
         }
     }
 }
@@ -0,0 +1,48 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+/// Small and fast pseudo-random number generator (SplitMix64); not suitable
+/// for anything but sampling
+/// See: https://prng.di.unimi.it/splitmix64.c
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Creates a generator for a stream identified by the keys, like pixel
+    /// coordinates, so that the streams don't depend on the order in which
+    /// they are used
+    pub fn from_keys(seed: u64, keys: &[u64]) -> Rng {
+        let mut rng = Rng::new(seed);
+        for key in keys.iter() {
+            rng.state = rng.next_u64() ^ key.wrapping_mul(0xd1b54a32d192ed03);
+        }
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in the range of [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// Puts the items in a random order (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
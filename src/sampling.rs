@@ -0,0 +1,143 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::rng::Rng;
+
+use std::f64::consts::PI;
+
+/// Placement of the samples within the strata of a pixel
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SamplingPattern {
+    /// The centers of the cells of a regular grid
+    #[default]
+    Regular,
+    /// A random point within each of the cells, a.k.a. stratified sampling
+    Jittered,
+}
+
+impl SamplingPattern {
+    /// Returns the positions of `n * n` samples within a unit square
+    pub fn samples(&self, n: usize, rng: &mut Rng) -> Vec<(f64, f64)> {
+        let mut samples = Vec::with_capacity(n * n);
+        for j in 0..n {
+            for i in 0..n {
+                let (ox, oy) = match self {
+                    SamplingPattern::Regular => (0.5, 0.5),
+                    SamplingPattern::Jittered => (rng.next_f64(), rng.next_f64()),
+                };
+                samples.push(((i as f64 + ox) / n as f64, (j as f64 + oy) / n as f64));
+            }
+        }
+        samples
+    }
+}
+
+/// Maps a point of the unit square onto the unit disk preserving the
+/// relative areas, so that a stratified grid stays stratified
+/// See: https://www.pbr-book.org/3ed-2018/Monte_Carlo_Integration/2D_Sampling_with_Multidimensional_Transformations
+pub fn concentric_disk(s: f64, t: f64) -> (f64, f64) {
+    let (a, b) = (2.0 * s - 1.0, 2.0 * t - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, theta) = match a.abs() > b.abs() {
+        true => (a, PI / 4.0 * (b / a)),
+        false => (b, PI / 2.0 - PI / 4.0 * (a / b)),
+    };
+    (r * theta.cos(), r * theta.sin())
+}
+
+/// Weighs the samples by their distance from the center of the pixel; the
+/// samples are spread over the whole support of the filter, so the wider
+/// filters take in the neighborhood of the pixel
+/// See: https://www.pbr-book.org/3ed-2018/Sampling_and_Reconstruction/Image_Reconstruction
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ReconstructionFilter {
+    /// Equal weights within the pixel
+    #[default]
+    Box,
+    /// Weights falling off linearly up to the distance of one pixel
+    Tent,
+    /// Gaussian falloff up to the distance of 1.5 pixels
+    Gaussian,
+    /// Mitchell-Netravali filter with B = C = 1/3 up to the distance of two
+    /// pixels; it has negative lobes that sharpen the image a bit
+    Mitchell,
+}
+
+const GAUSSIAN_ALPHA: f64 = 2.0;
+
+impl ReconstructionFilter {
+    /// Extent of the filter in pixels in each direction from the center
+    pub fn radius(&self) -> f64 {
+        match self {
+            ReconstructionFilter::Box => 0.5,
+            ReconstructionFilter::Tent => 1.0,
+            ReconstructionFilter::Gaussian => 1.5,
+            ReconstructionFilter::Mitchell => 2.0,
+        }
+    }
+
+    /// Weight of a sample at the given offset in pixels from the center
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    fn weight_1d(&self, d: f64) -> f64 {
+        let r = self.radius();
+        let d = d.abs();
+        if d > r {
+            return 0.0;
+        }
+        match self {
+            ReconstructionFilter::Box => 1.0,
+            ReconstructionFilter::Tent => 1.0 - d / r,
+            ReconstructionFilter::Gaussian => {
+                ((-GAUSSIAN_ALPHA * d * d).exp() - (-GAUSSIAN_ALPHA * r * r).exp()).max(0.0)
+            }
+            ReconstructionFilter::Mitchell => {
+                let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+                let x = 2.0 * d / r;
+                let v = if x < 1.0 {
+                    (12.0 - 9.0 * b - 6.0 * c) * x.powi(3)
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x.powi(2)
+                        + (6.0 - 2.0 * b)
+                } else {
+                    (-b - 6.0 * c) * x.powi(3)
+                        + (6.0 * b + 30.0 * c) * x.powi(2)
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c)
+                };
+                v / 6.0
+            }
+        }
+    }
+}
+
+/// Settings of the adaptive anti-aliasing: the pixels start with the rays
+/// through their corners and the ones where the corner colors differ by more
+/// than the threshold in any channel are split into quadrants recursively
+/// See: https://en.wikipedia.org/wiki/Adaptive_sampling
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AdaptiveSampling {
+    pub threshold: f64,
+    pub max_depth: u8,
+}
+
+pub const DEFAULT_ADAPTIVE_THRESHOLD: f64 = 0.1;
+pub const DEFAULT_ADAPTIVE_DEPTH: u8 = 3;
+
+impl AdaptiveSampling {
+    pub fn new(threshold: f64, max_depth: u8) -> AdaptiveSampling {
+        AdaptiveSampling {
+            threshold,
+            max_depth,
+        }
+    }
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        AdaptiveSampling::new(DEFAULT_ADAPTIVE_THRESHOLD, DEFAULT_ADAPTIVE_DEPTH)
+    }
+}
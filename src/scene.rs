@@ -0,0 +1,994 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+//! Scene description files in the spirit of the YAML scenes of The Ray
+//! Tracer Challenge
+//!
+//! A scene is a sequence of items, each one either adding something to the
+//! scene or defining a reusable value:
+//!
+//! ```yaml
+//! - add: camera
+//!   width: 320
+//!   height: 240
+//!   field-of-view: 1.0472
+//!   from: [0, 1.5, -5]
+//!   to: [0, 1, 0]
+//!   up: [0, 1, 0]
+//!
+//! - add: light
+//!   at: [-10, 10, -10]
+//!   intensity: [1, 1, 1]
+//!
+//! - add: light
+//!   type: rect
+//!   corner: [-1, 5, -1]
+//!   uvec: [2, 0, 0]
+//!   vvec: [0, 0, 2]
+//!   usteps: 4
+//!   vsteps: 4
+//!   intensity: [0.5, 0.5, 0.5]
+//!
+//! - define: shiny
+//!   value:
+//!     specular: 0.9
+//!     reflective: 0.3
+//!
+//! - define: red-shiny
+//!   extend: shiny
+//!   value:
+//!     color: [1, 0, 0]
+//!
+//! - add: sphere
+//!   material: red-shiny
+//!   transform:
+//!     - [scale, 0.5, 0.5, 0.5]
+//!     - [translate, 0, 1, 0]
+//! ```
+//!
+//! The transforms are applied in the order they are listed. The shapes are:
+//! `sphere`, `plane`, `cube`, `cylinder` and `cone` (with `min`, `max` and
+//! `closed`), `group` (with `children`), `csg` (with `operation`, `left` and
+//! `right`), and `obj` (with `file` relative to the scene file). Shapes with
+//! no material of their own inherit the material of their parent. The shapes
+//! with an `end-transform` move from their `transform` at the time 0 to the
+//! end one at the time 1, smeared over the camera's `shutter` interval, eg.
+//! `[0, 1]`; the shutter is closed by default.
+//!
+//! Besides the procedural patterns, the materials may use `image` patterns
+//! with a `file` (PNG or PPM, relative to the scene file), a `mapping`
+//! (`planar`, `spherical`, `cylindrical` or `cube`) and a `wrap` mode (`wrap`
+//! or `clamp`). The surface detail comes from a `normal-map` (with `file`,
+//! `mapping` and `transform`) or from the noise `bump` (with `amplitude`,
+//! `octaves` and `transform`).
+//!
+//! The camera may have a `projection`: `perspective` (the default),
+//! `orthographic` (with `view-width` instead of `field-of-view`), `fisheye`
+//! or `equirectangular` (with no `field-of-view`).
+//!
+//! The rays missing all the shapes see the background, black by default:
+//!
+//! ```yaml
+//! - add: background
+//!   type: gradient
+//!   bottom: [1, 1, 1]
+//!   top: [0.5, 0.7, 1]
+//! ```
+//!
+//! The other types are `solid` (with `color`), and `equirectangular` and
+//! `cube-map` (with `file`).
+//!
+//! The shapes cast opaque shadows by default; the transparent ones may let
+//! the light through instead:
+//!
+//! ```yaml
+//! - add: shadows
+//!   transparent: true
+//! ```
+//!
+//! Setting `enabled` to `false` turns the shadows off altogether.
+
+use crate::background::Background;
+use crate::camera::{Camera, Projection};
+use crate::canvas::Canvas;
+use crate::cone::cone_min_max;
+use crate::csg::{csg, CsgOperation};
+use crate::cube::cube_unit;
+use crate::cylinder::cylinder_min_max;
+use crate::group::Group;
+use crate::light::{directional_light, AreaLight, Attenuation, Light, PointLight, SpotLight};
+use crate::material::Material;
+use crate::matrix::Matrix;
+use crate::obj::ObjFile;
+use crate::pattern::Pattern;
+use crate::pattern_blended::blended_pattern;
+use crate::pattern_checker::checker_pattern;
+use crate::pattern_gradient::gradient_pattern;
+use crate::pattern_image::image_pattern;
+use crate::pattern_noise::noise_pattern;
+use crate::pattern_radial_gradient::radial_gradient_pattern;
+use crate::pattern_ring::ring_pattern;
+use crate::pattern_solid::solid_pattern;
+use crate::pattern_stripe::stripe_pattern;
+use crate::perturbation::NormalPerturbation;
+use crate::plane::plane_unit;
+use crate::shape::Shape;
+use crate::sphere::sphere_unit;
+use crate::texture::{Texture, TextureWrap, UvMapping};
+use crate::transformations::{
+    rotation_x, rotation_y, rotation_z, scaling, shearing, translation, view_transform,
+};
+use crate::tuple::{color, point, vector, Tuple};
+use crate::world::World;
+use crate::yaml::{parse_yaml, YamlNode, YamlValue};
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Guards against the definitions referring to themselves
+const MAX_DEFINE_DEPTH: usize = 32;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "{}", err),
+            SceneError::Parse {
+                file,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", file, line, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(err: std::io::Error) -> SceneError {
+        SceneError::Io(err)
+    }
+}
+
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera,
+}
+
+impl Scene {
+    /// Builds the scene described by the input; the file name is used in the
+    /// error messages and to resolve the paths of the files the scene refers
+    /// to
+    pub fn parse(input: &str, file_name: &str) -> Result<Scene, SceneError> {
+        let doc = parse_yaml(input).map_err(|err| SceneError::Parse {
+            file: file_name.to_string(),
+            line: err.line,
+            message: err.message,
+        })?;
+
+        let mut loader = SceneLoader {
+            file: file_name.to_string(),
+            base_dir: Path::new(file_name)
+                .parent()
+                .map(|p| p.to_path_buf())
+                .unwrap_or_default(),
+            defines: HashMap::new(),
+            world: World::empty(),
+            camera: None,
+        };
+        loader.load(&doc)?;
+
+        match loader.camera {
+            Some(camera) => Ok(Scene {
+                world: loader.world,
+                camera,
+            }),
+            None => Err(loader.error(&doc, "the scene has no camera")),
+        }
+    }
+
+    pub fn load(file_path: &str) -> Result<Scene, SceneError> {
+        let input = fs::read_to_string(file_path)?;
+        Scene::parse(input.as_str(), file_path)
+    }
+}
+
+struct SceneLoader {
+    file: String,
+    base_dir: PathBuf,
+    defines: HashMap<String, YamlNode>,
+    world: World,
+    camera: Option<Camera>,
+}
+
+impl SceneLoader {
+    fn error(&self, node: &YamlNode, message: &str) -> SceneError {
+        SceneError::Parse {
+            file: self.file.clone(),
+            line: node.line,
+            message: message.to_string(),
+        }
+    }
+
+    fn load(&mut self, doc: &YamlNode) -> Result<(), SceneError> {
+        let items = match &doc.value {
+            YamlValue::Sequence(items) => items,
+            YamlValue::Mapping(entries) if entries.is_empty() => return Ok(()),
+            _ => return Err(self.error(doc, "the scene must be a sequence of items")),
+        };
+
+        for item in items.iter() {
+            if item.as_mapping().is_none() {
+                return Err(self.error(item, "expected a mapping"));
+            }
+            if let Some(name) = item.get("define") {
+                self.define(item, name)?;
+                continue;
+            }
+
+            let kind = self.required_str(item, "add")?;
+            match kind {
+                "camera" => {
+                    let camera = self.camera(item)?;
+                    self.camera = Some(camera);
+                }
+                "light" => {
+                    let light = self.light(item)?;
+                    self.world.lights.push(light);
+                }
+                "background" => {
+                    let background = self.background(item)?;
+                    self.world.background = background;
+                }
+                "shadows" => self.shadows(item)?,
+                _ => {
+                    let shape = self.shape(item, None, 0)?;
+                    self.world.shapes.push(shape);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores a definition; extending a mapping merges it with the extended
+    /// one with the new keys taking precedence
+    fn define(&mut self, item: &YamlNode, name: &YamlNode) -> Result<(), SceneError> {
+        self.check_keys(item, &["define", "extend", "value"])?;
+        let name = match name.as_str() {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => return Err(self.error(name, "the name of a definition must be a string")),
+        };
+        let mut value = self.required(item, "value")?.clone();
+
+        if let Some(parent) = item.get("extend") {
+            let parent_value = self.lookup(parent)?;
+            let (base, own) = match (parent_value.as_mapping(), value.as_mapping()) {
+                (Some(base), Some(own)) => (base, own),
+                _ => return Err(self.error(parent, "only mappings can be extended")),
+            };
+            let mut merged: Vec<(String, YamlNode)> = base
+                .iter()
+                .filter(|(k, _)| own.iter().all(|(ok, _)| ok != k))
+                .cloned()
+                .collect();
+            merged.extend(own.iter().cloned());
+            value = YamlNode::new(value.line, YamlValue::Mapping(merged));
+        }
+
+        self.defines.insert(name, value);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &YamlNode) -> Result<&YamlNode, SceneError> {
+        let key = name
+            .as_str()
+            .ok_or_else(|| self.error(name, "expected the name of a definition"))?;
+        self.defines
+            .get(key)
+            .ok_or_else(|| self.error(name, &format!("undefined name: {}", key)))
+    }
+
+    fn check_keys(&self, node: &YamlNode, allowed: &[&str]) -> Result<(), SceneError> {
+        for (key, value) in node.as_mapping().unwrap_or_default().iter() {
+            if !allowed.contains(&key.as_str()) {
+                return Err(self.error(value, &format!("unknown key: {}", key)));
+            }
+        }
+        Ok(())
+    }
+
+    fn required<'a>(&self, node: &'a YamlNode, key: &str) -> Result<&'a YamlNode, SceneError> {
+        node.get(key)
+            .ok_or_else(|| self.error(node, &format!("missing key: {}", key)))
+    }
+
+    fn required_str<'a>(&self, node: &'a YamlNode, key: &str) -> Result<&'a str, SceneError> {
+        let value = self.required(node, key)?;
+        value
+            .as_str()
+            .ok_or_else(|| self.error(value, &format!("{} must be a string", key)))
+    }
+
+    fn number(&self, node: &YamlNode) -> Result<f64, SceneError> {
+        node.as_str()
+            .and_then(|s| s.parse::<f64>().ok())
+            .ok_or_else(|| self.error(node, "expected a number"))
+    }
+
+    fn integer(&self, node: &YamlNode) -> Result<usize, SceneError> {
+        node.as_str()
+            .and_then(|s| s.parse::<usize>().ok())
+            .ok_or_else(|| self.error(node, "expected a non-negative integer"))
+    }
+
+    fn boolean(&self, node: &YamlNode) -> Result<bool, SceneError> {
+        match node.as_str() {
+            Some("true") => Ok(true),
+            Some("false") => Ok(false),
+            _ => Err(self.error(node, "expected true or false")),
+        }
+    }
+
+    fn numbers(&self, node: &YamlNode) -> Result<Vec<f64>, SceneError> {
+        let items = node
+            .as_sequence()
+            .ok_or_else(|| self.error(node, "expected a sequence of numbers"))?;
+        items.iter().map(|n| self.number(n)).collect()
+    }
+
+    fn triple(&self, node: &YamlNode) -> Result<[f64; 3], SceneError> {
+        let v = self.numbers(node)?;
+        if v.len() != 3 {
+            return Err(self.error(node, "expected three numbers"));
+        }
+        Ok([v[0], v[1], v[2]])
+    }
+
+    fn camera(&self, item: &YamlNode) -> Result<Camera, SceneError> {
+        self.check_keys(
+            item,
+            &[
+                "add",
+                "width",
+                "height",
+                "field-of-view",
+                "from",
+                "to",
+                "up",
+                "aperture",
+                "focal-distance",
+                "projection",
+                "view-width",
+                "shutter",
+            ],
+        )?;
+        let width = self.integer(self.required(item, "width")?)?;
+        let height = self.integer(self.required(item, "height")?)?;
+        if width == 0 || height == 0 {
+            return Err(self.error(item, "the camera needs a non-zero size"));
+        }
+        let projection = self.projection(item)?;
+
+        // Only the perspective and the fisheye have a field of view
+        let fov = match projection {
+            Projection::Perspective | Projection::Fisheye => {
+                self.number(self.required(item, "field-of-view")?)?
+            }
+            _ => match item.get("field-of-view") {
+                Some(node) => self.number(node)?,
+                None => PI / 2.0,
+            },
+        };
+        let from = point_from(self.triple(self.required(item, "from")?)?);
+        let to = point_from(self.triple(self.required(item, "to")?)?);
+        let up = vector_from(self.triple(self.required(item, "up")?)?);
+
+        let view = view_transform(from, to, up);
+        if view.det() == 0.0 {
+            let msg = "the camera needs distinct from and to, and up not parallel to the view";
+            return Err(self.error(item, msg));
+        }
+
+        let mut camera = Camera::new(width, height, fov);
+        camera.set_projection(projection);
+        camera.set_transform(view);
+
+        // The lens focuses on the point the camera looks at unless told
+        // otherwise
+        if let Some(aperture) = item.get("aperture") {
+            match self.number(aperture)? {
+                a if a >= 0.0 => camera.set_aperture(a),
+                _ => return Err(self.error(aperture, "aperture must be non-negative")),
+            }
+            camera.set_focal_distance((to - from).norm());
+        }
+        if let Some(shutter) = item.get("shutter") {
+            let v = self.numbers(shutter)?;
+            if v.len() != 2 || v[0] > v[1] {
+                let msg = "shutter must be the opening and the closing time";
+                return Err(self.error(shutter, msg));
+            }
+            camera.set_shutter(v[0], v[1]);
+        }
+        if let Some(distance) = item.get("focal-distance") {
+            match self.number(distance)? {
+                d if d > 0.0 => camera.set_focal_distance(d),
+                _ => return Err(self.error(distance, "focal-distance must be positive")),
+            }
+        }
+        Ok(camera)
+    }
+
+    /// Reads the optional `projection` of the camera along with the
+    /// `view-width` of the orthographic one
+    fn projection(&self, item: &YamlNode) -> Result<Projection, SceneError> {
+        let node = match item.get("projection") {
+            Some(node) => node,
+            None => return Ok(Projection::Perspective),
+        };
+        let kind = node
+            .as_str()
+            .ok_or_else(|| self.error(node, "projection must be a string"))?;
+        match kind {
+            "perspective" => Ok(Projection::Perspective),
+            "orthographic" => {
+                let width_node = self.required(item, "view-width")?;
+                match self.number(width_node)? {
+                    width if width > 0.0 => Ok(Projection::Orthographic { width }),
+                    _ => Err(self.error(width_node, "view-width must be positive")),
+                }
+            }
+            "fisheye" => Ok(Projection::Fisheye),
+            "equirectangular" => Ok(Projection::Equirectangular),
+            _ => Err(self.error(node, &format!("unknown projection: {}", kind))),
+        }
+    }
+
+    /// Lights of the `point` type (the default) have a position `at`;
+    /// `directional` ones have a `direction`; `spot` ones have a position
+    /// `at`, a `direction`, a cone `angle` and a `falloff` exponent; the area
+    /// lights are: `rect` with a `corner` and the edges `uvec` and `vvec`
+    /// split into `usteps` and `vsteps` cells, `disk` with the center `at`,
+    /// a `normal`, a `radius` and `steps`, and `sphere` with the center `at`,
+    /// a `radius` and `steps`; all but the directional lights may have an
+    /// `attenuation` and a `range`
+    fn light(&self, item: &YamlNode) -> Result<Box<dyn Light>, SceneError> {
+        let kind = match item.get("type") {
+            Some(node) => node
+                .as_str()
+                .ok_or_else(|| self.error(node, "type must be a string"))?,
+            None => "point",
+        };
+        let keys: &[&str] = match kind {
+            "point" => &["at"],
+            "directional" => &["direction"],
+            "spot" => &["at", "direction", "angle", "falloff"],
+            "rect" => &["corner", "uvec", "vvec", "usteps", "vsteps", "jitter"],
+            "disk" => &["at", "normal", "radius", "steps", "jitter"],
+            "sphere" => &["at", "radius", "steps", "jitter"],
+            _ => {
+                let node = item.get("type").unwrap();
+                return Err(self.error(node, &format!("unknown light type: {}", kind)));
+            }
+        };
+        let mut allowed = vec!["add", "type", "intensity"];
+        allowed.extend_from_slice(keys);
+        if kind != "directional" {
+            allowed.extend_from_slice(&["attenuation", "range"]);
+        }
+        self.check_keys(item, &allowed)?;
+
+        let intensity = color_from(self.triple(self.required(item, "intensity")?)?);
+        let vec = |key| -> Result<Tuple, SceneError> {
+            Ok(vector_from(self.triple(self.required(item, key)?)?))
+        };
+        let pnt = |key| -> Result<Tuple, SceneError> {
+            Ok(point_from(self.triple(self.required(item, key)?)?))
+        };
+        let num = |key| -> Result<f64, SceneError> { self.number(self.required(item, key)?) };
+        let steps = |key| -> Result<usize, SceneError> {
+            let node = self.required(item, key)?;
+            match self.integer(node)? {
+                0 => Err(self.error(node, &format!("{} must be positive", key))),
+                n => Ok(n),
+            }
+        };
+
+        let attenuation = match item.get("attenuation") {
+            Some(node) => self.attenuation(node)?,
+            None => Attenuation::None,
+        };
+        let range = match item.get("range") {
+            Some(node) => self.number(node)?,
+            None => f64::INFINITY,
+        };
+
+        let mut area = match kind {
+            "point" => {
+                let mut light = PointLight::new(pnt("at")?, intensity);
+                light.attenuation = attenuation;
+                light.range = range;
+                return Ok(Box::new(light));
+            }
+            "directional" => return Ok(directional_light(vec("direction")?, intensity)),
+            "spot" => {
+                let falloff = match item.get("falloff") {
+                    Some(v) => self.number(v)?,
+                    None => 0.0,
+                };
+                let mut light = SpotLight::new(
+                    pnt("at")?,
+                    vec("direction")?,
+                    num("angle")?,
+                    falloff,
+                    intensity,
+                );
+                light.attenuation = attenuation;
+                light.range = range;
+                return Ok(Box::new(light));
+            }
+            "rect" => AreaLight::rect(
+                pnt("corner")?,
+                vec("uvec")?,
+                steps("usteps")?,
+                vec("vvec")?,
+                steps("vsteps")?,
+                intensity,
+            ),
+            "disk" => AreaLight::disk(
+                pnt("at")?,
+                vec("normal")?,
+                num("radius")?,
+                steps("steps")?,
+                intensity,
+            ),
+            _ => AreaLight::sphere(pnt("at")?, num("radius")?, steps("steps")?, intensity),
+        };
+        if let Some(jitter) = item.get("jitter") {
+            area.jitter = self.boolean(jitter)?;
+        }
+        area.attenuation = attenuation;
+        area.range = range;
+        Ok(Box::new(area))
+    }
+
+    /// Attenuation is either `none`, `inverse-square` or a list of the
+    /// constant, linear and quadratic coefficients
+    fn attenuation(&self, node: &YamlNode) -> Result<Attenuation, SceneError> {
+        match node.as_str() {
+            Some("none") => Ok(Attenuation::None),
+            Some("inverse-square") => Ok(Attenuation::InverseSquare),
+            Some(name) => Err(self.error(node, &format!("unknown attenuation: {}", name))),
+            None => {
+                let [constant, linear, quadratic] = self.triple(node)?;
+                if constant + linear + quadratic <= 0.0
+                    || constant < 0.0
+                    || linear < 0.0
+                    || quadratic < 0.0
+                {
+                    return Err(self.error(
+                        node,
+                        "the attenuation coefficients must be non-negative and not all zero",
+                    ));
+                }
+                Ok(Attenuation::Polynomial {
+                    constant,
+                    linear,
+                    quadratic,
+                })
+            }
+        }
+    }
+
+    /// Transforms are lists of operations and names of the defined lists;
+    /// the operations are applied in the order of the list
+    fn transform(&self, node: &YamlNode, depth: usize) -> Result<Matrix, SceneError> {
+        if depth > MAX_DEFINE_DEPTH {
+            return Err(self.error(node, "definitions nested too deeply"));
+        }
+        let items = node
+            .as_sequence()
+            .ok_or_else(|| self.error(node, "a transform must be a sequence"))?;
+
+        let mut m = Matrix::one();
+        for item in items.iter() {
+            let op = match &item.value {
+                YamlValue::Scalar(_) => self.transform(self.lookup(item)?, depth + 1)?,
+                YamlValue::Sequence(args) if !args.is_empty() => {
+                    let name = args[0]
+                        .as_str()
+                        .ok_or_else(|| self.error(item, "expected an operation name"))?;
+                    let v: Vec<f64> = args[1..]
+                        .iter()
+                        .map(|a| self.number(a))
+                        .collect::<Result<_, _>>()?;
+                    let expect = |n: usize| {
+                        if v.len() != n {
+                            return Err(self.error(
+                                item,
+                                &format!("{} takes {} arguments, got {}", name, n, v.len()),
+                            ));
+                        }
+                        Ok(())
+                    };
+                    match name {
+                        "translate" => expect(3).map(|_| translation(v[0], v[1], v[2]))?,
+                        "scale" => expect(3).map(|_| scaling(v[0], v[1], v[2]))?,
+                        "rotate-x" => expect(1).map(|_| rotation_x(v[0]))?,
+                        "rotate-y" => expect(1).map(|_| rotation_y(v[0]))?,
+                        "rotate-z" => expect(1).map(|_| rotation_z(v[0]))?,
+                        "shear" => {
+                            expect(6).map(|_| shearing(v[0], v[1], v[2], v[3], v[4], v[5]))?
+                        }
+                        _ => return Err(self.error(item, &format!("unknown transform: {}", name))),
+                    }
+                }
+                _ => return Err(self.error(item, "expected a transform operation")),
+            };
+            m = op * m;
+        }
+
+        // The shapes, the patterns and the perturbations invert their
+        // transforms
+        if m.det() == 0.0 {
+            return Err(self.error(node, "the transform is not invertible"));
+        }
+        Ok(m)
+    }
+
+    /// Materials are either mappings or names of the defined mappings
+    fn material(&self, node: &YamlNode) -> Result<Material, SceneError> {
+        let node = match node.value {
+            YamlValue::Scalar(_) => self.lookup(node)?,
+            _ => node,
+        };
+        let entries = node
+            .as_mapping()
+            .ok_or_else(|| self.error(node, "a material must be a mapping"))?;
+
+        let mut m = Material::new();
+        for (key, value) in entries.iter() {
+            match key.as_str() {
+                "color" => m.color = color_from(self.triple(value)?),
+                "pattern" => m.pattern = Some(self.pattern(value, 0)?),
+                "ambient" => m.ambient = self.number(value)?,
+                "diffuse" => m.diffuse = self.number(value)?,
+                "specular" => m.specular = self.number(value)?,
+                "shininess" => m.shininess = self.number(value)?,
+                "reflective" => m.reflective = self.number(value)?,
+                "transparency" => m.transparency = self.number(value)?,
+                "refractive-index" => m.refractive_index = self.number(value)?,
+                "casts-shadow" => m.casts_shadow = self.boolean(value)?,
+                "normal-map" | "bump" => {
+                    m.normal_perturbation = Some(self.perturbation(key, value)?)
+                }
+                _ => return Err(self.error(value, &format!("unknown material property: {}", key))),
+            }
+        }
+        Ok(m)
+    }
+
+    fn perturbation(&self, kind: &str, node: &YamlNode) -> Result<NormalPerturbation, SceneError> {
+        let transform = match node.get("transform") {
+            Some(t) => self.transform(t, 0)?,
+            None => Matrix::one(),
+        };
+        if kind == "bump" {
+            self.check_keys(node, &["amplitude", "octaves", "transform"])?;
+            let amplitude = self.number(self.required(node, "amplitude")?)?;
+            let octaves = match node.get("octaves") {
+                Some(o) => match self.integer(o)? {
+                    n @ 1..=16 => n as u8,
+                    _ => return Err(self.error(o, "octaves must be in the range of 1-16")),
+                },
+                None => 1,
+            };
+            return Ok(NormalPerturbation::bump(amplitude, octaves, transform));
+        }
+
+        self.check_keys(node, &["file", "mapping", "transform"])?;
+        let image = self.image(self.required(node, "file")?)?;
+        let mapping = self.mapping(node)?;
+        Ok(NormalPerturbation::normal_map(image, mapping, transform))
+    }
+
+    fn mapping(&self, node: &YamlNode) -> Result<UvMapping, SceneError> {
+        match node.get("mapping") {
+            None => Ok(UvMapping::Planar),
+            Some(m) => match m.as_str() {
+                Some("planar") => Ok(UvMapping::Planar),
+                Some("spherical") => Ok(UvMapping::Spherical),
+                Some("cylindrical") => Ok(UvMapping::Cylindrical),
+                Some("cube") => Ok(UvMapping::Cube),
+                _ => Err(self.error(m, "unknown texture mapping")),
+            },
+        }
+    }
+
+    /// Patterns are mappings with a type, two colors or sub-patterns, and an
+    /// optional transform
+    fn pattern(&self, node: &YamlNode, depth: usize) -> Result<Box<dyn Pattern>, SceneError> {
+        if depth > MAX_DEFINE_DEPTH {
+            return Err(self.error(node, "patterns nested too deeply"));
+        }
+        let node = match node.value {
+            YamlValue::Scalar(_) => self.lookup(node)?,
+            _ => node,
+        };
+        if node.as_mapping().is_none() {
+            return Err(self.error(node, "a pattern must be a mapping"));
+        }
+        let kind = self.required_str(node, "type")?;
+        let transform = match node.get("transform") {
+            Some(t) => self.transform(t, 0)?,
+            None => Matrix::one(),
+        };
+
+        if kind == "solid" {
+            self.check_keys(node, &["type", "color"])?;
+            return Ok(solid_pattern(color_from(
+                self.triple(self.required(node, "color")?)?,
+            )));
+        }
+
+        if kind == "perturbed" || kind == "noise" {
+            self.check_keys(node, &["type", "pattern", "transform"])?;
+            let sub = self.pattern(self.required(node, "pattern")?, depth + 1)?;
+            return Ok(noise_pattern(sub, transform));
+        }
+
+        if kind == "image" {
+            self.check_keys(node, &["type", "file", "mapping", "wrap", "transform"])?;
+            let image = self.image(self.required(node, "file")?)?;
+            let mapping = self.mapping(node)?;
+            let wrap = match node.get("wrap") {
+                None => TextureWrap::Wrap,
+                Some(w) => match w.as_str() {
+                    Some("wrap") => TextureWrap::Wrap,
+                    Some("clamp") => TextureWrap::Clamp,
+                    _ => return Err(self.error(w, "unknown texture wrap mode")),
+                },
+            };
+            return Ok(image_pattern(image, mapping, wrap, transform));
+        }
+
+        self.check_keys(node, &["type", "colors", "transform"])?;
+        let colors = self.required(node, "colors")?;
+        let items = match colors.as_sequence() {
+            Some(items) if items.len() == 2 => items,
+            _ => return Err(self.error(colors, "expected two colors or patterns")),
+        };
+        let mut subs = Vec::new();
+        for item in items.iter() {
+            subs.push(match item.value {
+                YamlValue::Sequence(_) => solid_pattern(color_from(self.triple(item)?)),
+                _ => self.pattern(item, depth + 1)?,
+            });
+        }
+        let p2 = subs.pop().unwrap();
+        let p1 = subs.pop().unwrap();
+
+        let constructor = match kind {
+            "stripes" => stripe_pattern,
+            "gradient" => gradient_pattern,
+            "rings" => ring_pattern,
+            "checkers" => checker_pattern,
+            "radial-gradient" => radial_gradient_pattern,
+            "blended" => blended_pattern,
+            _ => return Err(self.error(node, &format!("unknown pattern: {}", kind))),
+        };
+        Ok(constructor(p1, p2, transform))
+    }
+
+    /// Loads an image relative to the scene file
+    fn image(&self, file: &YamlNode) -> Result<Canvas, SceneError> {
+        let name = file
+            .as_str()
+            .ok_or_else(|| self.error(file, "file must be a string"))?;
+        let path = self.base_dir.join(name);
+        Canvas::load(&path.to_string_lossy())
+            .map_err(|err| self.error(file, &format!("{}: {}", name, err)))
+    }
+
+    fn shadows(&mut self, item: &YamlNode) -> Result<(), SceneError> {
+        self.check_keys(item, &["add", "enabled", "transparent"])?;
+        if let Some(enabled) = item.get("enabled") {
+            self.world.shadows = self.boolean(enabled)?;
+        }
+        if let Some(transparent) = item.get("transparent") {
+            self.world.transparent_shadows = self.boolean(transparent)?;
+        }
+        Ok(())
+    }
+
+    fn background(&self, item: &YamlNode) -> Result<Background, SceneError> {
+        let kind = self.required_str(item, "type")?;
+        match kind {
+            "solid" => {
+                self.check_keys(item, &["add", "type", "color"])?;
+                let c = self.triple(self.required(item, "color")?)?;
+                Ok(Background::Solid(color_from(c)))
+            }
+            "gradient" => {
+                self.check_keys(item, &["add", "type", "bottom", "top"])?;
+                Ok(Background::Gradient {
+                    bottom: color_from(self.triple(self.required(item, "bottom")?)?),
+                    top: color_from(self.triple(self.required(item, "top")?)?),
+                })
+            }
+            "equirectangular" | "cube-map" => {
+                self.check_keys(item, &["add", "type", "file"])?;
+                let texture =
+                    Texture::new(self.image(self.required(item, "file")?)?, TextureWrap::Wrap);
+                Ok(match kind {
+                    "cube-map" => Background::CubeMap(texture),
+                    _ => Background::Equirectangular(texture),
+                })
+            }
+            _ => Err(self.error(
+                item.get("type").unwrap(),
+                &format!("unknown background type: {}", kind),
+            )),
+        }
+    }
+
+    /// Builds a shape; the shapes with no material of their own get the
+    /// inherited one
+    fn shape(
+        &self,
+        item: &YamlNode,
+        inherited: Option<&Material>,
+        depth: usize,
+    ) -> Result<Box<dyn Shape>, SceneError> {
+        if depth > MAX_DEFINE_DEPTH {
+            return Err(self.error(item, "shapes nested too deeply"));
+        }
+        if item.as_mapping().is_none() {
+            return Err(self.error(item, "a shape must be a mapping"));
+        }
+        let kind = self.required_str(item, "add")?;
+        let own_material = match item.get("material") {
+            Some(m) => Some(self.material(m)?),
+            None => None,
+        };
+        let material = own_material.as_ref().or(inherited);
+        let default_material = Material::new();
+        let leaf_material = material.unwrap_or(&default_material);
+        let transform = match item.get("transform") {
+            Some(t) => self.transform(t, 0)?,
+            None => Matrix::one(),
+        };
+        let end_transform = match item.get("end-transform") {
+            Some(t) => Some(self.transform(t, 0)?),
+            None => None,
+        };
+
+        const COMMON: [&str; 4] = ["add", "material", "transform", "end-transform"];
+        let with_common = |extra: &[&'static str]| -> Vec<&str> {
+            COMMON.iter().chain(extra.iter()).copied().collect()
+        };
+
+        let mut shape = match kind {
+            "sphere" | "plane" | "cube" => {
+                self.check_keys(item, &COMMON)?;
+                let mut s = match kind {
+                    "sphere" => sphere_unit(),
+                    "plane" => plane_unit(),
+                    _ => cube_unit(),
+                };
+                s.set_material(leaf_material);
+                s
+            }
+            "cylinder" | "cone" => {
+                self.check_keys(item, &with_common(&["min", "max", "closed"]))?;
+                let bound = |key: &str, default: f64| match item.get(key) {
+                    Some(v) => self.number(v),
+                    None => Ok(default),
+                };
+                let min = bound("min", f64::NEG_INFINITY)?;
+                let max = bound("max", f64::INFINITY)?;
+                let closed = match item.get("closed") {
+                    Some(v) => self.boolean(v)?,
+                    None => false,
+                };
+                let mut s = match kind {
+                    "cylinder" => cylinder_min_max(min, max, closed),
+                    _ => cone_min_max(min, max, closed),
+                };
+                s.set_material(leaf_material);
+                s
+            }
+            "group" => {
+                self.check_keys(item, &with_common(&["children"]))?;
+                let mut g = Group::new();
+                if let Some(children) = item.get("children") {
+                    let children = children
+                        .as_sequence()
+                        .ok_or_else(|| self.error(children, "children must be a sequence"))?;
+                    for c in children.iter() {
+                        g.add_child(self.shape(c, material, depth + 1)?);
+                    }
+                }
+                if let Some(m) = material {
+                    g.set_material(m);
+                }
+                Box::new(g)
+            }
+            "csg" => {
+                self.check_keys(item, &with_common(&["operation", "left", "right"]))?;
+                let op = self.required(item, "operation")?;
+                let operation = match op.as_str() {
+                    Some("union") => CsgOperation::Union,
+                    Some("intersection") => CsgOperation::Intersection,
+                    Some("difference") => CsgOperation::Difference,
+                    _ => return Err(self.error(op, "unknown CSG operation")),
+                };
+                let left = self.shape(self.required(item, "left")?, material, depth + 1)?;
+                let right = self.shape(self.required(item, "right")?, material, depth + 1)?;
+                let mut s = csg(operation, left, right);
+                if let Some(m) = material {
+                    s.set_material(m);
+                }
+                s
+            }
+            "obj" => {
+                self.check_keys(item, &with_common(&["file"]))?;
+                let file = self.required(item, "file")?;
+                let name = file
+                    .as_str()
+                    .ok_or_else(|| self.error(file, "file must be a string"))?;
+                let path = self.base_dir.join(name);
+                let obj = ObjFile::load(&path.to_string_lossy())
+                    .map_err(|err| self.error(file, &format!("{}: {}", name, err)))?;
+                obj.to_group(Matrix::one(), leaf_material)
+            }
+            _ => {
+                // A shape defined earlier; the material of the item is
+                // inherited by the parts of the shape that have none
+                let defined = self.lookup(self.required(item, "add")?)?;
+                self.check_keys(item, &COMMON)?;
+                if defined.get("add").is_none() {
+                    return Err(self.error(item, &format!("{} is not a shape", kind)));
+                }
+                self.shape(defined, material, depth + 1)?
+            }
+        };
+        shape.transform(transform);
+        if let Some(end) = end_transform {
+            shape.set_motion(end);
+        }
+        Ok(shape)
+    }
+}
+
+fn point_from(v: [f64; 3]) -> Tuple {
+    point(v[0], v[1], v[2])
+}
+
+fn vector_from(v: [f64; 3]) -> Tuple {
+    vector(v[0], v[1], v[2])
+}
+
+fn color_from(v: [f64; 3]) -> Tuple {
+    color(v[0], v[1], v[2])
+}
+
+pub fn parse_scene(input: &str, file_name: &str) -> Result<Scene, SceneError> {
+    Scene::parse(input, file_name)
+}
+
+pub fn load_scene(file_path: &str) -> Result<Scene, SceneError> {
+    Scene::load(file_path)
+}
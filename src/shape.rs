@@ -1,32 +1,106 @@
 // Copyright 2022 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
+use crate::bounds::Bounds;
+use crate::intersections::Intersection;
 use crate::material::Material;
 use crate::matrix::Matrix;
+use crate::motion::Motion;
 use crate::ray::Ray;
 use crate::tuple::Tuple;
 
 use std::ops::Deref;
 
-pub trait Shape: Deref<Target = dyn LocalShape> {
-    fn intersect(&self, ray: &Ray) -> Vec<f64>;
+pub trait Shape: Deref<Target = dyn LocalShape> + Send + Sync {
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>>;
     fn transform(&mut self, transform: Matrix);
     fn current_transform(&self) -> &Matrix;
     fn current_inverse_transform(&self) -> &Matrix;
+
+    /// Returns the transform at the given time; it is the current transform
+    /// unless the shape moves
+    fn transform_at(&self, _time: f64) -> Matrix {
+        *self.current_transform()
+    }
+
+    fn inverse_transform_at(&self, _time: f64) -> Matrix {
+        *self.current_inverse_transform()
+    }
+
+    /// Makes the shape move while the shutter is open: the current transform
+    /// becomes the keyframe at the time 0 and the given one the keyframe at
+    /// the time 1; the transforms applied afterwards move both keyframes
+    fn set_motion(&mut self, end: Matrix);
+
+    /// Returns the normal at a point in the world's frame of reference; the
+    /// moving shapes use the keyframe at the time 0, and the groups and the
+    /// CSG shapes, having no surface of their own, return the zero vector
     fn normal_at(&self, pt: Tuple) -> Tuple;
+
+    /// Returns the normal at a point in the world's frame of reference using
+    /// the additional surface information recorded in the intersection,
+    /// including the time of the ray for the moving shapes
+    fn normal_at_hit(&self, pt: Tuple, hit: &Intersection) -> Tuple;
     fn material(&self) -> &Material;
     fn material_mut(&mut self) -> &mut Material;
     fn set_material(&mut self, material: &Material);
+
+    /// Returns the box bounding the shape in the world's frame of reference
+    fn bounds(&self) -> Bounds;
+
+    /// Builds the acceleration structures of the shapes containing other
+    /// shapes; needs to be called again when the children change
+    fn build_bvh(&mut self) {}
+
+    /// Checks whether the other shape is this shape or one of its descendants
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+
+    /// Checks whether the ray hits the shape at a distance in the range of
+    /// (0, max_t), counting only the surfaces whose materials qualify; unlike
+    /// `intersect`, it may stop at the first such hit
+    fn any_hit(&self, ray: &Ray, max_t: f64, qualifies: &dyn Fn(&Material) -> bool) -> bool {
+        self.intersect(ray)
+            .iter()
+            .any(|x| x.t() > 0.0 && x.t() < max_t && qualifies(x.shape().material()))
+    }
 }
 
-pub trait LocalShape {
+pub trait LocalShape: Send + Sync {
     fn local_intersect(&self, ray: &Ray) -> Vec<f64>;
     fn local_normal_at(&self, pt: Tuple) -> Tuple;
+
+    /// Returns the box bounding the shape in the shape's frame of reference
+    fn local_bounds(&self) -> Bounds;
+
+    /// Returns the intersections as (t, u, v) triples where u and v are the
+    /// surface coordinates of the hit; shapes that do not need them report
+    /// zeros
+    fn local_intersect_uv(&self, ray: &Ray) -> Vec<(f64, f64, f64)> {
+        self.local_intersect(ray)
+            .iter()
+            .map(|t| (*t, 0.0, 0.0))
+            .collect()
+    }
+
+    fn local_normal_at_hit(&self, pt: Tuple, _hit: &Intersection) -> Tuple {
+        self.local_normal_at(pt)
+    }
+
+    /// Checks whether the ray hits the shape at a distance in the range of
+    /// (0, max_t); the shapes can do it without collecting the intersections
+    fn local_any_hit(&self, ray: &Ray, max_t: f64) -> bool {
+        self.local_intersect(ray)
+            .iter()
+            .any(|t| *t > 0.0 && *t < max_t)
+    }
 }
 
 pub struct ShapeImpl<T: LocalShape> {
     transform: Matrix,
     transform_inv: Matrix,
+    motion: Option<Motion>,
     material: Material,
     shape: T,
 }
@@ -39,24 +113,45 @@ where
         ShapeImpl {
             transform: Matrix::one(),
             transform_inv: Matrix::one(),
+            motion: None,
             material: Material::new(),
             shape,
         }
     }
+
+    pub fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+
+    fn normal_to_world(&self, normal_o: Tuple, transform_inv: &Matrix) -> Tuple {
+        // Technically we should invert and transpose the submatrix(3, 3), but
+        // we can clean up the mess by zeroing the w component of the result.
+        let mut normal_w = transform_inv.transposed() * normal_o;
+        normal_w.set(3, 0.0);
+
+        normal_w.normalized()
+    }
 }
 
 impl<T> Shape for ShapeImpl<T>
 where
     T: LocalShape + 'static,
 {
-    fn intersect(&self, ray: &Ray) -> Vec<f64> {
-        let r = ray.transformed(self.transform_inv);
-        self.shape.local_intersect(&r)
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let r = ray.transformed(self.inverse_transform_at(ray.time()));
+        self.shape
+            .local_intersect_uv(&r)
+            .iter()
+            .map(|(t, u, v)| Intersection::new_uv(*t, self, *u, *v).with_time(ray.time()))
+            .collect()
     }
 
     fn transform(&mut self, transform: Matrix) {
         self.transform = transform * self.transform;
         self.transform_inv = self.transform.inverted();
+        if let Some(motion) = self.motion.as_mut() {
+            motion.transform(transform);
+        }
     }
 
     fn current_transform(&self) -> &Matrix {
@@ -67,17 +162,33 @@ where
         &self.transform_inv
     }
 
-    fn normal_at(&self, pt: Tuple) -> Tuple {
-        let pt_o = self.transform_inv * pt;
+    fn transform_at(&self, time: f64) -> Matrix {
+        match self.motion.as_ref() {
+            Some(motion) if time > 0.0 => motion.transform_at(time),
+            _ => self.transform,
+        }
+    }
 
-        let normal_o = self.shape.local_normal_at(pt_o);
+    fn inverse_transform_at(&self, time: f64) -> Matrix {
+        match self.motion.as_ref() {
+            Some(motion) if time > 0.0 => motion.inverse_transform_at(time),
+            _ => self.transform_inv,
+        }
+    }
 
-        // Technically we should invert and transpose the submatrix(3, 3), but
-        // we can clean up the mess by zeroing the w component of the result.
-        let mut normal_w = self.transform_inv.transposed() * normal_o;
-        normal_w.set(3, 0.0);
+    fn set_motion(&mut self, end: Matrix) {
+        self.motion = Some(Motion::new(self.transform, end));
+    }
 
-        normal_w.normalized()
+    fn normal_at(&self, pt: Tuple) -> Tuple {
+        let pt_o = self.transform_inv * pt;
+        self.normal_to_world(self.shape.local_normal_at(pt_o), &self.transform_inv)
+    }
+
+    fn normal_at_hit(&self, pt: Tuple, hit: &Intersection) -> Tuple {
+        let transform_inv = self.inverse_transform_at(hit.time());
+        let pt_o = transform_inv * pt;
+        self.normal_to_world(self.shape.local_normal_at_hit(pt_o, hit), &transform_inv)
     }
 
     fn material(&self) -> &Material {
@@ -91,6 +202,24 @@ where
     fn set_material(&mut self, material: &Material) {
         self.material = material.clone();
     }
+
+    fn any_hit(&self, ray: &Ray, max_t: f64, qualifies: &dyn Fn(&Material) -> bool) -> bool {
+        if !qualifies(&self.material) {
+            return false;
+        }
+        // The transform doesn't change the distances along the ray
+        let transform_inv = self.inverse_transform_at(ray.time());
+        self.shape
+            .local_any_hit(&ray.transformed(transform_inv), max_t)
+    }
+
+    /// The moving shapes are bounded over the whole motion
+    fn bounds(&self) -> Bounds {
+        match self.motion.as_ref() {
+            Some(motion) => motion.bounds(&self.shape.local_bounds()),
+            None => self.shape.local_bounds().transformed(self.transform),
+        }
+    }
 }
 
 impl<T> Deref for ShapeImpl<T>
@@ -0,0 +1,101 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::bounds::Bounds;
+use crate::intersections::Intersection;
+use crate::ray::Ray;
+use crate::shape::{LocalShape, Shape, ShapeImpl};
+use crate::tuple::Tuple;
+use crate::utils::intersect_triangle;
+
+pub struct SmoothTriangle {
+    p1: Tuple,
+    p2: Tuple,
+    p3: Tuple,
+    n1: Tuple,
+    n2: Tuple,
+    n3: Tuple,
+    e1: Tuple,
+    e2: Tuple,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, n1: Tuple, n2: Tuple, n3: Tuple) -> SmoothTriangle {
+        SmoothTriangle {
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+        }
+    }
+
+    pub fn p1(&self) -> Tuple {
+        self.p1
+    }
+
+    pub fn p2(&self) -> Tuple {
+        self.p2
+    }
+
+    pub fn p3(&self) -> Tuple {
+        self.p3
+    }
+
+    fn interpolate_normal(&self, u: f64, v: f64) -> Tuple {
+        self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)
+    }
+}
+
+impl LocalShape for SmoothTriangle {
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        self.local_intersect_uv(ray).iter().map(|x| x.0).collect()
+    }
+
+    fn local_intersect_uv(&self, ray: &Ray) -> Vec<(f64, f64, f64)> {
+        match intersect_triangle(ray, self.p1, self.e1, self.e2) {
+            None => vec![],
+            Some(x) => vec![x],
+        }
+    }
+
+    fn local_any_hit(&self, ray: &Ray, max_t: f64) -> bool {
+        matches!(
+            intersect_triangle(ray, self.p1, self.e1, self.e2),
+            Some((t, _, _)) if t > 0.0 && t < max_t
+        )
+    }
+
+    fn local_normal_at(&self, pt: Tuple) -> Tuple {
+        // Without the hit we recover the barycentric coordinates from the
+        // point itself by comparing the areas of the sub-triangles
+        let area = self.e1.cross(&self.e2);
+        let area_sq = area.dot(&area);
+        let to_pt = pt - self.p1;
+        let u = to_pt.cross(&self.e2).dot(&area) / area_sq;
+        let v = self.e1.cross(&to_pt).dot(&area) / area_sq;
+        self.interpolate_normal(u, v)
+    }
+
+    fn local_normal_at_hit(&self, _pt: Tuple, hit: &Intersection) -> Tuple {
+        self.interpolate_normal(hit.u(), hit.v())
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::from_points(&[self.p1, self.p2, self.p3])
+    }
+}
+
+pub fn smooth_triangle(
+    p1: Tuple,
+    p2: Tuple,
+    p3: Tuple,
+    n1: Tuple,
+    n2: Tuple,
+    n3: Tuple,
+) -> Box<dyn Shape> {
+    Box::new(ShapeImpl::new(SmoothTriangle::new(p1, p2, p3, n1, n2, n3)))
+}
@@ -1,6 +1,7 @@
 // Copyright 2022 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
+use crate::bounds::Bounds;
 use crate::material::Material;
 use crate::matrix::Matrix;
 use crate::ray::Ray;
@@ -31,11 +32,32 @@ impl LocalShape for Sphere {
         vec![d1, d2]
     }
 
+    fn local_any_hit(&self, ray: &Ray, max_t: f64) -> bool {
+        let dst = ray.origin() - point(0.0, 0.0, 0.0);
+        let a = ray.direction().norm().powi(2);
+        let b = 2.0 * ray.direction().dot(&dst);
+        let c = dst.norm().powi(2) - 1.0;
+        let delta = b.powi(2) - 4.0 * a * c;
+
+        if delta < 0.0 {
+            return false;
+        }
+
+        let s_delta = delta.sqrt();
+        let d1 = (-b - s_delta) / (2.0 * a);
+        let d2 = (-b + s_delta) / (2.0 * a);
+        (d1 > 0.0 && d1 < max_t) || (d2 > 0.0 && d2 < max_t)
+    }
+
     fn local_normal_at(&self, pt: Tuple) -> Tuple {
         // We get the normal in the local frame by subtracting the origin since
         // the point is on a unit sphere at origin.
         pt - point(0.0, 0.0, 0.0)
     }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0))
+    }
 }
 
 pub fn sphere_unit() -> Box<dyn Shape> {
@@ -0,0 +1,149 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::camera::{Camera, Projection};
+use crate::canvas::Canvas;
+use crate::transformations::view_transform;
+use crate::tuple::{point, vector};
+use crate::world::World;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+/// How the eyes of a stereo rig are aimed
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum StereoMode {
+    /// Both eyes look the same way as the camera, so only the objects at
+    /// infinity line up in the two images
+    #[default]
+    Parallel,
+    /// The eyes turn inwards to look at the point at the given distance in
+    /// front of the camera; simple, but it skews the images vertically
+    /// towards their sides
+    ToeIn { convergence: f64 },
+    /// The eyes look the same way as the camera, and their image planes
+    /// slide inwards so that the objects at the given distance line up; only
+    /// the perspective projection has an image plane to slide, the other
+    /// ones turn the eyes like the toe-in
+    OffAxis { convergence: f64 },
+}
+
+/// How a stereo pair is packed into a single image
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum StereoPacking {
+    /// The left eye on the left, the right eye on the right
+    #[default]
+    SideBySide,
+    /// The left eye at the top, the right eye at the bottom
+    TopBottom,
+}
+
+/// A pair of cameras made from a single one by moving its eye half of the
+/// interocular distance to the left and to the right
+pub struct StereoRig {
+    camera: Camera,
+    interocular: f64,
+    mode: StereoMode,
+}
+
+impl StereoRig {
+    pub fn new(camera: Camera, interocular: f64) -> StereoRig {
+        StereoRig {
+            camera,
+            interocular,
+            mode: StereoMode::Parallel,
+        }
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn interocular(&self) -> f64 {
+        self.interocular
+    }
+
+    pub fn set_interocular(&mut self, interocular: f64) {
+        self.interocular = interocular;
+    }
+
+    pub fn mode(&self) -> StereoMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: StereoMode) {
+        self.mode = mode;
+    }
+
+    /// Returns the camera of one of the eyes; all its settings other than
+    /// the transform and the shift come from the camera of the rig
+    pub fn eye(&self, eye: Eye) -> Camera {
+        // The camera's frame of reference has the left vector along +x
+        let offset = match eye {
+            Eye::Left => self.interocular / 2.0,
+            Eye::Right => -self.interocular / 2.0,
+        };
+        let from = point(offset, 0.0, 0.0);
+        let ahead = point(offset, 0.0, -1.0);
+        let up = vector(0.0, 1.0, 0.0);
+
+        let mut camera = self.camera.clone();
+        let to = match self.mode {
+            StereoMode::Parallel => ahead,
+            StereoMode::OffAxis { convergence }
+                if camera.projection() == Projection::Perspective =>
+            {
+                // The point at the convergence distance in front of the
+                // rig lies off the eye's axis by the offset over the
+                // distance on the image plane at z = -1
+                let (dx, dy) = camera.shift();
+                camera.set_shift(dx + offset / convergence / camera.pixel_size(), dy);
+                ahead
+            }
+            StereoMode::ToeIn { convergence } | StereoMode::OffAxis { convergence } => {
+                point(0.0, 0.0, -convergence)
+            }
+        };
+        camera.set_transform(view_transform(from, to, up) * *self.camera.transform());
+        camera
+    }
+
+    /// Renders the images of the left and the right eye
+    pub fn render(&self, world: &World) -> (Canvas, Canvas) {
+        (
+            self.eye(Eye::Left).render(world),
+            self.eye(Eye::Right).render(world),
+        )
+    }
+
+    /// Renders both eyes and packs them into a single image
+    pub fn render_packed(&self, world: &World, packing: StereoPacking) -> Canvas {
+        let (left, right) = self.render(world);
+        pack_stereo(&left, &right, packing)
+    }
+}
+
+/// Packs the images of the left and the right eye, which need to be of the
+/// same size, into a single image
+pub fn pack_stereo(left: &Canvas, right: &Canvas, packing: StereoPacking) -> Canvas {
+    let (w, h) = (left.width(), left.height());
+    if right.width() != w || right.height() != h {
+        panic!(
+            "stereo images differ in size: {}x{} and {}x{}",
+            w,
+            h,
+            right.width(),
+            right.height()
+        );
+    }
+    let (x, y, mut img) = match packing {
+        StereoPacking::SideBySide => (w, 0, Canvas::new(2 * w, h)),
+        StereoPacking::TopBottom => (0, h, Canvas::new(w, 2 * h)),
+    };
+    img.paste(left, 0, 0);
+    img.paste(right, x, y);
+    img
+}
@@ -0,0 +1,249 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::canvas::Canvas;
+use crate::tuple::{vector, Tuple};
+
+use std::f64::consts::PI;
+use std::fmt;
+use std::sync::Arc;
+
+/// What happens to the texture coordinates outside of the [0, 1] range
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TextureWrap {
+    /// The image repeats itself
+    #[default]
+    Wrap,
+    /// The pixels at the edges of the image extend indefinitely
+    Clamp,
+}
+
+/// Ways of projecting a point onto the texture coordinates
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UvMapping {
+    /// The xz plane with one copy of the image per unit square
+    Planar,
+    /// The unit sphere with the image stretched from pole to pole
+    Spherical,
+    /// The unit cylinder wrapped around the y axis with one copy of the image
+    /// per unit of height
+    Cylindrical,
+    /// The unit cube with the faces laid out in a horizontal cross: the top
+    /// row holds `up` in the second column, the middle one `left`, `front`,
+    /// `right` and `back`, and the bottom one `down` in the second column
+    Cube,
+}
+
+/// The faces of the cube mapping
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CubeFace {
+    Left,
+    Right,
+    Front,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    /// Returns the column and the row of the face in the cross layout
+    pub fn cell(&self) -> (usize, usize) {
+        match self {
+            CubeFace::Up => (1, 0),
+            CubeFace::Left => (0, 1),
+            CubeFace::Front => (1, 1),
+            CubeFace::Right => (2, 1),
+            CubeFace::Back => (3, 1),
+            CubeFace::Down => (1, 2),
+        }
+    }
+}
+
+impl UvMapping {
+    /// Maps a point to the (u, v) coordinates of the texture with v pointing
+    /// up; the cube mapping returns the coordinates within the face
+    pub fn uv(&self, pt: Tuple) -> (f64, f64) {
+        match self {
+            UvMapping::Planar => (pt.x(), pt.z()),
+            UvMapping::Spherical => spherical_uv(pt),
+            UvMapping::Cylindrical => {
+                let theta = pt.x().atan2(pt.z());
+                (1.0 - (theta / (2.0 * PI) + 0.5), pt.y())
+            }
+            UvMapping::Cube => {
+                let (_, u, v) = cube_uv(pt);
+                (u, v)
+            }
+        }
+    }
+
+    /// Returns the directions in which u and v grow at the point; they are
+    /// tangent to the surface the mapping wraps around, but may be zero where
+    /// the mapping is singular, like at the poles of the sphere
+    pub fn tangents(&self, pt: Tuple) -> (Tuple, Tuple) {
+        let (x, y, z) = (pt.x(), pt.y(), pt.z());
+        match self {
+            UvMapping::Planar => (vector(1.0, 0.0, 0.0), vector(0.0, 0.0, 1.0)),
+            UvMapping::Spherical => (vector(-z, 0.0, x), vector(-x * y, x * x + z * z, -z * y)),
+            UvMapping::Cylindrical => (vector(-z, 0.0, x), vector(0.0, 1.0, 0.0)),
+            UvMapping::Cube => match cube_uv(pt).0 {
+                CubeFace::Right => (vector(0.0, 0.0, -1.0), vector(0.0, 1.0, 0.0)),
+                CubeFace::Left => (vector(0.0, 0.0, 1.0), vector(0.0, 1.0, 0.0)),
+                CubeFace::Front => (vector(1.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)),
+                CubeFace::Back => (vector(-1.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)),
+                CubeFace::Up => (vector(1.0, 0.0, 0.0), vector(0.0, 0.0, -1.0)),
+                CubeFace::Down => (vector(1.0, 0.0, 0.0), vector(0.0, 0.0, 1.0)),
+            },
+        }
+    }
+}
+
+/// Maps a point to the texture coordinates of the unit sphere, the points
+/// need not be on the sphere itself
+pub fn spherical_uv(pt: Tuple) -> (f64, f64) {
+    let theta = pt.x().atan2(pt.z());
+    let radius = (pt.x() * pt.x() + pt.y() * pt.y() + pt.z() * pt.z()).sqrt();
+    if radius == 0.0 {
+        return (0.5, 0.5);
+    }
+    let phi = (pt.y() / radius).clamp(-1.0, 1.0).acos();
+    (1.0 - (theta / (2.0 * PI) + 0.5), 1.0 - phi / PI)
+}
+
+/// Picks the face of the cube the point projects onto, along with the
+/// coordinates within the face; the point is first scaled so that it lands
+/// on the unit cube
+pub fn cube_uv(pt: Tuple) -> (CubeFace, f64, f64) {
+    let max = pt.x().abs().max(pt.y().abs()).max(pt.z().abs());
+    if max == 0.0 {
+        return (CubeFace::Front, 0.5, 0.5);
+    }
+    let (x, y, z) = (pt.x() / max, pt.y() / max, pt.z() / max);
+
+    let face = if max == pt.x().abs() {
+        if x > 0.0 {
+            CubeFace::Right
+        } else {
+            CubeFace::Left
+        }
+    } else if max == pt.y().abs() {
+        if y > 0.0 {
+            CubeFace::Up
+        } else {
+            CubeFace::Down
+        }
+    } else if z > 0.0 {
+        CubeFace::Front
+    } else {
+        CubeFace::Back
+    };
+
+    let (u, v) = match face {
+        CubeFace::Right => (1.0 - z, y + 1.0),
+        CubeFace::Left => (z + 1.0, y + 1.0),
+        CubeFace::Front => (x + 1.0, y + 1.0),
+        CubeFace::Back => (1.0 - x, y + 1.0),
+        CubeFace::Up => (x + 1.0, 1.0 - z),
+        CubeFace::Down => (x + 1.0, z + 1.0),
+    };
+    (face, u / 2.0, v / 2.0)
+}
+
+/// An image sampled with bilinear filtering; the clones share the pixels
+#[derive(Clone)]
+pub struct Texture {
+    image: Arc<Canvas>,
+    wrap: TextureWrap,
+}
+
+impl Texture {
+    pub fn new(image: impl Into<Arc<Canvas>>, wrap: TextureWrap) -> Texture {
+        Texture {
+            image: image.into(),
+            wrap,
+        }
+    }
+
+    pub fn image(&self) -> &Canvas {
+        &self.image
+    }
+
+    pub fn wrap(&self) -> TextureWrap {
+        self.wrap
+    }
+
+    /// Returns the color at the given coordinates, (0, 0) being the bottom
+    /// left corner of the image and (1, 1) the top right one
+    pub fn sample(&self, u: f64, v: f64) -> Tuple {
+        let (w, h) = (self.image.width(), self.image.height());
+        self.bilinear(u, v, (0, 0, w, h), self.wrap)
+    }
+
+    /// Projects the point onto the image using the mapping and returns the
+    /// color there; the faces of the cube mapping are always clamped so that
+    /// the filtering doesn't bleed into the neighboring ones
+    pub fn sample_mapped(&self, mapping: UvMapping, pt: Tuple) -> Tuple {
+        if mapping != UvMapping::Cube {
+            let (u, v) = mapping.uv(pt);
+            return self.sample(u, v);
+        }
+
+        let (face, u, v) = cube_uv(pt);
+        let (col, row) = face.cell();
+        let w = self.image.width() / 4;
+        let h = self.image.height() / 3;
+        if w == 0 || h == 0 {
+            return self.sample(u, v);
+        }
+        self.bilinear(u, v, (col * w, row * h, w, h), TextureWrap::Clamp)
+    }
+
+    /// Interpolates between the four pixels closest to the coordinates within
+    /// the given rectangle of the image
+    fn bilinear(
+        &self,
+        u: f64,
+        v: f64,
+        rect: (usize, usize, usize, usize),
+        wrap: TextureWrap,
+    ) -> Tuple {
+        let (x0, y0, w, h) = rect;
+
+        // The centers of the pixels sit at the half-integer coordinates
+        let x = u * w as f64 - 0.5;
+        let y = (1.0 - v) * h as f64 - 0.5;
+        let (fx, fy) = (x - x.floor(), y - y.floor());
+        let (ix, iy) = (x.floor() as i64, y.floor() as i64);
+
+        let index = |i: i64, size: usize| match wrap {
+            TextureWrap::Wrap => i.rem_euclid(size as i64) as usize,
+            TextureWrap::Clamp => i.clamp(0, size as i64 - 1) as usize,
+        };
+        let (xa, xb) = (x0 + index(ix, w), x0 + index(ix + 1, w));
+        let (ya, yb) = (y0 + index(iy, h), y0 + index(iy + 1, h));
+
+        let top = self.image.at(xa, ya) * (1.0 - fx) + self.image.at(xb, ya) * fx;
+        let bottom = self.image.at(xa, yb) * (1.0 - fx) + self.image.at(xb, yb) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+impl fmt::Debug for Texture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Texture")
+            .field("width", &self.image.width())
+            .field("height", &self.image.height())
+            .field("wrap", &self.wrap)
+            .finish()
+    }
+}
+
+/// Two textures are equal when they share the image, comparing the pixels
+/// would be too costly
+impl PartialEq for Texture {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.image, &other.image) && self.wrap == other.wrap
+    }
+}
+
+impl Eq for Texture {}
@@ -0,0 +1,80 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+use crate::bounds::Bounds;
+use crate::ray::Ray;
+use crate::shape::{LocalShape, Shape, ShapeImpl};
+use crate::tuple::Tuple;
+use crate::utils::intersect_triangle;
+
+pub struct Triangle {
+    p1: Tuple,
+    p2: Tuple,
+    p3: Tuple,
+    e1: Tuple,
+    e2: Tuple,
+    normal: Tuple,
+}
+
+impl Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Triangle {
+        // The edges and the normal are the same for every ray, so we compute
+        // them once
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        let normal = e2.cross(&e1).normalized();
+        Triangle {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal,
+        }
+    }
+
+    pub fn p1(&self) -> Tuple {
+        self.p1
+    }
+
+    pub fn p2(&self) -> Tuple {
+        self.p2
+    }
+
+    pub fn p3(&self) -> Tuple {
+        self.p3
+    }
+}
+
+impl LocalShape for Triangle {
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        self.local_intersect_uv(ray).iter().map(|x| x.0).collect()
+    }
+
+    fn local_intersect_uv(&self, ray: &Ray) -> Vec<(f64, f64, f64)> {
+        match intersect_triangle(ray, self.p1, self.e1, self.e2) {
+            None => vec![],
+            Some(x) => vec![x],
+        }
+    }
+
+    fn local_any_hit(&self, ray: &Ray, max_t: f64) -> bool {
+        matches!(
+            intersect_triangle(ray, self.p1, self.e1, self.e2),
+            Some((t, _, _)) if t > 0.0 && t < max_t
+        )
+    }
+
+    fn local_normal_at(&self, _pt: Tuple) -> Tuple {
+        // The triangle is flat, so the normal is the same everywhere
+        self.normal
+    }
+
+    fn local_bounds(&self) -> Bounds {
+        Bounds::from_points(&[self.p1, self.p2, self.p3])
+    }
+}
+
+pub fn triangle(p1: Tuple, p2: Tuple, p3: Tuple) -> Box<dyn Shape> {
+    Box::new(ShapeImpl::new(Triangle::new(p1, p2, p3)))
+}
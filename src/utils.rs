@@ -3,15 +3,14 @@
 
 use crate::constants::EPSILON;
 use crate::ray::Ray;
+use crate::tuple::Tuple;
 
 pub fn feq(a: f64, b: f64) -> bool {
     (a - b).abs() < EPSILON
 }
 
 pub fn peq<T: ?Sized>(left: &T, right: &T) -> bool {
-    let left: *const T = left;
-    let right: *const T = right;
-    left == right
+    std::ptr::addr_eq(left, right)
 }
 
 pub fn check_cap(ray: &Ray, limit: f64, radius: f64) -> Option<f64> {
@@ -49,6 +48,35 @@ pub fn filter_min_max(t: f64, ray: &Ray, min: f64, max: f64) -> bool {
     false
 }
 
+/// Möller–Trumbore ray-triangle intersection returning (t, u, v) where u and
+/// v are the barycentric coordinates of the hit:
+/// https://en.wikipedia.org/wiki/M%C3%B6ller%E2%80%93Trumbore_intersection_algorithm
+pub fn intersect_triangle(ray: &Ray, p1: Tuple, e1: Tuple, e2: Tuple) -> Option<(f64, f64, f64)> {
+    let dir_cross_e2 = ray.direction().cross(&e2);
+    let det = e1.dot(&dir_cross_e2);
+
+    // The ray is parallel to the plane of the triangle
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let p1_to_origin = ray.origin() - p1;
+    let u = f * p1_to_origin.dot(&dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(&e1);
+    let v = f * ray.direction().dot(&origin_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = f * e2.dot(&origin_cross_e1);
+    Some((t, u, v))
+}
+
 #[macro_export]
 macro_rules! pattern_boilerplate_2p {
     ($cls:ident, $unit:ident, $color:ident, $full:ident) => {
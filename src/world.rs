@@ -1,6 +1,8 @@
 // Copyright 2022 Lukasz Janyst <lukasz@jany.st>
 // Licensed under the MIT license, see the LICENSE file for details.
 
+use crate::background::Background;
+use crate::bvh::Bvh;
 use crate::intersections::{intersect, IntersectionProperties, Intersections};
 use crate::light::{point_light, Light};
 use crate::material::Material;
@@ -10,10 +12,19 @@ use crate::sphere::{sphere, sphere_unit};
 use crate::transformations::scaling;
 use crate::tuple::{color, point, Tuple};
 
+const WHITE: Tuple = color(1.0, 1.0, 1.0);
+
 pub struct World {
     pub shapes: Vec<Box<dyn Shape>>,
-    pub lights: Vec<Light>,
+    pub lights: Vec<Box<dyn Light>>,
     pub shadows: bool,
+    /// Lets the light through the transparent shapes filtering it by their
+    /// color; otherwise all the shapes casting shadows are opaque, as in the
+    /// book
+    pub transparent_shadows: bool,
+    /// The color of the rays that miss all the shapes
+    pub background: Background,
+    bvh: Option<Bvh>,
 }
 
 impl World {
@@ -22,40 +33,50 @@ impl World {
             shapes: Vec::new(),
             lights: Vec::new(),
             shadows: true,
+            transparent_shadows: false,
+            background: Background::default(),
+            bvh: None,
         }
     }
 
-    pub fn default() -> World {
-        let mut w = World {
-            shapes: Vec::new(),
-            lights: Vec::new(),
-            shadows: true,
-        };
-
-        let l = point_light(point(-10.0, 10.0, -10.0), color(1.0, 1.0, 1.0));
-        w.lights.push(l);
-
-        let mut s1 = sphere_unit();
-        let mut m1 = Material::new();
-        m1.color = color(0.8, 1.0, 0.6);
-        m1.diffuse = 0.7;
-        m1.specular = 0.2;
-        s1.set_material(&m1);
+    /// Builds the bounding volume hierarchy over the shapes of the world; it
+    /// needs to be rebuilt when the shapes are transformed or removed
+    pub fn build_bvh(&mut self) {
+        for s in self.shapes.iter_mut() {
+            s.build_bvh();
+        }
+        self.bvh = Some(Bvh::new(&self.shapes));
+    }
 
-        let s2 = sphere(scaling(0.5, 0.5, 0.5));
+    pub fn clear_bvh(&mut self) {
+        self.bvh = None;
+    }
 
-        w.shapes.push(s1);
-        w.shapes.push(s2);
+    pub fn has_bvh(&self) -> bool {
+        self.bvh.is_some()
+    }
 
-        w
+    /// Checks whether the ray hits any of the shapes at a distance in the
+    /// range of (0, max_t) with a qualifying material; it doesn't collect the
+    /// intersections and stops at the first such hit
+    pub fn any_hit(&self, ray: &Ray, max_t: f64, qualifies: &dyn Fn(&Material) -> bool) -> bool {
+        match self.bvh.as_ref() {
+            Some(bvh) => bvh.any_hit(&self.shapes, ray, max_t, qualifies),
+            None => self.shapes.iter().any(|s| s.any_hit(ray, max_t, qualifies)),
+        }
     }
 
-    pub fn intersect(&self, ray: &Ray) -> Intersections {
+    pub fn intersect(&self, ray: &Ray) -> Intersections<'_> {
         let mut xs = Intersections::new();
 
-        for s in self.shapes.iter() {
-            let s_xs = intersect(s.as_ref(), ray);
-            xs.append(s_xs);
+        match self.bvh.as_ref() {
+            Some(bvh) => xs.append(bvh.intersect(&self.shapes, ray)),
+            None => {
+                for s in self.shapes.iter() {
+                    let s_xs = intersect(s.as_ref(), ray);
+                    xs.append(s_xs);
+                }
+            }
         }
 
         xs.sort();
@@ -64,23 +85,34 @@ impl World {
 
     pub fn shade_hit(&self, props: &IntersectionProperties, depth: u8) -> Tuple {
         let mut color = Tuple::zero_color();
+        let surface = props
+            .shape
+            .material()
+            .color_at(props.shape, &props.point, props.time);
         for l in self.lights.iter() {
-            let mut shadowed = false;
-
-            if self.shadows {
-                // we need to use a point slightly above our point along the normal to
-                // account for floating-point inaccuracies
-                shadowed = self.is_shadowed(l, props.over_point);
-            }
+            // The jittered samples of the area lights depend on the point, so
+            // we take them once and use the same ones for the shadows
+            let samples = l.samples(&props.point);
+            let transmittances: Vec<Tuple> = samples
+                .iter()
+                .map(|s| {
+                    if !self.shadows {
+                        return WHITE;
+                    }
+                    // we need to use a point slightly above our point along the normal to
+                    // account for floating-point inaccuracies
+                    self.transmittance(props.over_point, s.direction, s.distance, props.time)
+                })
+                .collect();
 
             color = color
-                + props.shape.material().lighting(
-                    props.shape,
-                    l,
-                    &props.point,
+                + props.shape.material().lighting_with_samples(
+                    &surface,
+                    l.as_ref(),
+                    &samples,
+                    &transmittances,
                     &props.eyev,
                     &props.normalv,
-                    shadowed,
                 );
         }
 
@@ -101,7 +133,7 @@ impl World {
             return Tuple::zero_color();
         }
 
-        let reflected_ray = Ray::new(props.over_point, props.reflectv);
+        let reflected_ray = Ray::new(props.over_point, props.reflectv).with_time(props.time);
         props.shape.material().reflective * self.color_at(&reflected_ray, depth - 1)
     }
 
@@ -126,7 +158,7 @@ impl World {
 
         // Spawn the refracted ray and compute it's color
         let direction = (ratio * cos_theta_1 - cos_theta_2) * props.normalv - ratio * props.eyev;
-        let refracted_ray = Ray::new(props.under_point, direction);
+        let refracted_ray = Ray::new(props.under_point, direction).with_time(props.time);
 
         props.shape.material().transparency * self.color_at(&refracted_ray, depth - 1)
     }
@@ -136,7 +168,7 @@ impl World {
         let hit = xs.hit();
 
         if hit.is_none() {
-            return Tuple::zero_color();
+            return self.background.color_at(ray.direction());
         }
 
         let h = hit.unwrap();
@@ -144,15 +176,107 @@ impl World {
         self.shade_hit(&props, depth)
     }
 
-    pub fn is_shadowed(&self, light: &Light, pt: Tuple) -> bool {
-        let v = light.position - pt;
-        let distance = v.norm();
-        let direction = v.normalized();
+    /// Checks whether the light is completely hidden from the point
+    pub fn is_shadowed(&self, light: &dyn Light, pt: Tuple) -> bool {
+        light
+            .samples(&pt)
+            .iter()
+            .all(|s| self.is_occluded(pt, s.direction, s.distance, 0.0))
+    }
+
+    /// Returns the fraction of the light's samples visible from the point,
+    /// possibly through transparent shapes
+    pub fn light_visibility(&self, light: &dyn Light, pt: Tuple) -> f64 {
+        let samples = light.samples(&pt);
+        let visible = samples
+            .iter()
+            .filter(|s| !self.is_occluded(pt, s.direction, s.distance, 0.0))
+            .count();
+        visible as f64 / samples.len() as f64
+    }
+
+    /// Returns the fraction of each of the color channels of the light that
+    /// reaches the point, averaged over the light's samples
+    pub fn light_transmittance(&self, light: &dyn Light, pt: Tuple) -> Tuple {
+        self.light_transmittance_at(light, pt, 0.0)
+    }
+
+    /// Returns the transmittance like `light_transmittance` with the moving
+    /// shapes placed where they are at the given time
+    pub fn light_transmittance_at(&self, light: &dyn Light, pt: Tuple, time: f64) -> Tuple {
+        let samples = light.samples(&pt);
+        let mut sum = Tuple::zero_color();
+        for s in samples.iter() {
+            sum = sum + self.transmittance(pt, s.direction, s.distance, time);
+        }
+        sum / samples.len() as f64
+    }
+
+    fn is_occluded(&self, pt: Tuple, direction: Tuple, distance: f64, time: f64) -> bool {
+        let t = self.transmittance(pt, direction, distance, time);
+        t.r() <= 0.0 && t.g() <= 0.0 && t.b() <= 0.0
+    }
+
+    /// Traces the shadow ray through the shapes between the point and the
+    /// light; every crossing of a transparent surface filters the light by
+    /// the transparency and the color of the material
+    fn transmittance(&self, pt: Tuple, direction: Tuple, distance: f64, time: f64) -> Tuple {
+        let r = Ray::new(pt, direction).with_time(time);
+
+        // Most of the shadow rays are either unobstructed or blocked by
+        // something opaque, so we don't need the full list of intersections
+        if !self.any_hit(&r, distance, &|m| m.casts_shadow) {
+            return WHITE;
+        }
+        let opaque = |m: &Material| m.casts_shadow && m.transparency <= 0.0;
+        if !self.transparent_shadows || self.any_hit(&r, distance, &opaque) {
+            return Tuple::zero_color();
+        }
 
-        let r = Ray::new(pt, direction);
         let xs = self.intersect(&r);
-        let hit = xs.hit();
 
-        matches!(hit, Some(h) if h.t() < distance)
+        let mut transmittance = WHITE;
+        for i in 0..xs.len() {
+            let x = xs.at(i);
+            if x.t() <= 0.0 {
+                continue;
+            }
+            if x.t() >= distance {
+                break;
+            }
+            let material = x.shape().material();
+            if !material.casts_shadow {
+                continue;
+            }
+            if material.transparency <= 0.0 {
+                return Tuple::zero_color();
+            }
+            let c = material.color_at(x.shape(), &r.position(x.t()), time);
+            transmittance = material.transparency * transmittance.hadamard(&c);
+        }
+        transmittance
+    }
+}
+
+impl Default for World {
+    fn default() -> World {
+        let mut w = World::empty();
+
+        let l = point_light(point(-10.0, 10.0, -10.0), color(1.0, 1.0, 1.0));
+        w.lights.push(l);
+
+        let mut s1 = sphere_unit();
+        let mut m1 = Material::new();
+        m1.color = color(0.8, 1.0, 0.6);
+        m1.diffuse = 0.7;
+        m1.specular = 0.2;
+        s1.set_material(&m1);
+
+        let s2 = sphere(scaling(0.5, 0.5, 0.5));
+
+        w.shapes.push(s1);
+        w.shapes.push(s2);
+
+        w
     }
 }
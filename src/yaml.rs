@@ -0,0 +1,466 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+//! A subset of YAML sufficient for describing scenes: block and flow
+//! sequences and mappings, plain and quoted scalars, and comments. Anchors,
+//! tags, multi-document streams, and multi-line scalars are not supported.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum YamlValue {
+    /// Scalars are kept as strings and interpreted by the consumer
+    Scalar(String),
+    Sequence(Vec<YamlNode>),
+    /// Keys in the order of appearance
+    Mapping(Vec<(String, YamlNode)>),
+}
+
+/// A value together with the line it starts at
+#[derive(Debug, Clone, PartialEq)]
+pub struct YamlNode {
+    pub line: usize,
+    pub value: YamlValue,
+}
+
+impl YamlNode {
+    pub fn new(line: usize, value: YamlValue) -> YamlNode {
+        YamlNode { line, value }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.value {
+            YamlValue::Scalar(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_sequence(&self) -> Option<&[YamlNode]> {
+        match &self.value {
+            YamlValue::Sequence(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_mapping(&self) -> Option<&[(String, YamlNode)]> {
+        match &self.value {
+            YamlValue::Mapping(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Looks up a key of a mapping
+    pub fn get(&self, key: &str) -> Option<&YamlNode> {
+        self.as_mapping()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YamlError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for YamlError {}
+
+fn error<T>(line: usize, message: &str) -> Result<T, YamlError> {
+    Err(YamlError {
+        line,
+        message: message.to_string(),
+    })
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    content: String,
+}
+
+/// Quotes start the scalars only, so the apostrophes within plain scalars
+/// don't count
+fn starts_quote(c: char, prev: char) -> bool {
+    (c == '"' || c == '\'') && (prev.is_whitespace() || "[{,:".contains(prev))
+}
+
+/// Removes a comment from the line; the hash starts a comment only at the
+/// beginning or after a whitespace and outside of quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if starts_quote(c, prev) => quote = Some(c),
+            None if c == '#' && prev.is_whitespace() => return &line[..i],
+            None => {}
+        }
+        prev = c;
+    }
+    line
+}
+
+/// Nesting depth of the flow collections left open at the end of the text
+fn open_brackets(text: &str) -> isize {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut prev = ' ';
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if starts_quote(c, prev) => quote = Some(c),
+            None => match c {
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                _ => {}
+            },
+        }
+        prev = c;
+    }
+    depth
+}
+
+/// Splits the input into the meaningful lines joining the flow collections
+/// spanning multiple lines
+fn split_lines(input: &str) -> Result<Vec<Line>, YamlError> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut continued = false;
+    for (i, raw) in input.lines().enumerate() {
+        let number = i + 1;
+        let text = strip_comment(raw).trim_end();
+        let content = text.trim_start();
+        if content.is_empty() {
+            continue;
+        }
+        if content == "---" || content == "..." {
+            continue;
+        }
+
+        if continued {
+            let last = lines.last_mut().unwrap();
+            last.content.push(' ');
+            last.content.push_str(content);
+            continued = open_brackets(&last.content) > 0;
+            continue;
+        }
+
+        let prefix = &text[..text.len() - content.len()];
+        if prefix.contains('\t') {
+            return error(number, "tabs are not allowed in indentation");
+        }
+        lines.push(Line {
+            number,
+            indent: prefix.len(),
+            content: content.to_string(),
+        });
+        continued = open_brackets(content) > 0;
+    }
+
+    if continued {
+        return error(lines.last().unwrap().number, "unterminated flow collection");
+    }
+    Ok(lines)
+}
+
+fn is_sequence_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+/// Finds the colon separating a key from its value outside of quotes and
+/// brackets
+fn find_key_separator(content: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut depth = 0;
+    let bytes = content.as_bytes();
+    for (i, c) in content.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' if i == 0 => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                ':' if depth == 0 && (i + 1 == bytes.len() || bytes[i + 1] == b' ') => {
+                    return Some(i)
+                }
+                _ => {}
+            },
+        }
+    }
+    None
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+impl Parser {
+    fn parse_block(&mut self, indent: usize) -> Result<YamlNode, YamlError> {
+        let line = &self.lines[self.pos];
+        if is_sequence_item(&line.content) {
+            self.parse_sequence(indent)
+        } else if find_key_separator(&line.content).is_some() {
+            self.parse_mapping(indent)
+        } else {
+            let node = parse_flow(&line.content, line.number)?;
+            self.pos += 1;
+            Ok(node)
+        }
+    }
+
+    /// Parses the block nested under the line before the current one, if any
+    fn parse_nested(&mut self, parent_indent: usize, line: usize) -> Result<YamlNode, YamlError> {
+        if self.pos < self.lines.len() {
+            let next = &self.lines[self.pos];
+            if next.indent > parent_indent {
+                return self.parse_block(next.indent);
+            }
+        }
+        Ok(YamlNode::new(line, YamlValue::Scalar(String::new())))
+    }
+
+    fn parse_sequence(&mut self, indent: usize) -> Result<YamlNode, YamlError> {
+        let start = self.lines[self.pos].number;
+        let mut items = Vec::new();
+        while self.pos < self.lines.len() {
+            let line = &self.lines[self.pos];
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return error(line.number, "unexpected indentation");
+            }
+            if !is_sequence_item(&line.content) {
+                break;
+            }
+
+            let number = line.number;
+            let rest = line.content[1..].trim_start();
+            if rest.is_empty() {
+                self.pos += 1;
+                items.push(self.parse_nested(indent, number)?);
+                continue;
+            }
+
+            // Treat the content following the dash as if it started a line
+            // of its own, so that the mappings may continue below it
+            let offset = line.content.len() - rest.len();
+            let rest = rest.to_string();
+            self.lines[self.pos].indent = indent + offset;
+            self.lines[self.pos].content = rest;
+            items.push(self.parse_block(indent + offset)?);
+        }
+        Ok(YamlNode::new(start, YamlValue::Sequence(items)))
+    }
+
+    fn parse_mapping(&mut self, indent: usize) -> Result<YamlNode, YamlError> {
+        let start = self.lines[self.pos].number;
+        let mut entries: Vec<(String, YamlNode)> = Vec::new();
+        while self.pos < self.lines.len() {
+            let line = &self.lines[self.pos];
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return error(line.number, "unexpected indentation");
+            }
+            if is_sequence_item(&line.content) {
+                return error(line.number, "expected a key, got a sequence item");
+            }
+
+            let number = line.number;
+            let sep = match find_key_separator(&line.content) {
+                Some(sep) => sep,
+                None => return error(number, "expected a key"),
+            };
+            let key = unquote(line.content[..sep].trim(), number)?;
+            if entries.iter().any(|(k, _)| *k == key) {
+                return error(number, &format!("duplicate key: {}", key));
+            }
+            let rest = line.content[sep + 1..].trim().to_string();
+            self.pos += 1;
+
+            let value = if !rest.is_empty() {
+                parse_flow(&rest, number)?
+            } else if self.pos < self.lines.len()
+                && self.lines[self.pos].indent == indent
+                && is_sequence_item(&self.lines[self.pos].content)
+            {
+                // Sequences may be indented at the same level as their key
+                self.parse_sequence(indent)?
+            } else {
+                self.parse_nested(indent, number)?
+            };
+            entries.push((key, value));
+        }
+        Ok(YamlNode::new(start, YamlValue::Mapping(entries)))
+    }
+}
+
+fn unquote(text: &str, line: usize) -> Result<String, YamlError> {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(q) if q == '"' || q == '\'' => {
+            if text.len() < 2 || !text.ends_with(q) {
+                return error(line, "unterminated string");
+            }
+            Ok(text[1..text.len() - 1].to_string())
+        }
+        _ => Ok(text.to_string()),
+    }
+}
+
+/// Parses a scalar or a flow collection spanning the whole text
+fn parse_flow(text: &str, line: usize) -> Result<YamlNode, YamlError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut pos = 0;
+    let node = parse_flow_value(&chars, &mut pos, line, false)?;
+    skip_spaces(&chars, &mut pos);
+    if pos != chars.len() {
+        return error(line, "unexpected characters after a value");
+    }
+    Ok(node)
+}
+
+fn skip_spaces(chars: &[char], pos: &mut usize) {
+    while *pos < chars.len() && chars[*pos].is_whitespace() {
+        *pos += 1;
+    }
+}
+
+fn parse_flow_value(
+    chars: &[char],
+    pos: &mut usize,
+    line: usize,
+    nested: bool,
+) -> Result<YamlNode, YamlError> {
+    skip_spaces(chars, pos);
+    match chars.get(*pos) {
+        Some('[') => {
+            *pos += 1;
+            let mut items = Vec::new();
+            loop {
+                skip_spaces(chars, pos);
+                match chars.get(*pos) {
+                    Some(']') => {
+                        *pos += 1;
+                        break;
+                    }
+                    None => return error(line, "unterminated sequence"),
+                    _ => {}
+                }
+                items.push(parse_flow_value(chars, pos, line, true)?);
+                skip_spaces(chars, pos);
+                match chars.get(*pos) {
+                    Some(',') => *pos += 1,
+                    Some(']') => {}
+                    _ => return error(line, "expected ',' or ']' in a sequence"),
+                }
+            }
+            Ok(YamlNode::new(line, YamlValue::Sequence(items)))
+        }
+        Some('{') => {
+            *pos += 1;
+            let mut entries: Vec<(String, YamlNode)> = Vec::new();
+            loop {
+                skip_spaces(chars, pos);
+                match chars.get(*pos) {
+                    Some('}') => {
+                        *pos += 1;
+                        break;
+                    }
+                    None => return error(line, "unterminated mapping"),
+                    _ => {}
+                }
+                let key = match parse_flow_value(chars, pos, line, true)?.value {
+                    YamlValue::Scalar(key) => key,
+                    _ => return error(line, "mapping keys must be scalars"),
+                };
+                skip_spaces(chars, pos);
+                if chars.get(*pos) != Some(&':') {
+                    return error(line, "expected ':' after a key");
+                }
+                *pos += 1;
+                let value = parse_flow_value(chars, pos, line, true)?;
+                if entries.iter().any(|(k, _)| *k == key) {
+                    return error(line, &format!("duplicate key: {}", key));
+                }
+                entries.push((key, value));
+                skip_spaces(chars, pos);
+                match chars.get(*pos) {
+                    Some(',') => *pos += 1,
+                    Some('}') => {}
+                    _ => return error(line, "expected ',' or '}' in a mapping"),
+                }
+            }
+            Ok(YamlNode::new(line, YamlValue::Mapping(entries)))
+        }
+        Some(q) if *q == '"' || *q == '\'' => {
+            let q = *q;
+            *pos += 1;
+            let start = *pos;
+            while *pos < chars.len() && chars[*pos] != q {
+                *pos += 1;
+            }
+            if *pos == chars.len() {
+                return error(line, "unterminated string");
+            }
+            let s: String = chars[start..*pos].iter().collect();
+            *pos += 1;
+            Ok(YamlNode::new(line, YamlValue::Scalar(s)))
+        }
+        _ => {
+            // Inside of the flow collections the plain scalars end at the
+            // indicators
+            let start = *pos;
+            while *pos < chars.len() {
+                let c = chars[*pos];
+                if nested
+                    && (c == ',' || c == ']' || c == '}' || (c == ':' && is_separator(chars, *pos)))
+                {
+                    break;
+                }
+                *pos += 1;
+            }
+            let s: String = chars[start..*pos].iter().collect();
+            Ok(YamlNode::new(line, YamlValue::Scalar(s.trim().to_string())))
+        }
+    }
+}
+
+fn is_separator(chars: &[char], pos: usize) -> bool {
+    match chars.get(pos + 1) {
+        None => true,
+        Some(c) => c.is_whitespace() || *c == ',' || *c == ']' || *c == '}',
+    }
+}
+
+/// Parses a YAML document; an empty document gives an empty mapping
+pub fn parse_yaml(input: &str) -> Result<YamlNode, YamlError> {
+    let lines = split_lines(input)?;
+    if lines.is_empty() {
+        return Ok(YamlNode::new(1, YamlValue::Mapping(Vec::new())));
+    }
+
+    let indent = lines[0].indent;
+    let mut parser = Parser { lines, pos: 0 };
+    let node = parser.parse_block(indent)?;
+    if parser.pos < parser.lines.len() {
+        return error(
+            parser.lines[parser.pos].number,
+            "unexpected content after the document",
+        );
+    }
+    Ok(node)
+}
@@ -0,0 +1,525 @@
+// Copyright 2023 Lukasz Janyst <lukasz@jany.st>
+// Licensed under the MIT license, see the LICENSE file for details.
+
+//! Zlib streams with deflate compression and decompression
+//! See: https://www.rfc-editor.org/rfc/rfc1950 and https://www.rfc-editor.org/rfc/rfc1951
+
+use std::fmt;
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: usize = 15;
+const MAX_STORED_BLOCK: usize = 65535;
+
+const LENGTH_BASE: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compression {
+    /// Stored blocks, no compression
+    Stored,
+    /// LZ77 with the fixed Huffman codes
+    Fixed,
+}
+
+/// Writes the bits starting from the least significant one as required by
+/// deflate
+struct BitWriter {
+    data: Vec<u8>,
+    acc: u32,
+    nbits: u8,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            data: Vec::new(),
+            acc: 0,
+            nbits: 0,
+        }
+    }
+
+    fn write_bits(&mut self, value: u32, nbits: u8) {
+        for i in 0..nbits {
+            self.acc |= ((value >> i) & 1) << self.nbits;
+            self.nbits += 1;
+            if self.nbits == 8 {
+                self.data.push(self.acc as u8);
+                self.acc = 0;
+                self.nbits = 0;
+            }
+        }
+    }
+
+    /// Huffman codes are packed starting from the most significant bit
+    fn write_code(&mut self, code: u32, nbits: u8) {
+        for i in (0..nbits).rev() {
+            self.write_bits((code >> i) & 1, 1);
+        }
+    }
+
+    fn align(&mut self) {
+        if self.nbits != 0 {
+            self.data.push(self.acc as u8);
+            self.acc = 0;
+            self.nbits = 0;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.data
+    }
+}
+
+/// Adler-32 checksum of the uncompressed data
+pub fn adler32(data: &[u8]) -> u32 {
+    let mut a = 1_u32;
+    let mut b = 0_u32;
+    for chunk in data.chunks(5552) {
+        for byte in chunk.iter() {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// Compresses the data into a zlib stream
+pub fn zlib_compress(data: &[u8], compression: Compression) -> Vec<u8> {
+    // CMF: deflate with 32K window; FLG: no dictionary, check bits making
+    // CMF * 256 + FLG a multiple of 31
+    let mut res = vec![0x78, 0x01];
+    match compression {
+        Compression::Stored => res.append(&mut deflate_stored(data)),
+        Compression::Fixed => {
+            // Incompressible data is better off stored
+            let mut compressed = deflate_fixed(data);
+            if compressed.len() > data.len() + data.len() / MAX_STORED_BLOCK * 5 + 5 {
+                compressed = deflate_stored(data);
+            }
+            res.append(&mut compressed);
+        }
+    }
+    res.extend_from_slice(&adler32(data).to_be_bytes());
+    res
+}
+
+fn deflate_stored(data: &[u8]) -> Vec<u8> {
+    let mut res = Vec::new();
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        // An empty stream still needs a final block
+        res.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+        return res;
+    }
+
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        res.push(last as u8);
+        res.extend_from_slice(&len.to_le_bytes());
+        res.extend_from_slice(&(!len).to_le_bytes());
+        res.extend_from_slice(chunk);
+    }
+    res
+}
+
+fn write_fixed_literal(w: &mut BitWriter, lit: usize) {
+    match lit {
+        0..=143 => w.write_code(0x30 + lit as u32, 8),
+        144..=255 => w.write_code(0x190 + (lit - 144) as u32, 9),
+        256..=279 => w.write_code((lit - 256) as u32, 7),
+        _ => w.write_code(0xc0 + (lit - 280) as u32, 8),
+    }
+}
+
+fn write_fixed_match(w: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE
+        .iter()
+        .rposition(|base| *base <= length)
+        .unwrap();
+    write_fixed_literal(w, 257 + code);
+    w.write_bits((length - LENGTH_BASE[code]) as u32, LENGTH_EXTRA[code]);
+
+    let code = DIST_BASE
+        .iter()
+        .rposition(|base| *base <= distance)
+        .unwrap();
+    w.write_code(code as u32, 5);
+    w.write_bits((distance - DIST_BASE[code]) as u32, DIST_EXTRA[code]);
+}
+
+fn hash(data: &[u8], pos: usize) -> usize {
+    let v = (data[pos] as usize) << 16 | (data[pos + 1] as usize) << 8 | data[pos + 2] as usize;
+    (v.wrapping_mul(2654435761) >> 8) & ((1 << HASH_BITS) - 1)
+}
+
+/// A single block of LZ77-compressed data using the fixed Huffman codes
+fn deflate_fixed(data: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter::new();
+    w.write_bits(1, 1); // BFINAL
+    w.write_bits(1, 2); // BTYPE = fixed Huffman
+
+    // head holds the most recent position for each hash and prev chains the
+    // older positions with the same hash
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |pos: usize, head: &mut Vec<usize>, prev: &mut Vec<usize>| {
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash(data, pos);
+            prev[pos % WINDOW_SIZE] = head[h];
+            head[h] = pos;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+
+        if pos + MIN_MATCH <= data.len() {
+            let max_len = MAX_MATCH.min(data.len() - pos);
+            let mut candidate = head[hash(data, pos)];
+            let mut chain = 0;
+            while candidate != usize::MAX && pos - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(data[pos..pos + max_len].iter())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = pos - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW_SIZE];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            write_fixed_match(&mut w, best_len, best_dist);
+            for p in pos..pos + best_len {
+                insert(p, &mut head, &mut prev);
+            }
+            pos += best_len;
+        } else {
+            write_fixed_literal(&mut w, data[pos] as usize);
+            insert(pos, &mut head, &mut prev);
+            pos += 1;
+        }
+    }
+
+    write_fixed_literal(&mut w, 256); // end of block
+    w.finish()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZlibError {
+    Truncated,
+    Corrupt(String),
+}
+
+impl fmt::Display for ZlibError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ZlibError::Truncated => write!(f, "truncated zlib stream"),
+            ZlibError::Corrupt(message) => write!(f, "corrupt zlib stream: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ZlibError {}
+
+fn corrupt(message: &str) -> ZlibError {
+    ZlibError::Corrupt(message.to_string())
+}
+
+/// Reads the bits starting from the least significant one
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            data,
+            pos: 0,
+            bit: 0,
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<u32, ZlibError> {
+        if self.pos >= self.data.len() {
+            return Err(ZlibError::Truncated);
+        }
+        let bit = (self.data[self.pos] >> self.bit) & 1;
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+        Ok(bit as u32)
+    }
+
+    fn read_bits(&mut self, nbits: u8) -> Result<u32, ZlibError> {
+        let mut value = 0;
+        for i in 0..nbits {
+            value |= self.read_bit()? << i;
+        }
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], ZlibError> {
+        if self.pos + len > self.data.len() {
+            return Err(ZlibError::Truncated);
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+}
+
+/// Canonical Huffman code described by the number of codes of each length
+/// and the symbols ordered by their codes
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, ZlibError> {
+        let mut counts = [0_u16; 16];
+        for len in lengths.iter() {
+            counts[*len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Over-subscribed codes can't be decoded unambiguously; incomplete
+        // ones are fine as long as the missing codes never show up
+        let mut left = 1_i32;
+        for count in counts.iter().skip(1) {
+            left = left * 2 - *count as i32;
+            if left < 0 {
+                return Err(corrupt("over-subscribed Huffman code"));
+            }
+        }
+
+        let mut offsets = [0_u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, len) in lengths.iter().enumerate() {
+            if *len != 0 {
+                symbols[offsets[*len as usize] as usize] = symbol as u16;
+                offsets[*len as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, r: &mut BitReader) -> Result<usize, ZlibError> {
+        let mut code = 0_i32;
+        let mut first = 0_i32;
+        let mut index = 0_i32;
+        for len in 1..16 {
+            code |= r.read_bit()? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize] as usize);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(corrupt("invalid Huffman code"))
+    }
+}
+
+fn fixed_huffman() -> (Huffman, Huffman) {
+    let mut lengths = [0_u8; 288];
+    for (symbol, len) in lengths.iter_mut().enumerate() {
+        *len = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    (
+        Huffman::new(&lengths).unwrap(),
+        Huffman::new(&[5; 30]).unwrap(),
+    )
+}
+
+fn dynamic_huffman(r: &mut BitReader) -> Result<(Huffman, Huffman), ZlibError> {
+    const ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+    let nlen = r.read_bits(5)? as usize + 257;
+    let ndist = r.read_bits(5)? as usize + 1;
+    let ncode = r.read_bits(4)? as usize + 4;
+    if nlen > 286 || ndist > 30 {
+        return Err(corrupt("too many length or distance codes"));
+    }
+
+    let mut lengths = [0_u8; 19];
+    for index in ORDER.iter().take(ncode) {
+        lengths[*index] = r.read_bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&lengths)?;
+
+    let mut lengths = vec![0_u8; nlen + ndist];
+    let mut index = 0;
+    while index < nlen + ndist {
+        let symbol = code_lengths.decode(r)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                if index == 0 {
+                    return Err(corrupt("repeating a length with no previous one"));
+                }
+                (lengths[index - 1], 3 + r.read_bits(2)? as usize)
+            }
+            17 => (0, 3 + r.read_bits(3)? as usize),
+            _ => (0, 11 + r.read_bits(7)? as usize),
+        };
+        if index + repeat > nlen + ndist {
+            return Err(corrupt("too many code lengths"));
+        }
+        for len in lengths[index..index + repeat].iter_mut() {
+            *len = value;
+        }
+        index += repeat;
+    }
+
+    if lengths[256] == 0 {
+        return Err(corrupt("missing end-of-block code"));
+    }
+    Ok((
+        Huffman::new(&lengths[..nlen])?,
+        Huffman::new(&lengths[nlen..])?,
+    ))
+}
+
+fn inflate_block(
+    r: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> Result<(), ZlibError> {
+    loop {
+        let symbol = lit.decode(r)?;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let code = symbol - 257;
+                let length = LENGTH_BASE[code] + r.read_bits(LENGTH_EXTRA[code])? as usize;
+                let code = dist.decode(r)?;
+                if code >= 30 {
+                    return Err(corrupt("invalid distance code"));
+                }
+                let distance = DIST_BASE[code] + r.read_bits(DIST_EXTRA[code])? as usize;
+                if distance > out.len() {
+                    return Err(corrupt("distance too far back"));
+                }
+                // The match may overlap with the bytes it produces, so we
+                // copy them one by one
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+            _ => return Err(corrupt("invalid literal/length code")),
+        }
+    }
+}
+
+/// Decompresses a zlib stream verifying its checksum
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, ZlibError> {
+    if data.len() < 2 {
+        return Err(ZlibError::Truncated);
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0f != 8 || cmf >> 4 > 7 {
+        return Err(corrupt("not a deflate stream"));
+    }
+    if !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err(corrupt("bad header check bits"));
+    }
+    if flg & 0x20 != 0 {
+        return Err(corrupt("preset dictionaries are not supported"));
+    }
+
+    let mut r = BitReader::new(&data[2..]);
+    let mut out = Vec::new();
+    loop {
+        let last = r.read_bit()? == 1;
+        match r.read_bits(2)? {
+            0 => {
+                r.align();
+                let header = r.read_bytes(4)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+                if len != !nlen {
+                    return Err(corrupt("stored block length mismatch"));
+                }
+                out.extend_from_slice(r.read_bytes(len as usize)?);
+            }
+            1 => {
+                let (lit, dist) = fixed_huffman();
+                inflate_block(&mut r, &mut out, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_huffman(&mut r)?;
+                inflate_block(&mut r, &mut out, &lit, &dist)?;
+            }
+            _ => return Err(corrupt("invalid block type")),
+        }
+        if last {
+            break;
+        }
+    }
+
+    r.align();
+    let checksum = r.read_bytes(4)?;
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&out) {
+        return Err(corrupt("checksum mismatch"));
+    }
+    Ok(out)
+}
@@ -0,0 +1,71 @@
+use ray_tracer::{color, vector, Background, Canvas, CubeFace, Texture, TextureWrap};
+
+#[test]
+fn see_solid_and_gradient_backgrounds() {
+    let b = Background::default();
+    assert_eq!(b.color_at(vector(0.0, 1.0, 0.0)), color(0.0, 0.0, 0.0));
+
+    let b = Background::Solid(color(0.2, 0.3, 0.4));
+    assert_eq!(b.color_at(vector(1.0, -2.0, 3.0)), color(0.2, 0.3, 0.4));
+
+    let b = Background::Gradient {
+        bottom: color(1.0, 1.0, 1.0),
+        top: color(0.0, 0.5, 1.0),
+    };
+    assert_eq!(b.color_at(vector(0.0, 1.0, 0.0)), color(0.0, 0.5, 1.0));
+    assert_eq!(b.color_at(vector(0.0, -3.0, 0.0)), color(1.0, 1.0, 1.0));
+    assert_eq!(b.color_at(vector(0.0, 0.0, 2.0)), color(0.5, 0.75, 1.0));
+    assert_eq!(
+        b.color_at(vector(1.0, 1.0, 0.0)),
+        b.color_at(vector(0.0, 1.0, 1.0))
+    );
+}
+
+#[test]
+fn see_equirectangular_backgrounds() {
+    // The columns face -z, +x, +z and -x in turn
+    let mut image = Canvas::new(4, 1);
+    image.set(0, 0, &color(1.0, 0.0, 0.0));
+    image.set(1, 0, &color(0.0, 1.0, 0.0));
+    image.set(2, 0, &color(0.0, 0.0, 1.0));
+    image.set(3, 0, &color(1.0, 1.0, 0.0));
+    let b = Background::Equirectangular(Texture::new(image, TextureWrap::Wrap));
+
+    assert_eq!(b.color_at(vector(1.0, 0.0, -1.0)), color(1.0, 0.0, 0.0));
+    assert_eq!(b.color_at(vector(1.0, 0.0, 1.0)), color(0.0, 1.0, 0.0));
+    assert_eq!(b.color_at(vector(-2.0, 0.0, 2.0)), color(0.0, 0.0, 1.0));
+    assert_eq!(b.color_at(vector(-1.0, 0.0, -1.0)), color(1.0, 1.0, 0.0));
+
+    // Straight ahead falls between two columns
+    assert_eq!(b.color_at(vector(0.0, 0.0, 1.0)), color(0.0, 0.5, 0.5));
+}
+
+#[test]
+fn see_cube_map_backgrounds() {
+    let faces = [
+        (CubeFace::Left, vector(-1.0, 0.2, 0.3), color(1.0, 0.0, 0.0)),
+        (
+            CubeFace::Right,
+            vector(5.0, -1.0, 2.0),
+            color(0.0, 1.0, 0.0),
+        ),
+        (CubeFace::Front, vector(0.1, 0.1, 1.0), color(0.0, 0.0, 1.0)),
+        (CubeFace::Back, vector(0.0, 0.0, -1.0), color(1.0, 1.0, 0.0)),
+        (CubeFace::Up, vector(0.5, 0.9, 0.0), color(0.0, 1.0, 1.0)),
+        (CubeFace::Down, vector(0.0, -1.0, 0.0), color(1.0, 0.0, 1.0)),
+    ];
+    let mut image = Canvas::new(8, 6);
+    for (face, _, c) in faces.iter() {
+        let (col, row) = face.cell();
+        for x in 0..2 {
+            for y in 0..2 {
+                image.set(2 * col + x, 2 * row + y, c);
+            }
+        }
+    }
+
+    let b = Background::CubeMap(Texture::new(image, TextureWrap::Wrap));
+    for (_, direction, c) in faces.iter() {
+        assert_eq!(b.color_at(*direction), *c);
+    }
+}
@@ -0,0 +1,112 @@
+use ray_tracer::{
+    cone_min_max, cube_unit, cylinder_min_max, cylinder_unit, group, plane_unit, point, rotation_y,
+    scaling, sphere, sphere_unit, translation, triangle, vector, Bounds, Ray,
+};
+
+use std::f64::consts::{FRAC_1_SQRT_2, PI, SQRT_2};
+
+#[test]
+fn compute_shape_bounds() {
+    let b = sphere_unit().bounds();
+    assert_eq!(b.min(), point(-1.0, -1.0, -1.0));
+    assert_eq!(b.max(), point(1.0, 1.0, 1.0));
+
+    let b = cube_unit().bounds();
+    assert_eq!(b.min(), point(-1.0, -1.0, -1.0));
+    assert_eq!(b.max(), point(1.0, 1.0, 1.0));
+
+    let b = cylinder_min_max(-5.0, 3.0, true).bounds();
+    assert_eq!(b.min(), point(-1.0, -5.0, -1.0));
+    assert_eq!(b.max(), point(1.0, 3.0, 1.0));
+
+    let b = cone_min_max(-5.0, 3.0, true).bounds();
+    assert_eq!(b.min(), point(-5.0, -5.0, -5.0));
+    assert_eq!(b.max(), point(5.0, 3.0, 5.0));
+
+    let b = triangle(
+        point(-3.0, 7.0, 2.0),
+        point(6.0, 2.0, -4.0),
+        point(2.0, -1.0, -1.0),
+    )
+    .bounds();
+    assert_eq!(b.min(), point(-3.0, -1.0, -4.0));
+    assert_eq!(b.max(), point(6.0, 7.0, 2.0));
+}
+
+#[test]
+fn compute_unbounded_shape_bounds() {
+    assert!(!plane_unit().bounds().is_finite());
+    assert!(!cylinder_unit().bounds().is_finite());
+    assert!(sphere_unit().bounds().is_finite());
+}
+
+#[test]
+fn transform_bounds() {
+    let b = Bounds::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0));
+    let t = b.transformed(rotation_y(PI / 4.0) * scaling(1.0, 2.0, 1.0));
+    assert_eq!(t.min(), point(-SQRT_2, -2.0, -SQRT_2));
+    assert_eq!(t.max(), point(SQRT_2, 2.0, SQRT_2));
+
+    let s = sphere(translation(1.0, -3.0, 5.0) * scaling(0.5, 2.0, 4.0));
+    assert_eq!(s.bounds().min(), point(0.5, -5.0, 1.0));
+    assert_eq!(s.bounds().max(), point(1.5, -1.0, 9.0));
+}
+
+#[test]
+fn compute_group_bounds() {
+    let g = group(
+        vec![
+            sphere(translation(2.0, 5.0, -3.0) * scaling(2.0, 2.0, 2.0)),
+            cylinder_min_max(-2.0, 2.0, true),
+        ],
+        translation(1.0, 0.0, 0.0),
+    );
+    assert_eq!(g.bounds().min(), point(0.0, -2.0, -5.0));
+    assert_eq!(g.bounds().max(), point(5.0, 7.0, 1.0));
+    assert_eq!(g.local_bounds().min(), point(-1.0, -2.0, -5.0));
+}
+
+#[test]
+fn merge_bounds() {
+    let b1 = Bounds::new(point(-5.0, -2.0, 0.0), point(7.0, 4.0, 4.0));
+    let b2 = Bounds::new(point(8.0, -7.0, -2.0), point(14.0, 2.0, 8.0));
+    let b = b1.merged(&b2);
+    assert_eq!(b.min(), point(-5.0, -7.0, -2.0));
+    assert_eq!(b.max(), point(14.0, 4.0, 8.0));
+    assert_eq!(Bounds::empty().merged(&b1), b1);
+    assert!(b.contains_point(point(10.0, 0.0, 0.0)));
+    assert!(!b.contains_point(point(10.0, 10.0, 0.0)));
+    assert_eq!(
+        b1.surface_area(),
+        2.0 * (12.0 * 6.0 + 6.0 * 4.0 + 4.0 * 12.0)
+    );
+}
+
+#[test]
+fn intersect_ray_with_bounds() {
+    let b = Bounds::new(point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0));
+    let hit = |origin, direction| b.intersects(&Ray::new(origin, direction));
+
+    assert!(hit(point(15.0, 1.0, 2.0), vector(-1.0, 0.0, 0.0)));
+    assert!(hit(point(-5.0, -1.0, 4.0), vector(1.0, 0.0, 0.0)));
+    assert!(hit(point(7.0, 6.0, 5.0), vector(0.0, -1.0, 0.0)));
+    assert!(hit(point(9.0, -5.0, 6.0), vector(0.0, 1.0, 0.0)));
+    assert!(hit(point(8.0, 2.0, 12.0), vector(0.0, 0.0, -1.0)));
+    assert!(hit(point(8.0, 1.0, 3.5), vector(0.0, 0.0, 1.0)));
+    assert!(!hit(
+        point(9.0, -1.0, -8.0),
+        vector(2.0, 4.0, 6.0).normalized()
+    ));
+    assert!(!hit(
+        point(8.0, 3.0, -4.0),
+        vector(6.0, 2.0, 4.0).normalized()
+    ));
+    assert!(!hit(point(12.0, 5.0, 4.0), vector(-1.0, 0.0, 0.0)));
+    assert!(!hit(
+        point(8.0, 2.0, 12.0),
+        vector(FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2)
+    ));
+
+    // boxes behind the ray count
+    assert!(hit(point(15.0, 1.0, 2.0), vector(1.0, 0.0, 0.0)));
+}
@@ -0,0 +1,196 @@
+use ray_tracer::{
+    color, cube, cylinder_min_max, intersect, plane, point, point_light, rotation_x, rotation_y,
+    scaling, sphere, sphere_glass, translation, triangle, vector, view_transform, Bvh, Camera,
+    Group, Ray, Shape, World,
+};
+
+use std::f64::consts::PI;
+
+fn triangle_fan(n: usize) -> Vec<Box<dyn Shape>> {
+    let mut shapes = Vec::new();
+    for i in 0..n {
+        let a1 = 2.0 * PI * i as f64 / n as f64;
+        let a2 = 2.0 * PI * (i + 1) as f64 / n as f64;
+        shapes.push(triangle(
+            point(0.0, 1.0, 0.0),
+            point(a1.cos(), 0.0, a1.sin()),
+            point(a2.cos(), 0.0, a2.sin()),
+        ));
+    }
+    shapes
+}
+
+fn test_world() -> World {
+    let mut w = World::empty();
+    w.lights
+        .push(point_light(point(-10.0, 10.0, -10.0), color(1.0, 1.0, 1.0)));
+
+    let mut floor = plane(translation(0.0, -1.0, 0.0));
+    floor.material_mut().reflective = 0.3;
+    w.shapes.push(floor);
+
+    for i in 0..6 {
+        for j in 0..6 {
+            let x = i as f64 - 2.5;
+            let z = j as f64 - 2.5;
+            let mut s = if (i + j) % 2 == 0 {
+                sphere(translation(x, -0.6, z) * scaling(0.4, 0.4, 0.4))
+            } else {
+                cube(translation(x, -0.7, z) * rotation_y(0.3 * x) * scaling(0.3, 0.3, 0.3))
+            };
+            s.material_mut().color = color(i as f64 / 6.0, j as f64 / 6.0, 0.5);
+            s.material_mut().reflective = 0.1;
+            w.shapes.push(s);
+        }
+    }
+
+    let mut glass = sphere_glass();
+    glass.transform(translation(0.0, 0.5, -1.0) * scaling(0.7, 0.7, 0.7));
+    glass.material_mut().reflective = 0.9;
+    w.shapes.push(glass);
+
+    let mut pyramid = Group::new();
+    for t in triangle_fan(12) {
+        pyramid.add_child(t);
+    }
+    pyramid.add_child(cylinder_min_max(0.0, 0.2, true));
+    pyramid.transform(translation(1.5, 0.3, 1.5) * rotation_x(0.2));
+    w.shapes.push(Box::new(pyramid));
+    w
+}
+
+#[test]
+fn render_identically_with_bvh() {
+    let mut c = Camera::new(48, 32, PI / 3.0);
+    c.set_transform(view_transform(
+        point(-2.0, 3.0, -6.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+
+    let mut w = test_world();
+    let img1 = c.render(&w);
+    w.build_bvh();
+    assert!(w.has_bvh());
+    let img2 = c.render(&w);
+
+    for x in 0..img1.width() {
+        for y in 0..img1.height() {
+            for i in 0..3 {
+                assert_eq!(img1.at(x, y).at(i), img2.at(x, y).at(i));
+            }
+        }
+    }
+}
+
+#[test]
+fn find_any_hit_with_bvh() {
+    // Compare against the full list of the intersections for rays fanned out
+    // over the scene, both with and without the BVH
+    let mut w = test_world();
+    let mut rays = Vec::new();
+    for i in 0..24 {
+        for j in 0..24 {
+            let target = point(i as f64 / 4.0 - 3.0, -0.6, j as f64 / 4.0 - 3.0);
+            let origin = point(-4.0, 3.0, -6.0);
+            rays.push(Ray::new(origin, (target - origin).normalized()));
+        }
+    }
+
+    let expected = |w: &World, r: &Ray, max_t: f64, opaque: bool| {
+        let xs = w.intersect(r);
+        (0..xs.len()).any(|i| {
+            let x = xs.at(i);
+            x.t() > 0.0 && x.t() < max_t && (!opaque || x.shape().material().transparency == 0.0)
+        })
+    };
+    for build in [false, true] {
+        if build {
+            w.build_bvh();
+        }
+        for r in rays.iter() {
+            for max_t in [2.0, 5.0, 8.0, f64::INFINITY] {
+                assert_eq!(
+                    w.any_hit(r, max_t, &|_| true),
+                    expected(&w, r, max_t, false)
+                );
+                assert_eq!(
+                    w.any_hit(r, max_t, &|m| m.transparency == 0.0),
+                    expected(&w, r, max_t, true)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn intersect_group_with_bvh() {
+    let mut g1 = Group::new();
+    let mut g2 = Group::new();
+    for t in triangle_fan(64) {
+        g1.add_child(t);
+    }
+    for t in triangle_fan(64) {
+        g2.add_child(t);
+    }
+    g2.build_bvh();
+
+    for i in 0..20 {
+        let x = i as f64 / 10.0 - 1.0;
+        let r = Ray::new(point(x, 0.5, -5.0), vector(0.1, -0.05, 1.0).normalized());
+        let xs1 = intersect(&g1, &r);
+        let xs2 = intersect(&g2, &r);
+        assert_eq!(xs1.len(), xs2.len());
+        for (x1, x2) in xs1.iter().zip(xs2.iter()) {
+            assert_eq!(x1.t(), x2.t());
+        }
+    }
+}
+
+#[test]
+fn intersect_shapes_added_after_bvh() {
+    let mut w = World::empty();
+    for i in 0..10 {
+        w.shapes.push(sphere(translation(3.0 * i as f64, 0.0, 0.0)));
+    }
+    w.build_bvh();
+    w.shapes.push(sphere(translation(0.0, 0.0, 10.0)));
+
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(w.intersect(&r).len(), 4);
+    let r = Ray::new(point(27.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(w.intersect(&r).len(), 2);
+    let r = Ray::new(point(1.5, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(w.intersect(&r).len(), 0);
+}
+
+#[test]
+fn intersect_shapes_removed_after_bvh() {
+    let mut w = World::empty();
+    for i in 0..10 {
+        w.shapes.push(sphere(translation(3.0 * i as f64, 0.0, 0.0)));
+    }
+    w.build_bvh();
+    w.shapes.truncate(5);
+
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(w.intersect(&r).len(), 2);
+    assert!(w.any_hit(&r, f64::INFINITY, &|_| true));
+    let r = Ray::new(point(27.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(w.intersect(&r).len(), 0);
+    assert!(!w.any_hit(&r, f64::INFINITY, &|_| true));
+}
+
+#[test]
+fn split_bvh() {
+    let shapes = triangle_fan(64);
+    let bvh = Bvh::new(&shapes);
+    assert!(bvh.size() > 1);
+
+    let shapes = vec![
+        sphere(translation(1.0, 0.0, 0.0)),
+        plane(scaling(1.0, 1.0, 1.0)),
+    ];
+    let bvh = Bvh::new(&shapes);
+    assert_eq!(bvh.size(), 1);
+}
@@ -1,8 +1,11 @@
 use ray_tracer::{
-    color, feq, point, rotation_y, translation, vector, view_transform, Camera, World,
+    color, feq, point, rotation_y, translation, vector, view_transform, AdaptiveSampling,
+    Background, Camera, Canvas, Projection, ReconstructionFilter, Region, SamplingPattern, World,
+    DEFAULT_MAX_DEPTH,
 };
 
 use std::f64::consts::{FRAC_1_SQRT_2, PI};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[test]
 fn compute_camera_pixel_size() {
@@ -49,3 +52,494 @@ fn render_the_default_world() {
     let img = c.render(&w);
     assert_eq!(img.at(5, 5), color(0.38066, 0.47583, 0.2855));
 }
+
+#[test]
+fn render_identically_with_threads() {
+    let w = World::default();
+    let mut c = Camera::new(37, 23, PI / 2.0);
+    c.set_transform(view_transform(
+        point(0.0, 1.0, -4.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+
+    c.set_threads(1);
+    let img1 = c.render(&w);
+
+    c.set_threads(4);
+    c.set_tile_size(5);
+    assert_eq!(c.threads(), 4);
+    assert_eq!(c.tile_size(), 5);
+    let img2 = c.render(&w);
+
+    for x in 0..img1.width() {
+        for y in 0..img1.height() {
+            for i in 0..3 {
+                assert_eq!(img1.at(x, y).at(i), img2.at(x, y).at(i));
+            }
+        }
+    }
+}
+
+#[test]
+fn share_world_between_threads() {
+    fn check<T: Send + Sync>() {}
+    check::<World>();
+
+    let mut c = Camera::new(10, 10, PI / 2.0);
+    c.set_threads(0);
+    assert_eq!(c.threads(), 1);
+}
+
+#[test]
+fn change_camera_resolution() {
+    let mut c = Camera::new(200, 125, PI / 2.0);
+    c.set_transform(translation(0.0, -2.0, 5.0));
+    c.set_resolution(400, 250);
+    assert_eq!(c.hres(), 400);
+    assert_eq!(c.vres(), 250);
+    assert!(feq(c.pixel_size(), 0.005));
+    assert_eq!(*c.transform(), translation(0.0, -2.0, 5.0));
+}
+
+#[test]
+fn construct_ray_through_subpixel() {
+    let c = Camera::new(201, 101, PI / 2.0);
+    let r = c.ray_for_subpixel(100, 50, 0.5, 0.5);
+    assert_eq!(r.direction(), c.ray_for_pixel(100, 50).direction());
+    let r = c.ray_for_subpixel(0, 0, 0.0, 0.0);
+    assert_eq!(r.origin(), point(0.0, 0.0, 0.0));
+    assert!(r.direction().x() > 0.66519 && r.direction().y() > 0.33259);
+}
+
+#[test]
+fn render_with_samples_and_progress() {
+    let w = World::default();
+    let mut c = Camera::new(11, 11, PI / 2.0);
+    c.set_transform(view_transform(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    c.set_tile_size(4);
+    assert_eq!(c.samples(), 1);
+    assert_eq!(c.max_depth(), DEFAULT_MAX_DEPTH);
+
+    // A single sample per pixel gives the same image as before
+    let done = AtomicUsize::new(0);
+    let image = c.render_with_progress(&w, &|finished, total| {
+        assert_eq!(total, 9);
+        assert!(finished <= total);
+        done.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(done.load(Ordering::Relaxed), 9);
+    assert_eq!(image.at(5, 5), color(0.38066, 0.47583, 0.2855));
+
+    // The samples are spread on a regular grid and averaged
+    c.set_samples(3);
+    let image = c.render(&w);
+    let mut expected = color(0.0, 0.0, 0.0);
+    for sy in [1.0, 3.0, 5.0] {
+        for sx in [1.0, 3.0, 5.0] {
+            let r = c.ray_for_subpixel(5, 5, sx / 6.0, sy / 6.0);
+            expected = expected + w.color_at(&r, DEFAULT_MAX_DEPTH);
+        }
+    }
+    assert_eq!(image.at(5, 5), expected / 9.0);
+    assert_ne!(image.at(5, 5), color(0.38066, 0.47583, 0.2855));
+    c.set_samples(0);
+    assert_eq!(c.samples(), 1);
+}
+
+#[test]
+fn render_reproducibly_with_jittered_samples() {
+    let w = World::default();
+    let mut c = Camera::new(21, 13, PI / 2.0);
+    c.set_transform(view_transform(
+        point(0.0, 1.0, -4.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    c.set_samples(2);
+    c.set_sampling(SamplingPattern::Jittered);
+    c.set_filter(ReconstructionFilter::Mitchell);
+    c.set_seed(7);
+    assert_eq!(c.sampling(), SamplingPattern::Jittered);
+    assert_eq!(c.filter(), ReconstructionFilter::Mitchell);
+    assert_eq!(c.seed(), 7);
+
+    c.set_threads(1);
+    let img1 = c.render(&w);
+    c.set_threads(3);
+    c.set_tile_size(4);
+    let img2 = c.render(&w);
+    for x in 0..img1.width() {
+        for y in 0..img1.height() {
+            assert_eq!(img1.at(x, y), img2.at(x, y));
+        }
+    }
+
+    c.set_seed(8);
+    let img3 = c.render(&w);
+    assert_ne!(img1.at(10, 6), img3.at(10, 6));
+}
+
+#[test]
+fn render_with_wide_filters() {
+    let w = World::default();
+    let mut c = Camera::new(11, 11, PI / 2.0);
+    c.set_transform(view_transform(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    c.set_samples(2);
+    c.set_filter(ReconstructionFilter::Tent);
+    let image = c.render(&w);
+
+    // The samples of a tent filter reach halfway into the neighbors and
+    // weigh the same on a regular grid
+    let mut expected = color(0.0, 0.0, 0.0);
+    for sy in [0.0, 1.0] {
+        for sx in [0.0, 1.0] {
+            let r = c.ray_for_subpixel(5, 5, sx, sy);
+            expected = expected + w.color_at(&r, DEFAULT_MAX_DEPTH);
+        }
+    }
+    assert_eq!(image.at(5, 5), expected / 4.0);
+}
+
+#[test]
+fn count_camera_rays() {
+    let w = World::default();
+    let mut c = Camera::new(11, 7, PI / 2.0);
+    let (_, stats) = c.render_with_stats(&w, &|_, _| {});
+    assert_eq!(stats.pixels, 77);
+    assert_eq!(stats.rays, 77);
+
+    c.set_samples(3);
+    let (_, stats) = c.render_with_stats(&w, &|_, _| {});
+    assert_eq!(stats.rays, 77 * 9);
+    assert_eq!(stats.rays_per_pixel(), 9.0);
+}
+
+#[test]
+fn render_with_adaptive_sampling() {
+    let w = World::default();
+    let mut c = Camera::new(11, 11, PI / 2.0);
+    c.set_transform(view_transform(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    c.set_tile_size(16);
+    assert_eq!(c.adaptive(), None);
+
+    // Without any subdivisions, the pixels average their corners
+    c.set_adaptive(Some(AdaptiveSampling::new(0.1, 0)));
+    let (image, stats) = c.render_with_stats(&w, &|_, _| {});
+    assert_eq!(stats.rays, 12 * 12);
+    let mut expected = color(0.0, 0.0, 0.0);
+    for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+        let r = c.ray_for_subpixel(5, 5, dx, dy);
+        expected = expected + w.color_at(&r, DEFAULT_MAX_DEPTH);
+    }
+    assert_eq!(image.at(5, 5), expected / 4.0);
+
+    // Only the pixels on the edges and highlights get subdivided
+    c.set_adaptive(Some(AdaptiveSampling::new(0.1, 2)));
+    let (image1, stats) = c.render_with_stats(&w, &|_, _| {});
+    assert!(stats.rays > 12 * 12);
+    assert!(stats.rays < 12 * 12 + 121 * 5 * 5);
+    assert_eq!(image1.at(0, 0), color(0.0, 0.0, 0.0));
+
+    // Subdividing every pixel traces all the rays
+    c.set_adaptive(Some(AdaptiveSampling::new(0.0, 1)));
+    let flat = World::empty();
+    let (_, stats) = c.render_with_stats(&w, &|_, _| {});
+    assert!(stats.rays > 12 * 12);
+    let (_, stats) = c.render_with_stats(&flat, &|_, _| {});
+    assert_eq!(stats.rays, 12 * 12);
+
+    // The result doesn't depend on the tiles and threads
+    c.set_adaptive(Some(AdaptiveSampling::new(0.1, 2)));
+    c.set_tile_size(3);
+    c.set_threads(3);
+    let (image2, _) = c.render_with_stats(&w, &|_, _| {});
+    for x in 0..11 {
+        for y in 0..11 {
+            assert_eq!(image1.at(x, y), image2.at(x, y));
+        }
+    }
+}
+
+#[test]
+fn cast_rays_through_the_lens() {
+    let mut c = Camera::new(201, 101, PI / 2.0);
+    c.set_transform(view_transform(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    assert_eq!(c.aperture(), 0.0);
+    assert_eq!(c.focal_distance(), 1.0);
+
+    // A pinhole ignores the lens coordinates
+    let r = c.ray_through_lens(30, 20, 0.5, 0.5, (0.1, 0.9));
+    assert_eq!(r.origin(), point(0.0, 0.0, -5.0));
+    assert_eq!(r.direction(), c.ray_for_pixel(30, 20).direction());
+
+    c.set_aperture(0.25);
+    c.set_focal_distance(5.0);
+    assert_eq!(c.aperture(), 0.25);
+    assert_eq!(c.focal_distance(), 5.0);
+
+    // All the rays through a pixel meet at the focal distance and start
+    // within the aperture
+    let center = c.ray_through_lens(30, 20, 0.5, 0.5, (0.5, 0.5));
+    assert_eq!(center.direction(), r.direction());
+    let focus = center.position(5.0 / center.direction().dot(&vector(0.0, 0.0, 1.0)));
+    for lens in [(0.0, 0.0), (1.0, 0.3), (0.2, 0.8), (0.9, 0.9)] {
+        let r = c.ray_through_lens(30, 20, 0.5, 0.5, lens);
+        let offset = r.origin() - point(0.0, 0.0, -5.0);
+        assert!(feq(offset.z(), 0.0));
+        assert!(offset.norm() <= 0.25 + 1e-9);
+        let t = (focus.z() - r.origin().z()) / r.direction().z();
+        assert_eq!(r.position(t), focus);
+    }
+
+    c.set_aperture(-1.0);
+    c.set_focal_distance(-1.0);
+    assert_eq!(c.aperture(), 0.0);
+    assert!(c.focal_distance() > 0.0);
+}
+
+#[test]
+fn render_with_depth_of_field() {
+    let w = World::default();
+    let mut c = Camera::new(21, 21, PI / 3.0);
+    c.set_transform(view_transform(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    c.set_samples(3);
+    c.set_sampling(SamplingPattern::Jittered);
+    let pinhole = c.render(&w);
+
+    // Objects at the focal distance stay sharp, while the background bleeds
+    // into the edge of the sphere
+    c.set_aperture(0.3);
+    c.set_focal_distance(4.0);
+    c.set_threads(1);
+    let blurred = c.render(&w);
+    c.set_threads(3);
+    let again = c.render(&w);
+    let mut differ = false;
+    for x in 0..pinhole.width() {
+        for y in 0..pinhole.height() {
+            assert_eq!(blurred.at(x, y), again.at(x, y));
+            differ |= blurred.at(x, y) != pinhole.at(x, y);
+        }
+    }
+    assert!(differ);
+    assert!((blurred.at(10, 10) - pinhole.at(10, 10)).norm() < 0.05);
+
+    // The adaptive sampling goes through the lens too
+    c.set_adaptive(Some(AdaptiveSampling::new(0.1, 1)));
+    let img1 = c.render(&w);
+    let img2 = c.render(&w);
+    assert_eq!(img1.at(6, 10), img2.at(6, 10));
+}
+
+#[test]
+fn construct_orthographic_rays() {
+    let mut c = Camera::new(201, 101, PI / 2.0);
+    c.set_projection(Projection::Orthographic { width: 4.0 });
+    assert_eq!(c.projection(), Projection::Orthographic { width: 4.0 });
+    assert!(feq(c.pixel_size(), 4.0 / 201.0));
+
+    let r = c.ray_for_pixel(100, 50);
+    assert_eq!(r.origin(), point(0.0, 0.0, 0.0));
+    assert_eq!(r.direction(), vector(0.0, 0.0, -1.0));
+
+    // The rays stay parallel across the image
+    let r = c.ray_for_subpixel(0, 0, 0.0, 0.0);
+    assert_eq!(r.origin(), point(2.0, 101.0 * c.pixel_size() / 2.0, 0.0));
+    assert_eq!(r.direction(), vector(0.0, 0.0, -1.0));
+
+    c.set_transform(translation(0.0, 0.0, 5.0));
+    let r = c.ray_for_pixel(100, 50);
+    assert_eq!(r.origin(), point(0.0, 0.0, -5.0));
+    assert_eq!(r.direction(), vector(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn construct_fisheye_rays() {
+    let mut c = Camera::new(100, 100, PI);
+    c.set_projection(Projection::Fisheye);
+
+    let r = c.ray_for_subpixel(50, 50, 0.0, 0.0);
+    assert_eq!(r.origin(), point(0.0, 0.0, 0.0));
+    assert_eq!(r.direction(), vector(0.0, 0.0, -1.0));
+
+    // The edges of the image look sideways, with +x on the left like for
+    // the perspective
+    let r = c.ray_for_subpixel(0, 50, 0.0, 0.0);
+    assert_eq!(r.direction(), vector(1.0, 0.0, 0.0));
+    let r = c.ray_for_subpixel(50, 0, 0.0, 0.0);
+    assert_eq!(r.direction(), vector(0.0, 1.0, 0.0));
+    let r = c.ray_for_subpixel(75, 50, 0.0, 0.0);
+    assert_eq!(r.direction(), vector(-FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2));
+
+    // Only the circle within the field of view is covered
+    assert!(c.covers(50, 50, 0.5, 0.5));
+    assert!(c.covers(0, 50, 0.5, 0.5));
+    assert!(!c.covers(0, 0, 0.5, 0.5));
+    assert!(!c.covers(99, 99, 0.5, 0.5));
+}
+
+#[test]
+fn construct_equirectangular_rays() {
+    let mut c = Camera::new(200, 100, PI / 2.0);
+    c.set_projection(Projection::Equirectangular);
+
+    let td = [
+        (100, 50, vector(0.0, 0.0, -1.0)),
+        (50, 50, vector(1.0, 0.0, 0.0)),
+        (150, 50, vector(-1.0, 0.0, 0.0)),
+        (0, 50, vector(0.0, 0.0, 1.0)),
+        (100, 0, vector(0.0, 1.0, 0.0)),
+        (100, 100, vector(0.0, -1.0, 0.0)),
+        (100, 25, vector(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2)),
+    ];
+    for (px, py, direction) in td.iter() {
+        let r = c.ray_for_subpixel(*px, *py, 0.0, 0.0);
+        assert_eq!(r.origin(), point(0.0, 0.0, 0.0));
+        assert_eq!(r.direction(), *direction);
+        assert!(c.covers(*px, *py, 0.0, 0.0));
+    }
+
+    // The resolution doesn't change the coverage
+    c.set_resolution(100, 100);
+    let r = c.ray_for_subpixel(50, 25, 0.0, 0.0);
+    assert_eq!(r.direction(), vector(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+}
+
+#[test]
+fn render_with_projections() {
+    let w = World::default();
+    let mut c = Camera::new(21, 21, PI);
+    c.set_transform(view_transform(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    let perspective = c.render(&w).at(10, 10);
+
+    // The center of the image looks straight ahead regardless of the
+    // projection
+    c.set_projection(Projection::Fisheye);
+    let fisheye = c.render(&w);
+    assert_eq!(fisheye.at(10, 10), perspective);
+    assert_eq!(fisheye.at(0, 0), color(0.0, 0.0, 0.0));
+
+    c.set_projection(Projection::Orthographic { width: 3.0 });
+    assert_eq!(c.render(&w).at(10, 10), perspective);
+
+    // The corners outside of the fisheye circle stay black even though
+    // the background isn't, and so do their samples
+    let mut w = World::default();
+    w.background = Background::Solid(color(1.0, 1.0, 1.0));
+    c.set_projection(Projection::Fisheye);
+    assert_eq!(c.render(&w).at(0, 0), color(0.0, 0.0, 0.0));
+    assert_eq!(c.render(&w).at(0, 10), color(1.0, 1.0, 1.0));
+    c.set_samples(4);
+    let edge = c.render(&w).at(2, 3).r();
+    assert!(edge > 0.0 && edge < 1.0);
+    c.set_adaptive(Some(AdaptiveSampling::new(0.1, 2)));
+    assert_eq!(c.render(&w).at(0, 0), color(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn shift_the_image_plane() {
+    let c = Camera::new(201, 101, PI / 2.0);
+    let mut s = Camera::new(201, 101, PI / 2.0);
+    s.set_shift(1.5, -2.0);
+    assert_eq!(s.shift(), (1.5, -2.0));
+    let r1 = s.ray_for_pixel(100, 50);
+    let r2 = c.ray_for_subpixel(101, 48, 1.0, 0.5);
+    assert_eq!(r1.origin(), r2.origin());
+    assert_eq!(r1.direction(), r2.direction());
+}
+
+#[test]
+fn render_regions() {
+    let w = World::default();
+    let mut c = Camera::new(37, 23, PI / 2.0);
+    c.set_transform(view_transform(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    c.set_samples(2);
+    c.set_sampling(SamplingPattern::Jittered);
+    c.set_tile_size(4);
+    c.set_threads(3);
+    let full = c.render(&w);
+
+    // The region's pixels match the full image regardless of the tiling
+    let region = Region::new(9, 5, 13, 11);
+    let (img, stats) = c.render_region_with_stats(&w, &region, &|_, _| {});
+    assert_eq!((img.width(), img.height()), (13, 11));
+    assert_eq!(stats.pixels, 13 * 11);
+    assert_eq!(stats.rays, 13 * 11 * 4);
+    for y in 0..11 {
+        for x in 0..13 {
+            assert_eq!(img.at(x, y), full.at(x + 9, y + 5));
+        }
+    }
+
+    // The pixels outside of the region stay untouched
+    let mut img = Canvas::new(37, 23);
+    img.set(0, 0, &color(1.0, 0.0, 0.0));
+    c.render_region_into(&w, &region, &mut img);
+    assert_eq!(img.at(0, 0), color(1.0, 0.0, 0.0));
+    assert_eq!(img.at(8, 5), color(0.0, 0.0, 0.0));
+    assert_eq!(img.at(15, 10), full.at(15, 10));
+    assert_eq!(img.at(21, 15), full.at(21, 15));
+    assert_eq!(img.at(22, 16), color(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn check_regions_against_image() {
+    let r = Region::new(30, 20, 7, 3);
+    assert!(r.fits(37, 23));
+    assert!(!r.fits(36, 23));
+    assert!(!r.fits(37, 22));
+    assert!(!Region::new(usize::MAX, 0, 1, 1).fits(10, 10));
+    assert!(!Region::new(0, 1, 1, usize::MAX).fits(10, 10));
+    assert_eq!(r.clipped(37, 23), r);
+    assert_eq!(r.clipped(35, 21), Region::new(30, 20, 5, 1));
+    assert_eq!(r.clipped(10, 10), Region::new(10, 10, 0, 0));
+}
+
+#[test]
+fn clip_regions_out_of_bounds() {
+    let w = World::default();
+    let c = Camera::new(11, 11, PI / 2.0);
+    let full = c.render(&w);
+
+    let (img, stats) = c.render_region_with_stats(&w, &Region::new(5, 5, 7, 1), &|_, _| {});
+    assert_eq!((img.width(), img.height()), (6, 1));
+    assert_eq!(stats.pixels, 6);
+    assert_eq!(img.at(5, 0), full.at(10, 5));
+
+    let img = c.render_region(&w, &Region::new(20, 0, 5, 5));
+    assert_eq!((img.width(), img.height()), (0, 5));
+
+    // The canvas smaller than the image gets only the pixels that fit
+    let mut img = Canvas::new(11, 10);
+    c.render_region_into(&w, &Region::new(0, 8, 11, 3), &mut img);
+    assert_eq!(img.at(10, 9), full.at(10, 9));
+}
@@ -1,4 +1,4 @@
-use ray_tracer::{color, Canvas};
+use ray_tracer::{color, crc32, png_encode, Canvas, ImageError, PngBitDepth};
 
 #[test]
 fn create_canvas() {
@@ -83,3 +83,133 @@ fn ppm_ends_with_newline() {
     let ppm = c.ppm();
     assert_eq!(ppm.chars().last().unwrap(), '\n');
 }
+
+#[test]
+fn construct_binary_ppm() {
+    let mut c = Canvas::new(2, 2);
+    c.set(0, 0, &color(1.5, 0.0, 0.0));
+    c.set(1, 1, &color(0.0, 0.5, 1.0));
+
+    let ppm = c.ppm_binary();
+    let mut expected = b"P6\n2 2\n255\n".to_vec();
+    expected.extend_from_slice(&[255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 255]);
+    assert_eq!(ppm, expected);
+}
+
+#[test]
+fn read_ppm_roundtrip() {
+    let mut c = Canvas::new(10, 3);
+    for i in 0..10 {
+        for j in 0..3 {
+            c.set(i, j, &color(i as f64 / 10.0, j as f64 / 3.0, 0.7));
+        }
+    }
+
+    let plain = Canvas::from_ppm(c.ppm().as_bytes()).unwrap();
+    let binary = Canvas::from_ppm(&c.ppm_binary()).unwrap();
+    assert_eq!(plain.ppm(), c.ppm());
+    assert_eq!(binary.ppm_binary(), c.ppm_binary());
+    assert_eq!(plain.at(5, 2), binary.at(5, 2));
+}
+
+#[test]
+fn read_ppm_with_comments() {
+    let ppm = "P3\n# made by hand\n2 1 # width and height\n15\n15 0 0\n# second pixel\n0 15 5\n";
+    let c = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+    assert_eq!(c.width(), 2);
+    assert_eq!(c.height(), 1);
+    assert_eq!(c.at(0, 0), color(1.0, 0.0, 0.0));
+    assert_eq!(c.at(1, 0), color(0.0, 1.0, 1.0 / 3.0));
+}
+
+#[test]
+fn read_16bit_binary_ppm() {
+    let mut ppm = b"P6 1 1 65535\n".to_vec();
+    ppm.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+    let c = Canvas::from_ppm(&ppm).unwrap();
+    assert_eq!(c.at(0, 0), color(1.0, 32768.0 / 65535.0, 0.0));
+}
+
+#[test]
+fn read_broken_ppm() {
+    assert!(matches!(
+        Canvas::from_ppm(b"P5\n1 1\n255\n0"),
+        Err(ImageError::BadMagic)
+    ));
+    assert!(matches!(
+        Canvas::from_ppm(b"P3\n0 1\n255\n"),
+        Err(ImageError::BadHeader(_))
+    ));
+    assert!(matches!(
+        Canvas::from_ppm(b"P3\nten 1\n255\n"),
+        Err(ImageError::BadHeader(_))
+    ));
+    assert!(matches!(
+        Canvas::from_ppm(b"P3\n1 1\n70000\n0 0 0"),
+        Err(ImageError::BadHeader(_))
+    ));
+    assert!(matches!(
+        Canvas::from_ppm(b"P3\n2 1\n255\n0 0 0 0 0"),
+        Err(ImageError::Truncated)
+    ));
+    assert!(matches!(
+        Canvas::from_ppm(b"P6\n2 1\n255\n\0\0\0\0"),
+        Err(ImageError::Truncated)
+    ));
+    assert!(matches!(
+        Canvas::from_ppm(b"P3\n1 1\n255\n0 256 0"),
+        Err(ImageError::Corrupt(_))
+    ));
+    assert!(matches!(
+        Canvas::from_ppm(b"P3"),
+        Err(ImageError::Truncated)
+    ));
+}
+
+#[test]
+fn read_ppm_with_huge_dimensions() {
+    assert!(matches!(
+        Canvas::from_ppm(b"P6\n100000 100000\n255\n"),
+        Err(ImageError::Truncated)
+    ));
+    assert!(matches!(
+        Canvas::from_ppm(b"P3\n100000 100000\n255\n0 0 0"),
+        Err(ImageError::Truncated)
+    ));
+    assert!(matches!(
+        Canvas::from_ppm(b"P6\n4294967296 4294967296\n255\n"),
+        Err(ImageError::BadHeader(_))
+    ));
+}
+
+#[test]
+fn read_png_with_huge_dimensions() {
+    // A 16-bit RGBA image of 0xffffffff x 0xffffffff pixels
+    let mut png = png_encode(1, 1, PngBitDepth::Sixteen, &[0; 6]);
+    png[16..24].fill(0xff);
+    png[25] = 6;
+    let crc = crc32(&png[12..29]);
+    png[29..33].copy_from_slice(&crc.to_be_bytes());
+    assert!(matches!(
+        Canvas::from_png(&png),
+        Err(ImageError::BadHeader(_))
+    ));
+}
+
+#[test]
+fn paste_canvas() {
+    let mut c = Canvas::new(5, 4);
+    let mut patch = Canvas::new(2, 3);
+    patch.set(0, 0, &color(1.0, 0.0, 0.0));
+    patch.set(1, 2, &color(0.0, 1.0, 0.0));
+    c.set(0, 0, &color(0.0, 0.0, 1.0));
+    c.paste(&patch, 3, 1);
+    assert_eq!(c.at(3, 1), color(1.0, 0.0, 0.0));
+    assert_eq!(c.at(4, 3), color(0.0, 1.0, 0.0));
+    assert_eq!(c.at(0, 0), color(0.0, 0.0, 1.0));
+
+    // The pixels outside of the canvas are dropped
+    c.paste(&patch, 4, 2);
+    assert_eq!(c.at(4, 2), color(1.0, 0.0, 0.0));
+    c.paste(&patch, 7, 7);
+}
@@ -0,0 +1,159 @@
+use ray_tracer::{
+    AdaptiveSampling, Camera, ReconstructionFilter, Region, RenderOptions, SamplingPattern,
+};
+
+use std::f64::consts::PI;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+#[test]
+fn parse_render_options() {
+    let opts = RenderOptions::parse(&args(&[
+        "scene.yml",
+        "-o",
+        "out.png",
+        "--width",
+        "640",
+        "--height",
+        "480",
+        "--samples",
+        "3",
+        "--threads",
+        "2",
+        "--depth",
+        "7",
+        "--sampling",
+        "jittered",
+        "--filter",
+        "gaussian",
+        "--seed",
+        "12",
+        "--aperture",
+        "0.2",
+        "--focal-distance",
+        "4.5",
+        "--region",
+        "10,20,30,40",
+        "--onto",
+        "full.png",
+        "-q",
+    ]))
+    .unwrap();
+    assert_eq!(opts.scene, "scene.yml");
+    assert_eq!(opts.output, "out.png");
+    assert_eq!(opts.width, Some(640));
+    assert_eq!(opts.height, Some(480));
+    assert_eq!(opts.samples, Some(3));
+    assert_eq!(opts.threads, Some(2));
+    assert_eq!(opts.depth, Some(7));
+    assert_eq!(opts.sampling, Some(SamplingPattern::Jittered));
+    assert_eq!(opts.filter, Some(ReconstructionFilter::Gaussian));
+    assert_eq!(opts.seed, Some(12));
+    assert_eq!(opts.aperture, Some(0.2));
+    assert_eq!(opts.focal_distance, Some(4.5));
+    assert_eq!(opts.region, Some(Region::new(10, 20, 30, 40)));
+    assert_eq!(opts.onto, Some("full.png".to_string()));
+    assert!(opts.quiet);
+    assert!(!opts.help);
+}
+
+#[test]
+fn derive_output_name_from_scene() {
+    let opts = RenderOptions::parse(&args(&["scenes/room.yml"])).unwrap();
+    assert_eq!(opts.output, "room.ppm");
+    assert_eq!(opts.width, None);
+    assert!(!opts.quiet);
+}
+
+#[test]
+fn reject_bad_options() {
+    let err = |a: &[&str]| RenderOptions::parse(&args(a)).unwrap_err();
+    assert_eq!(err(&[]), "missing the scene file");
+    assert_eq!(err(&["a.yml", "b.yml"]), "unexpected argument: b.yml");
+    assert_eq!(err(&["a.yml", "--fast"]), "unknown option: --fast");
+    assert_eq!(err(&["a.yml", "-o"]), "-o needs a value");
+    assert_eq!(
+        err(&["a.yml", "--width", "0"]),
+        "--width needs a positive number, got 0"
+    );
+    assert_eq!(
+        err(&["a.yml", "--depth", "-1"]),
+        "--depth needs a number of 0-255, got -1"
+    );
+    assert_eq!(
+        err(&["a.yml", "--filter", "lanczos"]),
+        "unknown filter: lanczos"
+    );
+    assert_eq!(
+        err(&["a.yml", "--sampling", "random"]),
+        "unknown sampling pattern: random"
+    );
+    assert_eq!(
+        err(&["a.yml", "--adaptive", "-0.1"]),
+        "--adaptive needs a non-negative number, got -0.1"
+    );
+    assert_eq!(
+        err(&["a.yml", "--aperture", "-1"]),
+        "--aperture needs a non-negative number, got -1"
+    );
+    assert_eq!(
+        err(&["a.yml", "--focal-distance", "0"]),
+        "--focal-distance needs a positive number, got 0"
+    );
+    assert_eq!(
+        err(&["a.yml", "--region", "1,2,3"]),
+        "--region needs x,y,width,height in pixels, got 1,2,3"
+    );
+    assert_eq!(
+        err(&["a.yml", "--region", "1,2,0,4"]),
+        "--region needs x,y,width,height in pixels, got 1,2,0,4"
+    );
+    assert_eq!(
+        err(&["a.yml", "--onto", "full.png"]),
+        "--onto needs a --region"
+    );
+    assert!(RenderOptions::parse(&args(&["--help"])).unwrap().help);
+}
+
+#[test]
+fn apply_options_to_camera() {
+    let mut c = Camera::new(200, 100, PI / 2.0);
+    let opts = RenderOptions::parse(&args(&["a.yml", "--width", "400", "--samples", "2"])).unwrap();
+    opts.apply(&mut c);
+    assert_eq!(c.hres(), 400);
+    assert_eq!(c.vres(), 200);
+    assert_eq!(c.samples(), 2);
+    assert_eq!(c.field_of_view(), PI / 2.0);
+
+    let opts = RenderOptions::parse(&args(&[
+        "a.yml",
+        "--height",
+        "50",
+        "--threads",
+        "3",
+        "--depth",
+        "2",
+        "--filter",
+        "tent",
+        "--seed",
+        "3",
+        "--adaptive",
+        "0.05",
+        "--aperture",
+        "0.1",
+        "--focal-distance",
+        "6",
+    ]))
+    .unwrap();
+    opts.apply(&mut c);
+    assert_eq!(c.adaptive(), Some(AdaptiveSampling::new(0.05, 3)));
+    assert_eq!(c.filter(), ReconstructionFilter::Tent);
+    assert_eq!(c.seed(), 3);
+    assert_eq!((c.hres(), c.vres()), (100, 50));
+    assert_eq!(c.threads(), 3);
+    assert_eq!(c.max_depth(), 2);
+    assert_eq!(c.aperture(), 0.1);
+    assert_eq!(c.focal_distance(), 6.0);
+}
@@ -0,0 +1,188 @@
+use ray_tracer::{
+    color, csg, cube, cube_unit, feq, group_unit, intersect, peq, point, point_light, scaling,
+    sphere, sphere_glass, sphere_unit, translation, vector, Csg, CsgOperation, Intersection,
+    Intersections, Ray, World,
+};
+
+#[test]
+fn check_csg_operation_rules() {
+    // (lhit, inl, inr, union, intersection, difference)
+    let td = [
+        (true, true, true, false, true, false),
+        (true, true, false, true, false, true),
+        (true, false, true, false, true, false),
+        (true, false, false, true, false, true),
+        (false, true, true, false, true, true),
+        (false, true, false, false, true, true),
+        (false, false, true, true, false, false),
+        (false, false, false, true, false, false),
+    ];
+
+    for (lhit, inl, inr, union, intersection, difference) in td.iter() {
+        assert_eq!(CsgOperation::Union.allows(*lhit, *inl, *inr), *union);
+        assert_eq!(
+            CsgOperation::Intersection.allows(*lhit, *inl, *inr),
+            *intersection
+        );
+        assert_eq!(
+            CsgOperation::Difference.allows(*lhit, *inl, *inr),
+            *difference
+        );
+    }
+}
+
+#[test]
+fn filter_intersections() {
+    let td = [
+        (CsgOperation::Union, 0, 3),
+        (CsgOperation::Intersection, 1, 2),
+        (CsgOperation::Difference, 0, 1),
+    ];
+
+    for (op, x0, x1) in td.iter() {
+        let c = Csg::new(*op, sphere_unit(), cube_unit());
+        let xs = vec![
+            Intersection::new(1.0, c.left()),
+            Intersection::new(2.0, c.right()),
+            Intersection::new(3.0, c.left()),
+            Intersection::new(4.0, c.right()),
+        ];
+        let result = c.filter_intersections(xs.clone());
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0], xs[*x0]);
+        assert_eq!(result[1], xs[*x1]);
+    }
+}
+
+#[test]
+fn intersect_ray_and_csg() {
+    let c = csg(CsgOperation::Union, sphere_unit(), cube_unit());
+    let r = Ray::new(point(0.0, 2.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert!(intersect(c.as_ref(), &r).is_empty());
+
+    let c = Csg::new(
+        CsgOperation::Union,
+        sphere_unit(),
+        sphere(translation(0.0, 0.0, 0.5)),
+    );
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(&c, &r);
+    assert_eq!(xs.len(), 2);
+    assert!(feq(xs[0].t(), 4.0));
+    assert!(peq(xs[0].shape(), c.left()));
+    assert!(feq(xs[1].t(), 6.5));
+    assert!(peq(xs[1].shape(), c.right()));
+}
+
+#[test]
+fn compute_normals_and_materials_from_children() {
+    let mut left = cube_unit();
+    left.material_mut().color = color(1.0, 0.0, 0.0);
+    let mut right = sphere(scaling(1.3, 1.3, 1.3));
+    right.material_mut().color = color(0.0, 0.0, 1.0);
+
+    let mut c = csg(CsgOperation::Difference, left, right);
+    c.transform(translation(0.0, 0.0, 2.0));
+
+    // close to the edge of the cube the sphere carves a hole in the middle
+    let r = Ray::new(point(0.9, 0.9, -5.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(c.as_ref(), &r);
+    assert_eq!(xs.len(), 4);
+    assert!(feq(xs[0].t(), 6.0));
+    assert_eq!(xs[0].shape().material().color, color(1.0, 0.0, 0.0));
+    assert_eq!(
+        xs[0].shape().normal_at(r.position(xs[0].t())),
+        vector(0.0, 0.0, -1.0)
+    );
+    assert_eq!(xs[1].shape().material().color, color(0.0, 0.0, 1.0));
+    assert_eq!(xs[2].shape().material().color, color(0.0, 0.0, 1.0));
+    assert!(feq(xs[3].t(), 8.0));
+
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert!(intersect(c.as_ref(), &r).is_empty());
+
+    // The CSG shape itself has no surface
+    let n = c.normal_at(point(0.9, 0.9, 1.0));
+    assert_eq!(n, vector(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn nest_csg_in_groups() {
+    let inner = group_unit(vec![sphere_unit(), sphere(translation(5.0, 0.0, 0.0))]);
+    let c = Csg::new(
+        CsgOperation::Intersection,
+        inner,
+        cube(scaling(0.5, 0.5, 0.5)),
+    );
+
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(&c, &r);
+    assert_eq!(xs.len(), 2);
+    assert!(feq(xs[0].t(), 4.5));
+    assert!(feq(xs[1].t(), 5.5));
+    assert!(c.right().includes(xs[0].shape()));
+    assert!(!c.left().includes(xs[0].shape()));
+
+    let mut g = group_unit(vec![Box::new(c)]);
+    g.transform(translation(0.0, 0.3, 0.0));
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(g.as_ref(), &r);
+    assert_eq!(xs.len(), 2);
+    assert!(g.includes(xs[0].shape()));
+}
+
+#[test]
+fn compute_refraction_indices_with_csg() {
+    let mut a = sphere_glass();
+    a.transform(scaling(2.0, 2.0, 2.0));
+    a.material_mut().refractive_index = 1.5;
+    let mut b = sphere_glass();
+    b.transform(translation(0.0, 0.0, -0.25));
+    b.material_mut().refractive_index = 2.0;
+
+    let c = csg(CsgOperation::Union, a, sphere_unit());
+    let r = Ray::new(point(0.0, 0.0, -4.0), vector(0.0, 0.0, 1.0));
+    let mut xs = Intersections::new();
+    xs.append(intersect(c.as_ref(), &r));
+    xs.append(intersect(b.as_ref(), &r));
+    xs.sort();
+
+    let expected = [(1.0, 1.5), (1.5, 2.0), (2.0, 1.5), (1.5, 1.0)];
+    assert_eq!(xs.len(), expected.len());
+    for (i, (n1, n2)) in expected.iter().enumerate() {
+        let p = xs.at(i).properties(&r, &xs);
+        assert_eq!(p.refraction_indices, (*n1, *n2));
+    }
+}
+
+#[test]
+fn shade_csg_in_world() {
+    let mut w = World::default();
+    let s1 = w.shapes.remove(0);
+    let s2 = w.shapes.remove(0);
+    w.shapes.push(csg(CsgOperation::Union, s1, s2));
+    w.lights.clear();
+    w.lights
+        .push(point_light(point(-10.0, 10.0, -10.0), color(1.0, 1.0, 1.0)));
+
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(w.color_at(&r, 5), color(0.38066, 0.47583, 0.2855));
+}
+
+#[test]
+fn find_any_hit_in_csg() {
+    // The right sphere swallows the left one, so there's nothing to hit
+    let s1 = sphere_unit();
+    let s2 = sphere(scaling(2.0, 2.0, 2.0));
+    let c = csg(CsgOperation::Difference, s1, s2);
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert!(!c.any_hit(&r, f64::INFINITY, &|_| true));
+
+    let c = csg(
+        CsgOperation::Union,
+        sphere_unit(),
+        cube(translation(3.0, 0.0, 0.0)),
+    );
+    assert!(c.any_hit(&r, f64::INFINITY, &|_| true));
+    assert!(!c.any_hit(&r, 3.5, &|_| true));
+}
@@ -0,0 +1,131 @@
+use ray_tracer::{
+    color, group, group_unit, intersect, peq, point, point_light, rotation_y, scaling, sphere,
+    sphere_unit, translation, vector, Group, Material, Ray, Shape, World,
+};
+
+use std::f64::consts::PI;
+
+#[test]
+fn intersect_ray_with_empty_group() {
+    let g = group_unit(vec![]);
+    let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0));
+    assert!(intersect(g.as_ref(), &r).is_empty());
+}
+
+#[test]
+fn intersect_ray_with_nonempty_group() {
+    let mut g = Group::new();
+    g.add_child(sphere_unit());
+    g.add_child(sphere(translation(0.0, 0.0, -3.0)));
+    g.add_child(sphere(translation(5.0, 0.0, 0.0)));
+
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(&g, &r);
+    assert_eq!(xs.len(), 4);
+    assert!(peq(xs[0].shape(), g.children()[1].as_ref()));
+    assert!(peq(xs[1].shape(), g.children()[1].as_ref()));
+    assert!(peq(xs[2].shape(), g.children()[0].as_ref()));
+    assert!(peq(xs[3].shape(), g.children()[0].as_ref()));
+}
+
+#[test]
+fn intersect_transformed_group() {
+    let g = group(
+        vec![sphere(translation(5.0, 0.0, 0.0))],
+        scaling(2.0, 2.0, 2.0),
+    );
+    let r = Ray::new(point(10.0, 0.0, -10.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(intersect(g.as_ref(), &r).len(), 2);
+    assert_eq!(
+        g.local_intersect(&r.transformed(*g.current_inverse_transform()))
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn apply_parent_transforms_to_children() {
+    // The transform can be applied before or after adding the children
+    let g2 = group(
+        vec![sphere(translation(5.0, 0.0, 0.0))],
+        scaling(2.0, 2.0, 2.0),
+    );
+    let mut g1 = Group::new();
+    g1.transform(rotation_y(PI / 2.0));
+    g1.add_child(g2);
+
+    let r = Ray::new(point(-10.0, 0.0, -10.0), vector(1.0, 0.0, 0.0));
+    let xs = intersect(&g1, &r);
+    assert_eq!(xs.len(), 2);
+    let pt = *xs[0].shape().current_inverse_transform() * point(-2.0, 0.0, -10.0);
+    assert_eq!(pt, point(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn compute_normal_of_nested_child() {
+    let mut g2 = Group::new();
+    g2.transform(scaling(1.0, 2.0, 3.0));
+    g2.add_child(sphere(translation(5.0, 0.0, 0.0)));
+    let g1 = group(vec![Box::new(g2)], rotation_y(PI / 2.0));
+
+    let r = Ray::new(point(1.7321, 1.1547, -10.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(g1.as_ref(), &r);
+    assert!(!xs.is_empty());
+    let n = xs[0].shape().normal_at(point(1.7321, 1.1547, -5.5774));
+    assert_eq!(n, vector(0.2857, 0.4286, -0.8571));
+
+    // The groups themselves have no surface
+    let n = g1.normal_at(point(1.7321, 1.1547, -5.5774));
+    assert_eq!(n, vector(0.0, 0.0, 0.0));
+    let n = g1.normal_at_hit(point(1.7321, 1.1547, -5.5774), &xs[0]);
+    assert_eq!(n, vector(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn propagate_group_material() {
+    let mut m = Material::new();
+    m.color = color(1.0, 0.0, 0.0);
+
+    let mut g = group_unit(vec![sphere_unit(), sphere_unit()]);
+    g.set_material(&m);
+    assert_eq!(g.material(), &m);
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(
+        intersect(g.as_ref(), &r)[0].shape().material(),
+        &Material::new()
+    );
+
+    let mut g = Group::new();
+    g.set_propagate_material(true);
+    g.add_child(sphere_unit());
+    let mut sub = Group::new();
+    sub.set_propagate_material(true);
+    sub.add_child(sphere_unit());
+    g.add_child(Box::new(sub));
+    g.set_material(&m);
+    for x in intersect(&g, &r).iter() {
+        assert_eq!(x.shape().material(), &m);
+    }
+}
+
+#[test]
+fn shade_group_in_world() {
+    let mut w = World::empty();
+    w.lights
+        .push(point_light(point(-10.0, 10.0, -10.0), color(1.0, 1.0, 1.0)));
+
+    let mut m = Material::new();
+    m.color = color(0.8, 1.0, 0.6);
+    m.diffuse = 0.7;
+    m.specular = 0.2;
+
+    let mut g = Group::new();
+    g.set_propagate_material(true);
+    g.add_child(sphere(scaling(0.5, 0.5, 0.5)));
+    g.set_material(&m);
+    g.transform(scaling(2.0, 2.0, 2.0));
+    w.shapes.push(Box::new(g));
+
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(w.color_at(&r, 5), color(0.38066, 0.47583, 0.2855));
+}
@@ -1,10 +1,197 @@
-use ray_tracer::{color, point, point_light};
+use ray_tracer::{
+    color, directional_light, feq, point, point_light, sphere_unit, spot_light, vector, AreaLight,
+    Attenuation, Light, LightArea, Material, PointLight, SpotLight, World,
+};
+
+use std::f64::consts::PI;
 
 #[test]
 fn create_light() {
     let p = point(0.0, 0.0, 0.0);
     let i = color(1.0, 1.0, 1.0);
     let l = point_light(p, i);
-    assert_eq!(l.position, p);
-    assert_eq!(l.intensity, i);
+    assert_eq!(l.intensity(), i);
+    let pl = l.as_any().downcast_ref::<PointLight>().unwrap();
+    assert_eq!(pl.position, p);
+
+    let samples = l.samples(&point(0.0, 0.0, -2.0));
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].direction, vector(0.0, 0.0, 1.0));
+    assert_eq!(samples[0].distance, 2.0);
+    assert_eq!(samples[0].intensity, i);
+}
+
+#[test]
+fn create_area_light() {
+    let corner = point(0.0, 0.0, 0.0);
+    let v1 = vector(2.0, 0.0, 0.0);
+    let v2 = vector(0.0, 0.0, 1.0);
+    let mut l = AreaLight::rect(corner, v1, 4, v2, 2, color(1.0, 1.0, 1.0));
+    assert_eq!(l.position, point(1.0, 0.0, 0.5));
+    assert_eq!(l.area, LightArea::Rect { uvec: v1, vvec: v2 });
+    assert_eq!(l.sample_count(), 8);
+    assert!(l.jitter);
+
+    // Without the jitter, the samples are at the centers of the cells
+    l.jitter = false;
+    let points = l.sample_points(&point(0.0, 5.0, 0.0));
+    assert_eq!(points.len(), 8);
+    assert_eq!(points[0], point(0.25, 0.0, 0.25));
+    assert_eq!(points[2], point(1.25, 0.0, 0.25));
+    assert_eq!(points[5], point(0.75, 0.0, 0.75));
+    assert_eq!(points[7], point(1.75, 0.0, 0.75));
+}
+
+#[test]
+fn jitter_samples_within_cells() {
+    let l = AreaLight::rect(
+        point(0.0, 0.0, 0.0),
+        vector(2.0, 0.0, 0.0),
+        4,
+        vector(0.0, 0.0, 1.0),
+        2,
+        color(1.0, 1.0, 1.0),
+    );
+    let pt = point(0.0, 5.0, 0.0);
+    let points = l.sample_points(&pt);
+    assert_eq!(points, l.sample_points(&pt));
+    assert_ne!(points, l.sample_points(&point(0.0, 5.0, 1.0)));
+    for (i, p) in points.iter().enumerate() {
+        let (u, v) = ((i % 4) as f64, (i / 4) as f64);
+        assert!(p.x() >= u * 0.5 && p.x() <= (u + 1.0) * 0.5);
+        assert!(p.z() >= v * 0.5 && p.z() <= (v + 1.0) * 0.5);
+        assert_eq!(p.y(), 0.0);
+    }
+}
+
+#[test]
+fn sample_disk_and_sphere_lights() {
+    let center = point(1.0, 4.0, 0.0);
+    let l = AreaLight::disk(center, vector(0.0, -1.0, 0.0), 0.5, 3, color(1.0, 1.0, 1.0));
+    assert_eq!(l.sample_count(), 9);
+    for p in l.sample_points(&point(0.0, 0.0, 0.0)).iter() {
+        assert!(feq(p.y(), 4.0));
+        assert!((*p - center).norm() <= 0.5 + 1e-9);
+    }
+
+    // Spheres are sampled on the disk facing the lit point
+    let l = AreaLight::sphere(center, 0.5, 3, color(1.0, 1.0, 1.0));
+    let pt = point(1.0, 4.0, -10.0);
+    for p in l.sample_points(&pt).iter() {
+        assert!(feq(p.z(), 0.0));
+        assert!((*p - center).norm() <= 0.5 + 1e-9);
+    }
+}
+
+#[test]
+fn sample_directional_light() {
+    let l = directional_light(vector(0.0, -2.0, 0.0), color(1.0, 1.0, 1.0));
+    for pt in [point(0.0, 0.0, 0.0), point(100.0, -3.0, 7.0)] {
+        let samples = l.samples(&pt);
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].direction, vector(0.0, 1.0, 0.0));
+        assert_eq!(samples[0].distance, f64::INFINITY);
+    }
+
+    // The shadows reach to infinity
+    let w = World::default();
+    assert!(w.is_shadowed(l.as_ref(), point(0.0, -1000.0, 0.0)));
+    assert!(!w.is_shadowed(l.as_ref(), point(3.0, -1000.0, 0.0)));
+}
+
+#[test]
+fn sample_spot_light() {
+    let l = SpotLight::new(
+        point(0.0, 10.0, 0.0),
+        vector(0.0, -1.0, 0.0),
+        PI / 4.0,
+        2.0,
+        color(1.0, 1.0, 1.0),
+    );
+    assert_eq!(
+        l.intensity_towards(&vector(0.0, -1.0, 0.0)),
+        color(1.0, 1.0, 1.0)
+    );
+    let i = l.intensity_towards(&vector(1.0, -2.0, 0.0).normalized());
+    assert!(feq(i.r(), 0.8));
+    assert_eq!(
+        l.intensity_towards(&vector(1.0, -0.9, 0.0).normalized()),
+        color(0.0, 0.0, 0.0)
+    );
+
+    let l = spot_light(
+        point(0.0, 10.0, 0.0),
+        vector(0.0, -1.0, 0.0),
+        PI / 4.0,
+        2.0,
+        color(1.0, 1.0, 1.0),
+    );
+    let samples = l.samples(&point(5.0, 0.0, 0.0));
+    assert_eq!(samples[0].direction, vector(-5.0, 10.0, 0.0).normalized());
+    assert!(feq(samples[0].intensity.g(), 0.8));
+    assert_eq!(
+        l.samples(&point(11.0, 0.0, 0.0))[0].intensity,
+        color(0.0, 0.0, 0.0)
+    );
+}
+
+#[test]
+fn attenuate_light_with_distance() {
+    assert_eq!(Attenuation::default(), Attenuation::None);
+    assert_eq!(Attenuation::None.factor(10.0), 1.0);
+    assert_eq!(Attenuation::InverseSquare.factor(2.0), 0.25);
+    let a = Attenuation::Polynomial {
+        constant: 1.0,
+        linear: 0.5,
+        quadratic: 0.25,
+    };
+    assert_eq!(a.factor(0.0), 1.0);
+    assert_eq!(a.factor(2.0), 1.0 / 3.0);
+
+    let mut l = PointLight::new(point(0.0, 0.0, 0.0), color(1.0, 1.0, 1.0));
+    l.attenuation = Attenuation::InverseSquare;
+    let samples = l.samples(&point(0.0, 0.0, 4.0));
+    assert_eq!(samples[0].intensity, color(0.0625, 0.0625, 0.0625));
+
+    // Nothing reaches beyond the range
+    l.range = 5.0;
+    assert_eq!(
+        l.samples(&point(0.0, 5.0, 0.0))[0].intensity,
+        color(0.04, 0.04, 0.04)
+    );
+    assert_eq!(
+        l.samples(&point(0.0, 5.1, 0.0))[0].intensity,
+        color(0.0, 0.0, 0.0)
+    );
+
+    let mut l = SpotLight::new(
+        point(0.0, 10.0, 0.0),
+        vector(0.0, -1.0, 0.0),
+        PI / 4.0,
+        1.0,
+        color(1.0, 1.0, 1.0),
+    );
+    l.attenuation = Attenuation::InverseSquare;
+    let samples = l.samples(&point(0.0, 0.0, 0.0));
+    assert_eq!(samples[0].intensity, color(0.01, 0.01, 0.01));
+}
+
+#[test]
+fn attenuate_diffuse_and_specular_terms() {
+    let m = Material::new();
+    let s = sphere_unit();
+    let pos = point(0.0, 0.0, 0.0);
+    let eyev = vector(0.0, 0.0, -1.0);
+    let normalv = vector(0.0, 0.0, -1.0);
+    let mut l = PointLight::new(point(0.0, 0.0, -2.0), color(1.0, 1.0, 1.0));
+    let full = m.lighting(s.as_ref(), &l, &pos, &eyev, &normalv, 1.0);
+    assert_eq!(full, color(1.9, 1.9, 1.9));
+
+    // The ambient term stays the same
+    l.attenuation = Attenuation::InverseSquare;
+    let c = m.lighting(s.as_ref(), &l, &pos, &eyev, &normalv, 1.0);
+    assert_eq!(c, color(0.1 + 1.8 / 4.0, 0.1 + 1.8 / 4.0, 0.1 + 1.8 / 4.0));
+    l.range = 1.0;
+    let c = m.lighting(s.as_ref(), &l, &pos, &eyev, &normalv, 1.0);
+    assert_eq!(c, color(0.1, 0.1, 0.1));
 }
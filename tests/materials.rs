@@ -1,6 +1,6 @@
 use ray_tracer::{
     color, gradient_pattern_unit, point, point_light, sphere_unit, stripe_pattern_unit, vector,
-    Material, Tuple,
+    AreaLight, Material, Tuple,
 };
 
 use std::f64::consts::FRAC_1_SQRT_2;
@@ -19,6 +19,7 @@ fn create_default_material() {
     assert_eq!(m.reflective, 0.0);
     assert_eq!(m.transparency, 0.0);
     assert_eq!(m.refractive_index, 1.0);
+    assert!(m.casts_shadow);
 }
 
 #[test]
@@ -30,7 +31,7 @@ fn shade_material_light_behind_eye_perpendicular_to_surface() {
     let normalv = vector(0.0, 0.0, -1.0);
     let light = point_light(point(0.0, 0.0, -10.0), color(1.0, 1.0, 1.0));
     assert_eq!(
-        m.lighting(s.as_ref(), &light, &pos, &eyev, &normalv, false),
+        m.lighting(s.as_ref(), light.as_ref(), &pos, &eyev, &normalv, 1.0),
         color(1.9, 1.9, 1.9)
     );
 }
@@ -44,7 +45,7 @@ fn shade_material_light_perpendicular_eye_45_to_surface() {
     let normalv = vector(0.0, 0.0, -1.0);
     let light = point_light(point(0.0, 0.0, -10.0), color(1.0, 1.0, 1.0));
     assert_eq!(
-        m.lighting(s.as_ref(), &light, &pos, &eyev, &normalv, false),
+        m.lighting(s.as_ref(), light.as_ref(), &pos, &eyev, &normalv, 1.0),
         color(1.0, 1.0, 1.0)
     );
 }
@@ -58,7 +59,7 @@ fn shade_material_light_45_eye_perpendicular_to_surface() {
     let normalv = vector(0.0, 0.0, -1.0);
     let light = point_light(point(0.0, 0.0, 10.0), color(1.0, 1.0, 1.0));
     assert_eq!(
-        m.lighting(s.as_ref(), &light, &pos, &eyev, &normalv, false),
+        m.lighting(s.as_ref(), light.as_ref(), &pos, &eyev, &normalv, 1.0),
         color(0.1, 0.1, 0.1)
     );
 }
@@ -72,11 +73,90 @@ fn shade_material_in_shadow() {
     let normalv = vector(0.0, 0.0, -1.0);
     let light = point_light(point(0.0, 0.0, 10.0), color(1.0, 1.0, 1.0));
     assert_eq!(
-        m.lighting(s.as_ref(), &light, &pos, &eyev, &normalv, true),
+        m.lighting(s.as_ref(), light.as_ref(), &pos, &eyev, &normalv, 0.0),
         color(0.1, 0.1, 0.1)
     );
 }
 
+#[test]
+fn shade_material_with_partial_visibility() {
+    let mut m = Material::new();
+    m.ambient = 0.1;
+    m.diffuse = 0.9;
+    m.specular = 0.0;
+    let s = sphere_unit();
+    let pos = point(0.0, 0.0, -1.0);
+    let eyev = vector(0.0, 0.0, -1.0);
+    let normalv = vector(0.0, 0.0, -1.0);
+    let light = point_light(point(0.0, 0.0, -10.0), color(1.0, 1.0, 1.0));
+    for (visibility, expected) in [(1.0, 1.0), (0.5, 0.55), (0.0, 0.1)] {
+        assert_eq!(
+            m.lighting(
+                s.as_ref(),
+                light.as_ref(),
+                &pos,
+                &eyev,
+                &normalv,
+                visibility
+            ),
+            color(expected, expected, expected)
+        );
+    }
+}
+
+#[test]
+fn shade_material_with_colored_transmittance() {
+    let mut m = Material::new();
+    m.specular = 0.0;
+    let s = sphere_unit();
+    let pos = point(0.0, 0.0, -1.0);
+    let eyev = vector(0.0, 0.0, -1.0);
+    let normalv = vector(0.0, 0.0, -1.0);
+    let light = point_light(point(0.0, 0.0, -10.0), color(1.0, 1.0, 1.0));
+    let transmittance = color(1.0, 0.5, 0.0);
+    assert_eq!(
+        m.lighting_with_transmittance(
+            s.as_ref(),
+            light.as_ref(),
+            &pos,
+            &eyev,
+            &normalv,
+            &transmittance
+        ),
+        color(1.0, 0.55, 0.1)
+    );
+}
+
+#[test]
+fn shade_material_with_area_light() {
+    let mut light = AreaLight::rect(
+        point(-0.5, -0.5, -5.0),
+        vector(1.0, 0.0, 0.0),
+        2,
+        vector(0.0, 1.0, 0.0),
+        2,
+        color(1.0, 1.0, 1.0),
+    );
+    light.jitter = false;
+    let mut m = Material::new();
+    m.ambient = 0.1;
+    m.diffuse = 0.9;
+    m.specular = 0.0;
+    let s = sphere_unit();
+    let eye = point(0.0, 0.0, -5.0);
+    for (pos, expected) in [
+        (point(0.0, 0.0, -1.0), 0.9965),
+        (point(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2), 0.62318),
+    ] {
+        let eyev = (eye - pos).normalized();
+        let normalv = vector(pos.x(), pos.y(), pos.z());
+        assert_eq!(
+            m.lighting(s.as_ref(), &light, &pos, &eyev, &normalv, 1.0),
+            color(expected, expected, expected)
+        );
+    }
+}
+
 #[test]
 fn compare_material_patterns() {
     let p1 = stripe_pattern_unit(WHITE, BLACK);
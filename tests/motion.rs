@@ -0,0 +1,310 @@
+use ray_tracer::{
+    color, directional_light, group_unit, point, rotation_x, rotation_y, rotation_z, scaling,
+    shearing, sphere, sphere_unit, stripe_pattern_unit, translation, vector, view_transform,
+    Background, Bounds, Camera, Decomposition, Matrix, Motion, Projection, Quaternion, Ray,
+    SamplingPattern, World,
+};
+
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+#[test]
+fn convert_rotations_to_quaternions() {
+    let td = [
+        rotation_x(PI / 3.0),
+        rotation_y(-PI / 2.0),
+        rotation_z(PI),
+        rotation_x(0.3) * rotation_y(2.9) * rotation_z(-1.2),
+        Matrix::one(),
+    ];
+    for m in td.iter() {
+        let q = Quaternion::from_matrix(m);
+        assert!((q.dot(&q) - 1.0).abs() < 1e-9);
+        assert_eq!(q.matrix(), *m);
+    }
+
+    let q = Quaternion::from_matrix(&rotation_y(PI / 2.0));
+    assert!((q.angle_to(&Quaternion::new(1.0, 0.0, 0.0, 0.0)) - PI / 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn slerp_between_rotations() {
+    let a = Quaternion::from_matrix(&Matrix::one());
+    let b = Quaternion::from_matrix(&rotation_y(PI / 2.0));
+    assert_eq!(a.slerp(&b, 0.0).matrix(), Matrix::one());
+    assert_eq!(a.slerp(&b, 1.0).matrix(), rotation_y(PI / 2.0));
+    assert_eq!(a.slerp(&b, 0.5).matrix(), rotation_y(PI / 4.0));
+    assert_eq!(a.slerp(&b, 0.25).matrix(), rotation_y(PI / 8.0));
+
+    // The rotations take the shorter way around
+    let c = Quaternion::from_matrix(&rotation_z(-3.0 * PI / 4.0));
+    let d = Quaternion::from_matrix(&rotation_z(3.0 * PI / 4.0));
+    assert_eq!(c.slerp(&d, 0.5).matrix(), rotation_z(PI));
+}
+
+#[test]
+fn decompose_transforms() {
+    let td = [
+        translation(1.0, -2.0, 3.0) * rotation_y(0.7) * scaling(2.0, 0.5, 1.5),
+        rotation_x(-1.1) * shearing(0.5, 0.0, 0.2, 0.0, 0.0, 0.3),
+        translation(0.0, 1.0, 0.0) * scaling(-1.0, 1.0, 1.0),
+        view_transform(
+            point(1.0, 3.0, 2.0),
+            point(4.0, -2.0, 8.0),
+            vector(1.0, 1.0, 0.0),
+        ),
+    ];
+    for m in td.iter() {
+        let d = Decomposition::new(m);
+        assert_eq!(d.matrix(), *m);
+    }
+
+    let d = Decomposition::new(
+        &(translation(1.0, -2.0, 3.0) * rotation_y(0.7) * scaling(2.0, 0.5, 1.5)),
+    );
+    assert_eq!(d.translation(), point(1.0, -2.0, 3.0));
+    assert_eq!(d.rotation().matrix(), rotation_y(0.7));
+    assert_eq!(*d.stretch(), scaling(2.0, 0.5, 1.5));
+}
+
+#[test]
+fn interpolate_motion() {
+    let start = translation(-1.0, 0.0, 0.0);
+    let end = translation(3.0, 2.0, 0.0) * rotation_z(PI / 2.0) * scaling(3.0, 3.0, 3.0);
+    let mut m = Motion::new(start, end);
+    assert_eq!(m.transform_at(0.0), start);
+    assert_eq!(m.transform_at(1.0), end);
+    assert_eq!(
+        m.transform_at(0.5),
+        translation(1.0, 1.0, 0.0) * rotation_z(PI / 4.0) * scaling(2.0, 2.0, 2.0)
+    );
+
+    // The times outside of the keyframes stay at the keyframes
+    assert_eq!(m.transform_at(-1.0), start);
+    assert_eq!(m.transform_at(2.0), end);
+
+    // The fixed transforms go on top of both keyframes
+    m.transform(translation(0.0, 0.0, 5.0));
+    assert_eq!(m.transform_at(0.0), translation(-1.0, 0.0, 5.0));
+    assert_eq!(m.transform_at(1.0), translation(0.0, 0.0, 5.0) * end);
+}
+
+#[test]
+fn invert_motion() {
+    let start =
+        translation(1.0, -2.0, 3.0) * rotation_x(-1.1) * shearing(0.5, 0.0, 0.2, 0.0, 0.0, 0.3);
+    let end = translation(3.0, 2.0, 0.0) * rotation_z(PI / 2.0) * scaling(-1.0, 3.0, 0.5);
+    let mut m = Motion::new(start, end);
+    m.transform(rotation_y(0.7) * scaling(2.0, 2.0, 2.0));
+    for t in [-1.0, 0.0, 0.3, 0.5, 0.8, 1.0, 2.0] {
+        assert_eq!(m.inverse_transform_at(t), m.transform_at(t).inverted());
+    }
+}
+
+#[test]
+fn bound_motion() {
+    let local = Bounds::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0));
+    let m = Motion::new(translation(-5.0, 0.0, 0.0), translation(5.0, 0.0, 0.0));
+    let b = m.bounds(&local);
+    assert_eq!(b.min(), point(-6.0, -1.0, -1.0));
+    assert_eq!(b.max(), point(6.0, 1.0, 1.0));
+
+    // The corners of a spinning box sweep outside of the keyframe boxes
+    let m = Motion::new(Matrix::one(), rotation_y(PI / 2.0));
+    let b = m.bounds(&local);
+    for i in 0..=100 {
+        let transformed = local.transformed(m.transform_at(i as f64 / 100.0));
+        assert!(b.contains_point(transformed.min()));
+        assert!(b.contains_point(transformed.max()));
+    }
+    assert!(b.max().x() >= 2.0_f64.sqrt());
+
+    // The outer transform scales the padding only once
+    let mut m = Motion::new(Matrix::one(), rotation_y(PI / 2.0));
+    m.transform(scaling(10.0, 10.0, 10.0));
+    let b = m.bounds(&local);
+    for i in 0..=100 {
+        let transformed = local.transformed(m.transform_at(i as f64 / 100.0));
+        assert!(b.contains_point(transformed.min()));
+        assert!(b.contains_point(transformed.max()));
+    }
+    assert!(b.max().x() < 10.0 * 2.0_f64.sqrt() + 0.01);
+}
+
+#[test]
+fn intersect_moving_shapes() {
+    let mut s = sphere(translation(0.0, 0.0, 0.0));
+    s.set_motion(translation(4.0, 0.0, 0.0));
+    let b = s.bounds();
+    assert_eq!(b.min(), point(-1.0, -1.0, -1.0));
+    assert_eq!(b.max(), point(5.0, 1.0, 1.0));
+
+    let r = Ray::new(point(2.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert!(s.intersect(&r).is_empty());
+    assert_eq!(s.intersect(&r.with_time(0.5)).len(), 2);
+    assert!(s.intersect(&r.with_time(1.0)).is_empty());
+
+    // The intersections remember the time, so that the normals are computed
+    // where the shape was when hit
+    let r = Ray::new(point(4.0, 0.0, -5.0), vector(0.0, 0.0, 1.0)).with_time(1.0);
+    let xs = s.intersect(&r);
+    assert_eq!(xs[0].t(), 4.0);
+    assert_eq!(xs[0].time(), 1.0);
+    assert_eq!(
+        s.normal_at_hit(point(4.0, 0.0, -1.0), &xs[0]),
+        vector(0.0, 0.0, -1.0)
+    );
+    let pt = point(3.0 + FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2);
+    let r = Ray::new(pt - vector(0.0, 0.0, 1.0), vector(0.0, 0.0, 1.0)).with_time(0.75);
+    let xs = s.intersect(&r);
+    assert_eq!(
+        s.normal_at_hit(pt, &xs[0]),
+        vector(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2)
+    );
+
+    // Any hit queries follow the motion too
+    let r = Ray::new(point(4.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert!(!s.any_hit(&r, 10.0, &|_| true));
+    assert!(s.any_hit(&r.with_time(1.0), 10.0, &|_| true));
+}
+
+#[test]
+fn move_groups() {
+    let mut g = group_unit(vec![sphere_unit(), sphere(translation(3.0, 0.0, 0.0))]);
+    g.transform(translation(0.0, 0.0, 1.0));
+    g.set_motion(translation(0.0, 2.0, 1.0));
+
+    let r = Ray::new(point(3.0, 2.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert!(g.intersect(&r).is_empty());
+    let xs = g.intersect(&r.with_time(1.0));
+    assert_eq!(xs.len(), 2);
+    assert_eq!(xs[0].t(), 5.0);
+    assert_eq!(g.bounds().max(), point(4.0, 3.0, 2.0));
+
+    // The transforms of the parents apply to both keyframes
+    let mut outer = group_unit(vec![g]);
+    outer.transform(translation(10.0, 0.0, 0.0));
+    let r = Ray::new(point(13.0, 2.0, -5.0), vector(0.0, 0.0, 1.0)).with_time(1.0);
+    assert_eq!(outer.intersect(&r).len(), 2);
+}
+
+fn moving_world() -> World {
+    let mut w = World::default();
+    w.shapes.truncate(1);
+    w.shapes[0].set_motion(translation(1.0, 0.0, 0.0));
+    w.background = Background::Solid(color(1.0, 1.0, 1.0));
+
+    // The shading doesn't depend on the position under a directional light
+    w.lights[0] = directional_light(vector(1.0, -1.0, 1.0), color(1.0, 1.0, 1.0));
+    w
+}
+
+#[test]
+fn render_motion_blur() {
+    let w = moving_world();
+
+    // The orthographic pixels are a fifth of a unit wide
+    let mut c = Camera::new(21, 11, PI / 2.0);
+    c.set_projection(Projection::Orthographic { width: 4.2 });
+    c.set_transform(view_transform(
+        point(0.5, 0.0, -5.0),
+        point(0.5, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    assert_eq!(c.shutter(), (0.0, 0.0));
+
+    // With the shutter closed, the shape stays at the first keyframe
+    let still = c.render(&w);
+    c.set_shutter(1.0, 1.0);
+    let moved = c.render(&w);
+    assert_ne!(still.at(4, 5), moved.at(4, 5));
+    for x in 0..16 {
+        assert_eq!(still.at(x, 5), moved.at(x + 5, 5));
+    }
+
+    // An open shutter smears the sphere reproducibly
+    c.set_shutter(0.0, 1.0);
+    assert_eq!(c.shutter(), (0.0, 1.0));
+    c.set_samples(4);
+    c.set_sampling(SamplingPattern::Jittered);
+    c.set_threads(1);
+    let blurred = c.render(&w);
+    c.set_threads(3);
+    let again = c.render(&w);
+    for x in 0..blurred.width() {
+        for y in 0..blurred.height() {
+            assert_eq!(blurred.at(x, y), again.at(x, y));
+        }
+    }
+
+    // The edges of the sweep are covered part of the time, the middle all
+    // the time
+    let white = color(1.0, 1.0, 1.0);
+    for x in [4, 16] {
+        assert_ne!(blurred.at(x, 5), white);
+        assert_ne!(blurred.at(x, 5), still.at(x, 5));
+        assert_ne!(blurred.at(x, 5), moved.at(x, 5));
+    }
+    assert_eq!(blurred.at(0, 5), white);
+    assert_eq!(blurred.at(20, 5), white);
+
+    c.set_shutter(0.5, 0.2);
+    assert_eq!(c.shutter(), (0.5, 0.5));
+}
+
+#[test]
+fn shade_moving_shapes_at_ray_time() {
+    let w = moving_world();
+
+    // The hit follows the sphere
+    let r = Ray::new(point(1.5, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(w.color_at(&r, 5), color(1.0, 1.0, 1.0));
+    let r0 = Ray::new(point(0.5, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(w.color_at(&r.with_time(1.0), 5), w.color_at(&r0, 5));
+
+    let r = Ray::new(point(1.0, 0.0, -5.0), vector(0.0, 0.0, 1.0)).with_time(1.0);
+    let xs = w.intersect(&r);
+    let props = xs.hit().unwrap().properties(&r, &xs);
+    assert_eq!(props.time, 1.0);
+    assert_eq!(props.normalv, vector(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn move_patterns_and_shadows_with_shapes() {
+    let mut s = sphere_unit();
+    s.material_mut().pattern = Some(stripe_pattern_unit(
+        color(1.0, 1.0, 1.0),
+        color(0.0, 0.0, 0.0),
+    ));
+    s.set_motion(translation(1.0, 0.0, 0.0));
+
+    // The stripes stay in place on the surface
+    let m = s.material().clone();
+    let pt = point(0.5, 0.0, -0.8);
+    assert_eq!(m.color_at(s.as_ref(), &pt, 0.0), color(1.0, 1.0, 1.0));
+    let pt = point(1.5, 0.0, -0.8);
+    assert_eq!(m.color_at(s.as_ref(), &pt, 1.0), color(1.0, 1.0, 1.0));
+    assert_eq!(m.color_at(s.as_ref(), &pt, 0.0), color(0.0, 0.0, 0.0));
+
+    // The shadows move along with the shapes
+    let mut w = World::empty();
+    w.shapes.push(s);
+    let light = directional_light(vector(0.0, -1.0, 0.0), color(1.0, 1.0, 1.0));
+    let pt = point(1.5, -2.0, 0.0);
+    assert_eq!(
+        w.light_transmittance(light.as_ref(), pt),
+        color(1.0, 1.0, 1.0)
+    );
+    assert_eq!(
+        w.light_transmittance_at(light.as_ref(), pt, 1.0),
+        color(0.0, 0.0, 0.0)
+    );
+    let pt = point(-0.5, -2.0, 0.0);
+    assert_eq!(
+        w.light_transmittance(light.as_ref(), pt),
+        color(0.0, 0.0, 0.0)
+    );
+    assert_eq!(
+        w.light_transmittance_at(light.as_ref(), pt, 1.0),
+        color(1.0, 1.0, 1.0)
+    );
+}
@@ -0,0 +1,153 @@
+use ray_tracer::{
+    color, intersect, parse_obj, point, translation, vector, Material, ObjError, Ray,
+    OBJ_DEFAULT_GROUP,
+};
+
+#[test]
+fn ignore_unrecognized_lines() {
+    let input = "There was a young lady named Bright\n\
+                 who traveled much faster than light.\n\
+                 # a comment\n\
+                 She set out one day\n";
+    let obj = parse_obj(input).unwrap();
+    assert_eq!(obj.ignored_lines(), 3);
+}
+
+#[test]
+fn parse_vertex_records() {
+    let input = "v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0\n";
+    let obj = parse_obj(input).unwrap();
+    assert_eq!(obj.vertices().len(), 4);
+    assert_eq!(obj.vertices()[0], point(-1.0, 1.0, 0.0));
+    assert_eq!(obj.vertices()[1], point(-1.0, 0.5, 0.0));
+    assert_eq!(obj.vertices()[2], point(1.0, 0.0, 0.0));
+    assert_eq!(obj.vertices()[3], point(1.0, 1.0, 0.0));
+}
+
+#[test]
+fn parse_normal_and_texture_records() {
+    let input = "vn 0 0 1\nvn 0.707 0 -0.707\nvt 0.5 0.25\nvt 1\n";
+    let obj = parse_obj(input).unwrap();
+    assert_eq!(obj.normals()[0], vector(0.0, 0.0, 1.0));
+    assert_eq!(obj.normals()[1], vector(0.707, 0.0, -0.707));
+    assert_eq!(obj.texture_coords(), &[(0.5, 0.25), (1.0, 0.0)]);
+}
+
+#[test]
+fn ignore_trailing_comments() {
+    let input = "v -1 1 0 # top left\nv -1 0 0#bottom left\nv 1 0 0\n\
+                 f 1 2 3 # the only face\n  # indented comment\n";
+    let obj = parse_obj(input).unwrap();
+    assert_eq!(obj.vertices().len(), 3);
+    assert_eq!(obj.vertices()[1], point(-1.0, 0.0, 0.0));
+    assert_eq!(
+        obj.group(OBJ_DEFAULT_GROUP).unwrap().faces[0].vertices,
+        [0, 1, 2]
+    );
+    assert_eq!(obj.ignored_lines(), 0);
+}
+
+#[test]
+fn parse_triangle_faces() {
+    let input = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4\n";
+    let obj = parse_obj(input).unwrap();
+    let g = obj.group(OBJ_DEFAULT_GROUP).unwrap();
+    assert_eq!(g.faces.len(), 2);
+    assert_eq!(g.faces[0].vertices, [0, 1, 2]);
+    assert_eq!(g.faces[1].vertices, [0, 2, 3]);
+}
+
+#[test]
+fn triangulate_polygons() {
+    let input = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5\n";
+    let obj = parse_obj(input).unwrap();
+    let g = obj.group(OBJ_DEFAULT_GROUP).unwrap();
+    assert_eq!(g.faces.len(), 3);
+    assert_eq!(g.faces[0].vertices, [0, 1, 2]);
+    assert_eq!(g.faces[1].vertices, [0, 2, 3]);
+    assert_eq!(g.faces[2].vertices, [0, 3, 4]);
+}
+
+#[test]
+fn parse_faces_with_normals_and_texture_coords() {
+    let input = "v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+                 vn -1 0 0\nvn 1 0 0\nvn 0 1 0\nvt 0 0\n\
+                 f 1//3 2//1 3//2\nf 1/1/3 2/1/1 3/1/2\nf -3 -2 -1\n";
+    let obj = parse_obj(input).unwrap();
+    let g = obj.group(OBJ_DEFAULT_GROUP).unwrap();
+    assert_eq!(g.faces[0].normals, Some([2, 0, 1]));
+    assert_eq!(g.faces[0].texture_coords, None);
+    assert_eq!(g.faces[1].normals, Some([2, 0, 1]));
+    assert_eq!(g.faces[1].texture_coords, Some([0, 0, 0]));
+    assert_eq!(g.faces[2].vertices, [0, 1, 2]);
+    assert_eq!(g.faces[2].normals, None);
+}
+
+#[test]
+fn parse_named_groups() {
+    let input = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+                 g FirstGroup\nf 1 2 3\no SecondGroup\nf 1 3 4\ng FirstGroup\nf 2 3 4\n";
+    let obj = parse_obj(input).unwrap();
+    assert_eq!(obj.groups().len(), 3);
+    assert_eq!(obj.group(OBJ_DEFAULT_GROUP).unwrap().faces.len(), 0);
+    assert_eq!(obj.group("FirstGroup").unwrap().faces.len(), 2);
+    assert_eq!(obj.group("SecondGroup").unwrap().faces.len(), 1);
+}
+
+#[test]
+fn report_malformed_lines() {
+    let check = |input: &str, expected_line: usize| match parse_obj(input) {
+        Err(ObjError::Parse { line, .. }) => assert_eq!(line, expected_line),
+        _ => panic!("expected a parse error for {:?}", input),
+    };
+
+    check("v 1 2\n", 1);
+    check("v 0 0 0\nv 1 x 0\n", 2);
+    check("v 0 0 0\nv 1 0 0\nv 0 1 0\n\nf 1 2\n", 5);
+    check("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n", 4);
+    check("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 0\n", 4);
+    check("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2 3//1\n", 5);
+    check("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1/2/3/4 2 3\n", 4);
+}
+
+#[test]
+fn convert_to_shapes() {
+    let input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nv 0 -1 0\n\
+                 vn 0 0 -1\n\
+                 f 1 2 3\ng Smooth\nf 2//1 4//1 3//1\n";
+    let obj = parse_obj(input).unwrap();
+    let mut m = Material::new();
+    m.color = color(1.0, 0.0, 0.0);
+    let shapes = obj.shapes(translation(0.0, 0.0, 1.0), &m);
+    assert_eq!(shapes.len(), 2);
+
+    let r = Ray::new(point(0.0, 0.5, -2.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(shapes[0].as_ref(), &r);
+    assert_eq!(xs.len(), 1);
+    assert_eq!(xs[0].t(), 3.0);
+    assert_eq!(shapes[0].material(), &m);
+
+    let r = Ray::new(point(0.0, -0.5, -2.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(shapes[1].as_ref(), &r);
+    assert_eq!(xs.len(), 1);
+    assert_eq!(
+        shapes[1].normal_at_hit(r.position(xs[0].t()), &xs[0]),
+        vector(0.0, 0.0, -1.0)
+    );
+}
+
+#[test]
+fn convert_to_group() {
+    let input = "v 0 1 0\nv -1 0 0\nv 1 0 0\nv 0 -1 0\n\
+                 g First\nf 1 2 3\ng Second\nf 2 4 3\n";
+    let obj = parse_obj(input).unwrap();
+    let mut m = Material::new();
+    m.color = color(0.0, 1.0, 0.0);
+    let g = obj.to_group(translation(0.0, 0.0, 1.0), &m);
+
+    let r = Ray::new(point(0.0, -0.5, -2.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(g.as_ref(), &r);
+    assert_eq!(xs.len(), 1);
+    assert_eq!(xs[0].t(), 3.0);
+    assert_eq!(xs[0].shape().material(), &m);
+}
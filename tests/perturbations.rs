@@ -0,0 +1,131 @@
+use ray_tracer::{
+    plane, plane_unit, point, rotation_x, scaling, sphere_unit, vector, Canvas, Intersection,
+    Intersections, Material, Matrix, NormalPerturbation, Ray, Tuple, UvMapping, EPSILON,
+};
+
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+fn normal_map(normal: Tuple) -> Canvas {
+    let mut c = Canvas::new(1, 1);
+    c.set(0, 0, &((normal + vector(1.0, 1.0, 1.0)) * 0.5));
+    c
+}
+
+#[test]
+fn keep_normals_of_flat_normal_maps() {
+    let flat = NormalPerturbation::normal_map(
+        normal_map(vector(0.0, 0.0, 1.0)),
+        UvMapping::Spherical,
+        Matrix::one(),
+    );
+    let s = sphere_unit();
+    let td = [
+        point(0.0, 0.0, -1.0),
+        point(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0),
+        point(0.0, 1.0, 0.0),
+        point(0.0, -1.0, 0.0),
+    ];
+    for pt in td.iter() {
+        let n = s.normal_at(*pt);
+        assert_eq!(flat.perturb(s.as_ref(), *pt, n), n);
+    }
+}
+
+#[test]
+fn tilt_normals_with_normal_maps() {
+    // Red tilts the normal towards growing u, that is +x for the planes
+    let tilted = vector(FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2);
+    let p = NormalPerturbation::normal_map(normal_map(tilted), UvMapping::Planar, Matrix::one());
+
+    let s = plane_unit();
+    let pt = point(0.3, 0.0, 0.7);
+    let n = p.perturb(s.as_ref(), pt, s.normal_at(pt));
+    assert_eq!(n, vector(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0));
+
+    // The tangents follow the transform of the shape
+    let s = plane(rotation_x(PI / 2.0));
+    let pt = point(0.3, -0.7, 0.0);
+    let n = p.perturb(s.as_ref(), pt, s.normal_at(pt));
+    assert_eq!(n, vector(FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2));
+
+    // Green tilts the normal towards growing v, that is up on the sphere
+    let p = NormalPerturbation::normal_map(
+        normal_map(vector(0.0, 1.0, 0.0)),
+        UvMapping::Spherical,
+        Matrix::one(),
+    );
+    let s = sphere_unit();
+    let pt = point(0.0, 0.0, -1.0);
+    assert_eq!(
+        p.perturb(s.as_ref(), pt, s.normal_at(pt)),
+        vector(0.0, 1.0, 0.0)
+    );
+}
+
+#[test]
+fn bump_normals_with_noise() {
+    let s = sphere_unit();
+    let pt = point(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+    let n = s.normal_at(pt);
+
+    let flat = NormalPerturbation::bump(0.0, 3, scaling(0.1, 0.1, 0.1));
+    assert_eq!(flat.perturb(s.as_ref(), pt, n), n);
+
+    let bumpy = NormalPerturbation::bump(0.5, 3, scaling(0.1, 0.1, 0.1));
+    let mut tilted = 0;
+    for i in 0..16 {
+        let a = i as f64 * PI / 16.0;
+        let pt = point(a.cos(), a.sin(), 0.0);
+        let n = s.normal_at(pt);
+        let b = bumpy.perturb(s.as_ref(), pt, n);
+        assert!((b.norm() - 1.0).abs() < EPSILON);
+        assert!(b.dot(&n) > 0.0);
+        assert_eq!(b, bumpy.perturb(s.as_ref(), pt, n));
+        if b != n {
+            tilted += 1;
+        }
+    }
+    assert!(tilted > 8);
+}
+
+#[test]
+fn perturb_intersection_normals() {
+    let mut s = plane_unit();
+    let tilted = vector(FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2);
+    s.material_mut().normal_perturbation = Some(NormalPerturbation::normal_map(
+        normal_map(tilted),
+        UvMapping::Planar,
+        Matrix::one(),
+    ));
+
+    // The eye sees the surface at a grazing angle, so the perturbed normal
+    // faces away from it, but the hit is still outside
+    let r = Ray::new(point(-1.0, 0.1, 0.0), vector(1.0, -0.1, 0.0).normalized());
+    let i = Intersection::new(1.01_f64.sqrt(), s.as_ref());
+    let p = i.properties(&r, &Intersections::new());
+    assert_eq!(p.normalv, vector(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0));
+    assert!(!p.inside);
+    assert!(p.over_point.y() > EPSILON / 2.0);
+    assert_eq!(p.reflectv, r.direction().reflected(&p.normalv));
+
+    // From below the normals are flipped
+    let r = Ray::new(point(0.0, -1.0, 0.0), vector(0.0, 1.0, 0.0));
+    let i = Intersection::new(1.0, s.as_ref());
+    let p = i.properties(&r, &Intersections::new());
+    assert_eq!(p.normalv, vector(-FRAC_1_SQRT_2, -FRAC_1_SQRT_2, 0.0));
+    assert!(p.inside);
+    assert!(p.over_point.y() < -EPSILON / 2.0);
+}
+
+#[test]
+fn compare_materials_with_perturbations() {
+    let mut m1 = Material::new();
+    let mut m2 = Material::new();
+    m1.normal_perturbation = Some(NormalPerturbation::bump(0.5, 2, Matrix::one()));
+    assert!(m1 != m2);
+    m2.normal_perturbation = Some(NormalPerturbation::bump(0.5, 2, Matrix::one()));
+    assert!(m1 == m2);
+    m2.normal_perturbation = Some(NormalPerturbation::bump(0.5, 2, scaling(2.0, 2.0, 2.0)));
+    assert!(m1 != m2);
+    assert_eq!(Material::new().normal_perturbation, None);
+}
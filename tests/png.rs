@@ -0,0 +1,228 @@
+use ray_tracer::{
+    adler32, color, crc32, png_decode, png_encode, zlib_compress, zlib_decompress, Canvas,
+    Compression, ImageError, PngBitDepth, ZlibError, PNG_SIGNATURE,
+};
+
+fn chunks(png: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let mut res = Vec::new();
+    let mut pos = PNG_SIGNATURE.len();
+    while pos < png.len() {
+        let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind = String::from_utf8(png[pos + 4..pos + 8].to_vec()).unwrap();
+        let data = png[pos + 8..pos + 8 + len].to_vec();
+        let crc = u32::from_be_bytes(png[pos + 8 + len..pos + 12 + len].try_into().unwrap());
+        assert_eq!(crc, crc32(&png[pos + 4..pos + 8 + len]));
+        res.push((kind, data));
+        pos += len + 12;
+    }
+    res
+}
+
+#[test]
+fn compute_checksums() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"IEND"), 0xae426082);
+    assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    assert_eq!(adler32(b""), 1);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+}
+
+#[test]
+fn compress_stored() {
+    let z = zlib_compress(b"abc", Compression::Stored);
+    assert_eq!(
+        z,
+        vec![0x78, 0x01, 0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c', 0x02, 0x4d, 0x01, 0x27]
+    );
+
+    let z = zlib_compress(b"", Compression::Stored);
+    assert_eq!(
+        z,
+        vec![0x78, 0x01, 0x01, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01]
+    );
+
+    let data = vec![7_u8; 70000];
+    let z = zlib_compress(&data, Compression::Stored);
+    assert_eq!(z.len(), 2 + 5 + 65535 + 5 + 4465 + 4);
+    assert_eq!(z[2], 0x00);
+    assert_eq!(z[2 + 5 + 65535], 0x01);
+}
+
+#[test]
+fn compress_fixed() {
+    let z = zlib_compress(b"", Compression::Fixed);
+    assert_eq!(z, vec![0x78, 0x01, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01]);
+
+    let z = zlib_compress(b"a", Compression::Fixed);
+    assert_eq!(
+        z,
+        vec![0x78, 0x01, 0x4b, 0x04, 0x00, 0x00, 0x62, 0x00, 0x62]
+    );
+
+    let data = vec![7_u8; 70000];
+    let z = zlib_compress(&data, Compression::Fixed);
+    assert!(z.len() < 1000);
+    assert_eq!(&z[z.len() - 4..], &adler32(&data).to_be_bytes());
+}
+
+#[test]
+fn encode_png_header() {
+    let png = png_encode(2, 3, PngBitDepth::Eight, &[0; 18]);
+    assert_eq!(&png[..8], &PNG_SIGNATURE);
+
+    let chunks = chunks(&png);
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks[0].0, "IHDR");
+    assert_eq!(chunks[0].1, vec![0, 0, 0, 2, 0, 0, 0, 3, 8, 2, 0, 0, 0]);
+    assert_eq!(chunks[1].0, "IDAT");
+    assert_eq!(chunks[2].0, "IEND");
+    assert!(chunks[2].1.is_empty());
+}
+
+#[test]
+#[should_panic]
+fn encode_png_with_wrong_size() {
+    png_encode(2, 3, PngBitDepth::Sixteen, &[0; 18]);
+}
+
+#[test]
+fn construct_png_from_canvas() {
+    let mut c = Canvas::new(5, 3);
+    c.set(0, 0, &color(1.5, 0.0, 0.0));
+    c.set(2, 1, &color(0.0, 0.5, 0.0));
+    c.set(4, 2, &color(-0.5, 0.0, 1.0));
+
+    let chunks8 = chunks(&c.png(PngBitDepth::Eight));
+    assert_eq!(chunks8[0].1[8], 8);
+    let chunks16 = chunks(&c.png(PngBitDepth::Sixteen));
+    assert_eq!(chunks16[0].1[8], 16);
+    assert_eq!(&chunks16[0].1[..8], &[0, 0, 0, 5, 0, 0, 0, 3]);
+}
+
+// Compressed with the reference zlib at level 9, uses dynamic Huffman codes
+const DYNAMIC_STREAM: [u8; 188] = [
+    0x78, 0xda, 0x2d, 0x90, 0x81, 0x11, 0x04, 0x31, 0x08, 0x02, 0x6b, 0x15, 0x45, 0xe9, 0xbf, 0x82,
+    0x87, 0xdc, 0x67, 0x26, 0x97, 0x33, 0x03, 0xae, 0x44, 0xa5, 0x92, 0x54, 0xa7, 0x59, 0xd4, 0x9c,
+    0x74, 0xe3, 0x5a, 0x50, 0xfb, 0xbe, 0xf5, 0xad, 0x5e, 0x2a, 0xba, 0xcd, 0x3f, 0x07, 0xe5, 0x92,
+    0x10, 0x95, 0xed, 0x35, 0x6c, 0x68, 0x74, 0x54, 0x77, 0x4e, 0x5f, 0x79, 0x77, 0x13, 0x63, 0x8f,
+    0x3b, 0xbd, 0x9e, 0xd4, 0xba, 0x50, 0x5f, 0x3d, 0x50, 0x75, 0x61, 0x49, 0x5c, 0x6f, 0x3d, 0x2c,
+    0x5e, 0x2b, 0xfb, 0xd0, 0x76, 0x55, 0x84, 0xe1, 0x61, 0x3b, 0xde, 0xa1, 0x3f, 0xac, 0xa1, 0xa9,
+    0x86, 0x02, 0x96, 0xd7, 0x66, 0xc6, 0xbf, 0x6f, 0x07, 0x1b, 0xe9, 0x9e, 0xbd, 0x1e, 0xe5, 0x9c,
+    0xc1, 0xdd, 0x5f, 0xeb, 0xe1, 0x62, 0xc1, 0xf8, 0x4c, 0x3f, 0x0b, 0x23, 0x82, 0x31, 0x07, 0xec,
+    0x4b, 0x0b, 0x95, 0x6d, 0x2a, 0xc7, 0x7b, 0x39, 0x13, 0xc0, 0xb2, 0x09, 0x9c, 0x7b, 0xb8, 0x4d,
+    0xe4, 0xc9, 0x33, 0x18, 0x1d, 0xba, 0x8b, 0xde, 0xcc, 0x72, 0xd1, 0xc3, 0x6f, 0x78, 0x65, 0xb9,
+    0xb5, 0x2f, 0x86, 0x98, 0x6b, 0x47, 0x9e, 0x0c, 0x5a, 0xd7, 0xef, 0x39, 0x69, 0x60, 0xc6, 0xd6,
+    0x87, 0x30, 0x14, 0xfa, 0x6a, 0x46, 0xf9, 0x03, 0x6d, 0xbc, 0x9e, 0xd6,
+];
+
+fn dynamic_stream_data() -> Vec<u8> {
+    let mut x = 12345_u64;
+    let mut data = Vec::new();
+    for _ in 0..400 {
+        x = (x * 1103515245 + 12345) % (1 << 31);
+        data.push(b"abcdefgh"[((x >> 16) % 11).min(7) as usize]);
+    }
+    data
+}
+
+#[test]
+fn decompress_streams() {
+    let mut data = Vec::new();
+    for i in 0..100000_u32 {
+        data.push((i % 251) as u8 ^ (i / 1000) as u8);
+    }
+    for compression in [Compression::Stored, Compression::Fixed] {
+        let z = zlib_compress(&data, compression);
+        assert_eq!(zlib_decompress(&z).unwrap(), data);
+        assert_eq!(
+            zlib_decompress(&zlib_compress(b"", compression)).unwrap(),
+            Vec::<u8>::new()
+        );
+    }
+
+    assert_eq!(
+        zlib_decompress(&DYNAMIC_STREAM).unwrap(),
+        dynamic_stream_data()
+    );
+}
+
+#[test]
+fn decompress_broken_streams() {
+    let z = zlib_compress(b"abcabcabcabc", Compression::Fixed);
+    assert_eq!(
+        zlib_decompress(&z[..z.len() - 6]),
+        Err(ZlibError::Truncated)
+    );
+
+    let mut bad = z.clone();
+    let last = bad.len() - 1;
+    bad[last] ^= 0xff;
+    assert!(matches!(zlib_decompress(&bad), Err(ZlibError::Corrupt(_))));
+
+    assert!(matches!(
+        zlib_decompress(&[0x78, 0x02, 0x03, 0x00]),
+        Err(ZlibError::Corrupt(_))
+    ));
+}
+
+fn gradient_canvas() -> Canvas {
+    let mut c = Canvas::new(17, 9);
+    for x in 0..17 {
+        for y in 0..9 {
+            c.set(
+                x,
+                y,
+                &color(x as f64 / 16.0, y as f64 / 8.0, ((x * y) % 5) as f64 / 4.0),
+            );
+        }
+    }
+    c
+}
+
+#[test]
+fn decode_encoded_png() {
+    let c = gradient_canvas();
+    for depth in [PngBitDepth::Eight, PngBitDepth::Sixteen] {
+        let png = c.png(depth);
+        let img = png_decode(&png).unwrap();
+        assert_eq!((img.width, img.height), (17, 9));
+
+        // Writing the decoded image again gives the exact same file
+        let decoded = Canvas::from_png(&png).unwrap();
+        assert_eq!(decoded.png(depth), png);
+        for x in 0..17 {
+            for y in 0..9 {
+                let diff = decoded.at(x, y) - c.at(x, y);
+                assert!(diff.r().abs() < 1.0 / 255.0);
+                assert!(diff.g().abs() < 1.0 / 255.0);
+                assert!(diff.b().abs() < 1.0 / 255.0);
+            }
+        }
+    }
+}
+
+#[test]
+fn decode_broken_png() {
+    let png = gradient_canvas().png(PngBitDepth::Eight);
+    assert!(matches!(
+        png_decode(b"P6 1 1 255"),
+        Err(ImageError::BadMagic)
+    ));
+    assert!(matches!(
+        png_decode(&png[..png.len() - 20]),
+        Err(ImageError::Truncated)
+    ));
+
+    let mut bad = png.clone();
+    bad[20] ^= 0x01;
+    assert!(matches!(png_decode(&bad), Err(ImageError::Corrupt(_))));
+
+    let mut zero_width = png_encode(1, 1, PngBitDepth::Eight, &[0, 0, 0]);
+    zero_width[19] = 0;
+    let crc = crc32(&zero_width[12..29]);
+    zero_width[29..33].copy_from_slice(&crc.to_be_bytes());
+    assert!(matches!(
+        png_decode(&zero_width),
+        Err(ImageError::BadHeader(_))
+    ));
+}
@@ -35,3 +35,13 @@ fn scale_ray() {
     assert_eq!(r2.origin(), point(2.0, 6.0, 12.0));
     assert_eq!(r2.direction(), vector(0.0, 3.0, 0.0));
 }
+
+#[test]
+fn carry_time_along_ray() {
+    let r1 = Ray::new(point(1.0, 2.0, 3.0), vector(0.0, 1.0, 0.0));
+    assert_eq!(r1.time(), 0.0);
+    let r2 = r1.with_time(0.25);
+    assert_eq!(r2.time(), 0.25);
+    assert_eq!(r2.origin(), point(1.0, 2.0, 3.0));
+    assert_eq!(r2.transformed(translation(1.0, 0.0, 0.0)).time(), 0.25);
+}
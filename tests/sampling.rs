@@ -0,0 +1,64 @@
+use ray_tracer::{feq, ReconstructionFilter, Rng, SamplingPattern};
+
+#[test]
+fn generate_reproducible_numbers() {
+    let mut r1 = Rng::new(42);
+    let mut r2 = Rng::new(42);
+    let mut r3 = Rng::new(43);
+    for _ in 0..100 {
+        let v = r1.next_f64();
+        assert!((0.0..1.0).contains(&v));
+        assert_eq!(v, r2.next_f64());
+        assert_ne!(v, r3.next_f64());
+    }
+
+    // The keyed streams depend on all of the keys
+    let a = Rng::from_keys(1, &[2, 3]).next_u64();
+    assert_eq!(a, Rng::from_keys(1, &[2, 3]).next_u64());
+    assert_ne!(a, Rng::from_keys(1, &[3, 2]).next_u64());
+    assert_ne!(a, Rng::from_keys(2, &[2, 3]).next_u64());
+}
+
+#[test]
+fn place_samples_in_strata() {
+    let mut rng = Rng::new(0);
+    let regular = SamplingPattern::Regular.samples(2, &mut rng);
+    assert_eq!(
+        regular,
+        vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+    );
+
+    let jittered = SamplingPattern::Jittered.samples(4, &mut rng);
+    assert_eq!(jittered.len(), 16);
+    for (i, (x, y)) in jittered.iter().enumerate() {
+        let (cx, cy) = ((i % 4) as f64, (i / 4) as f64);
+        assert!(*x >= cx / 4.0 && *x < (cx + 1.0) / 4.0);
+        assert!(*y >= cy / 4.0 && *y < (cy + 1.0) / 4.0);
+    }
+    assert_ne!(jittered, SamplingPattern::Regular.samples(4, &mut rng));
+}
+
+#[test]
+fn weigh_samples_with_filters() {
+    let f = ReconstructionFilter::Box;
+    assert_eq!(f.radius(), 0.5);
+    assert_eq!(f.weight(0.4, -0.4), 1.0);
+    assert_eq!(f.weight(0.6, 0.0), 0.0);
+
+    let f = ReconstructionFilter::Tent;
+    assert_eq!(f.weight(0.0, 0.0), 1.0);
+    assert!(feq(f.weight(0.5, -0.5), 0.25));
+    assert_eq!(f.weight(1.0, 0.0), 0.0);
+
+    let f = ReconstructionFilter::Gaussian;
+    assert!(f.weight(0.0, 0.0) > f.weight(0.5, 0.0));
+    assert!(f.weight(0.5, 0.0) > f.weight(1.0, 0.0));
+    assert_eq!(f.weight(1.5, 0.0), 0.0);
+
+    // Mitchell peaks at the center, dips below zero and vanishes at the edge
+    let f = ReconstructionFilter::Mitchell;
+    assert!(feq(f.weight(0.0, 0.0), (8.0 / 9.0) * (8.0 / 9.0)));
+    assert!(f.weight(1.5, 0.0) < 0.0);
+    assert!(feq(f.weight(2.0, 0.0), 0.0));
+    assert_eq!(f.weight(0.0, 2.5), 0.0);
+}
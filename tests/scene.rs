@@ -0,0 +1,594 @@
+use ray_tracer::{
+    color, parse_scene, point, rotation_y, scaling, translation, vector, view_transform, AreaLight,
+    Background, Camera, Canvas, DirectionalLight, Light, Material, Matrix, NormalPerturbation,
+    PointLight, Projection, Ray, SceneError, SpotLight, World,
+};
+
+use std::f64::consts::PI;
+
+const CAMERA: &str = "- add: camera\n  width: 11\n  height: 11\n  field-of-view: 1.5707963267948966\n  from: [0, 0, -5]\n  to: [0, 0, 0]\n  up: [0, 1, 0]\n";
+
+fn parse_error(input: &str) -> (usize, String) {
+    match parse_scene(input, "test.yml") {
+        Err(SceneError::Parse {
+            file,
+            line,
+            message,
+        }) => {
+            assert_eq!(file, "test.yml");
+            (line, message)
+        }
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("the scene parsed fine"),
+    }
+}
+
+fn point_light_at(light: &dyn Light) -> &PointLight {
+    light.as_any().downcast_ref::<PointLight>().unwrap()
+}
+
+fn area_light_at(light: &dyn Light) -> &AreaLight {
+    light.as_any().downcast_ref::<AreaLight>().unwrap()
+}
+
+#[test]
+fn load_default_world() {
+    let input = format!(
+        "{}\n\
+         - add: light\n\
+         \x20 at: [-10, 10, -10]\n\
+         \x20 intensity: [1, 1, 1]\n\
+         - add: sphere\n\
+         \x20 material:\n\
+         \x20   color: [0.8, 1.0, 0.6]\n\
+         \x20   diffuse: 0.7\n\
+         \x20   specular: 0.2\n\
+         - add: sphere\n\
+         \x20 transform:\n\
+         \x20   - [scale, 0.5, 0.5, 0.5]\n",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    let w = World::default();
+    assert_eq!(scene.world.shapes.len(), 2);
+    assert_eq!(scene.world.lights.len(), 1);
+    assert_eq!(
+        point_light_at(scene.world.lights[0].as_ref()).position,
+        point(-10.0, 10.0, -10.0)
+    );
+    for i in 0..2 {
+        assert_eq!(scene.world.shapes[i].material(), w.shapes[i].material());
+        assert_eq!(
+            scene.world.shapes[i].current_transform(),
+            w.shapes[i].current_transform()
+        );
+    }
+
+    let mut c = Camera::new(11, 11, PI / 2.0);
+    c.set_transform(view_transform(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    assert_eq!(scene.camera.hres(), 11);
+    assert_eq!(scene.camera.pixel_size(), c.pixel_size());
+    assert_eq!(
+        scene.camera.render(&scene.world).at(5, 5),
+        c.render(&w).at(5, 5)
+    );
+}
+
+#[test]
+fn define_and_extend_materials() {
+    let input = format!(
+        "{}\n\
+         - define: white\n\
+         \x20 value:\n\
+         \x20   color: [1, 1, 1]\n\
+         \x20   diffuse: 0.7\n\
+         \x20   reflective: 0.1\n\
+         \x20   casts-shadow: false\n\
+         - define: blue\n\
+         \x20 extend: white\n\
+         \x20 value:\n\
+         \x20   color: [0.5, 0.8, 0.9]\n\
+         - add: cube\n\
+         \x20 material: blue\n",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    let mut m = Material::new();
+    m.color = color(0.5, 0.8, 0.9);
+    m.diffuse = 0.7;
+    m.reflective = 0.1;
+    m.casts_shadow = false;
+    assert_eq!(scene.world.shapes[0].material(), &m);
+}
+
+#[test]
+fn apply_transforms_in_order() {
+    let input = format!(
+        "{}\n\
+         - define: standard\n\
+         \x20 value:\n\
+         \x20   - [scale, 2, 2, 2]\n\
+         \x20   - [rotate-y, 1.5707963267948966]\n\
+         - add: plane\n\
+         \x20 transform:\n\
+         \x20   - standard\n\
+         \x20   - [translate, 1, 0, 0]\n",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    let expected = translation(1.0, 0.0, 0.0) * rotation_y(PI / 2.0) * scaling(2.0, 2.0, 2.0);
+    assert_eq!(*scene.world.shapes[0].current_transform(), expected);
+}
+
+#[test]
+fn load_patterns() {
+    let input = format!(
+        "{}\n\
+         - add: sphere\n\
+         \x20 material:\n\
+         \x20   pattern:\n\
+         \x20     type: stripes\n\
+         \x20     colors:\n\
+         \x20       - [1, 1, 1]\n\
+         \x20       - type: checkers\n\
+         \x20         colors: [[1, 0, 0], [0, 1, 0]]\n\
+         \x20         transform: [[scale, 0.25, 0.25, 0.25]]\n",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    let s = scene.world.shapes[0].as_ref();
+    let p = s.material().pattern.as_ref().unwrap();
+    assert_eq!(p.color_at(s, point(0.5, 0.0, 0.0)), color(1.0, 1.0, 1.0));
+    assert_eq!(p.color_at(s, point(1.1, 0.1, 0.1)), color(1.0, 0.0, 0.0));
+    assert_eq!(p.color_at(s, point(1.3, 0.1, 0.1)), color(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn load_image_patterns() {
+    let mut image = Canvas::new(2, 1);
+    image.set(1, 0, &color(1.0, 0.0, 0.0));
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("ray-tracer-texture-{}.ppm", std::process::id()));
+    image.save(&path.to_string_lossy()).unwrap();
+
+    let input = format!(
+        "{}\n\
+         - add: plane\n\
+         \x20 material:\n\
+         \x20   pattern:\n\
+         \x20     type: image\n\
+         \x20     file: {}\n\
+         \x20     wrap: clamp\n",
+        CAMERA,
+        path.file_name().unwrap().to_string_lossy()
+    );
+    let scene = parse_scene(&input, &dir.join("test.yml").to_string_lossy());
+    std::fs::remove_file(&path).unwrap();
+    let scene = scene.unwrap();
+    let s = scene.world.shapes[0].as_ref();
+    let p = s.material().pattern.as_ref().unwrap();
+    assert_eq!(p.color_at(s, point(-1.0, 0.0, 0.5)), color(0.0, 0.0, 0.0));
+    assert_eq!(p.color_at(s, point(2.0, 0.0, 0.5)), color(1.0, 0.0, 0.0));
+
+    match parse_scene(
+        "- add: plane\n  material:\n    pattern:\n      type: image\n      file: missing.png\n",
+        "dir/test.yml",
+    ) {
+        Err(err) => assert!(err.to_string().starts_with("dir/test.yml:5: missing.png: ")),
+        Ok(_) => panic!("expected an error"),
+    }
+}
+
+#[test]
+fn load_normal_perturbations() {
+    let input = format!(
+        "{}- add: sphere\n  material:\n    bump: {{ amplitude: 0.3, octaves: 2, transform: [[scale, 0.5, 0.5, 0.5]] }}\n",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert_eq!(
+        scene.world.shapes[0].material().normal_perturbation,
+        Some(NormalPerturbation::bump(0.3, 2, scaling(0.5, 0.5, 0.5)))
+    );
+
+    let (line, message) = parse_error(&format!(
+        "{}- add: sphere\n  material:\n    bump:\n      amplitude: 1\n      octaves: 0\n",
+        CAMERA
+    ));
+    assert_eq!(line, 12);
+    assert_eq!(message, "octaves must be in the range of 1-16");
+
+    let (line, message) = parse_error(&format!(
+        "{}- add: sphere\n  material:\n    normal-map:\n      file: map.png\n      mapping: conical\n",
+        CAMERA
+    ));
+    assert_eq!(line, 11);
+    assert!(message.starts_with("map.png: "));
+}
+
+#[test]
+fn load_backgrounds() {
+    let scene = parse_scene(CAMERA, "test.yml").unwrap();
+    assert_eq!(scene.world.background, Background::default());
+
+    let input = format!(
+        "{}- add: background\n  type: gradient\n  bottom: [1, 1, 1]\n  top: [0, 0, 1]\n",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert_eq!(
+        scene.world.background,
+        Background::Gradient {
+            bottom: color(1.0, 1.0, 1.0),
+            top: color(0.0, 0.0, 1.0)
+        }
+    );
+
+    let input = format!(
+        "{}- add: background\n  type: solid\n  color: [0.1, 0.2, 0.3]\n",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert_eq!(
+        scene.world.background,
+        Background::Solid(color(0.1, 0.2, 0.3))
+    );
+
+    let (line, message) = parse_error("- add: background\n  type: stars\n");
+    assert_eq!(
+        (line, message.as_str()),
+        (2, "unknown background type: stars")
+    );
+    let (line, message) = parse_error("- add: background\n  type: solid\n  top: [1, 1, 1]\n");
+    assert_eq!(line, 3);
+    assert_eq!(message, "unknown key: top");
+}
+
+#[test]
+fn load_shadow_settings() {
+    let scene = parse_scene(CAMERA, "test.yml").unwrap();
+    assert!(scene.world.shadows);
+    assert!(!scene.world.transparent_shadows);
+
+    let input = format!(
+        "{}- add: shadows
+  transparent: true
+",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert!(scene.world.shadows);
+    assert!(scene.world.transparent_shadows);
+
+    let input = format!(
+        "{}- add: shadows
+  enabled: false
+",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert!(!scene.world.shadows);
+
+    let (line, message) = parse_error(
+        "- add: shadows
+  transparent: 1
+",
+    );
+    assert_eq!((line, message.as_str()), (2, "expected true or false"));
+}
+
+#[test]
+fn load_nested_groups() {
+    let input = format!(
+        "{}\n\
+         - define: leg\n\
+         \x20 value:\n\
+         \x20   add: group\n\
+         \x20   children:\n\
+         \x20     - add: cylinder\n\
+         \x20       min: 0\n\
+         \x20       max: 1\n\
+         \x20       closed: true\n\
+         \x20     - add: sphere\n\
+         \x20       material: {{ color: [1, 0, 0] }}\n\
+         - add: group\n\
+         \x20 material:\n\
+         \x20   color: [0, 0, 1]\n\
+         \x20 transform: [[translate, 0, 2, 0]]\n\
+         \x20 children:\n\
+         \x20   - add: leg\n\
+         \x20     transform: [[translate, 5, 0, 0]]\n\
+         \x20   - add: csg\n\
+         \x20     operation: difference\n\
+         \x20     left: {{ add: cube }}\n\
+         \x20     right: {{ add: sphere, transform: [[scale, 1.5, 1.5, 1.5]] }}\n",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert_eq!(scene.world.shapes.len(), 1);
+
+    // The material of the outer group is inherited by the shapes that have
+    // none of their own
+    let r = Ray::new(point(5.0, 2.5, -5.0), vector(0.0, 0.0, 1.0));
+    let xs = scene.world.intersect(&r);
+    assert_eq!(xs.len(), 4);
+    assert_eq!(xs.at(0).t(), 4.0);
+    assert_eq!(xs.at(0).shape().material().color, color(0.0, 0.0, 1.0));
+
+    let r = Ray::new(point(5.0, 1.5, -5.0), vector(0.0, 0.0, 1.0));
+    let xs = scene.world.intersect(&r);
+    assert_eq!(xs.len(), 2);
+    assert_eq!(xs.at(0).shape().material().color, color(1.0, 0.0, 0.0));
+
+    // Only the corners of the cube stick out of the sphere
+    let r = Ray::new(point(0.0, 2.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(scene.world.intersect(&r).len(), 0);
+    let r = Ray::new(point(0.9, 2.9, -5.0), vector(0.0, 0.0, 1.0));
+    let xs = scene.world.intersect(&r);
+    assert_eq!(xs.len(), 4);
+    assert_eq!(xs.at(0).t(), 4.0);
+}
+
+#[test]
+fn load_area_lights() {
+    let input = format!(
+        "{}\n\
+         - add: light\n\
+         \x20 type: rect\n\
+         \x20 corner: [-1, 2, 4]\n\
+         \x20 uvec: [2, 0, 0]\n\
+         \x20 vvec: [0, 2, 0]\n\
+         \x20 usteps: 4\n\
+         \x20 vsteps: 2\n\
+         \x20 jitter: false\n\
+         \x20 intensity: [1.5, 1.5, 1.5]\n\
+         - add: light\n\
+         \x20 type: sphere\n\
+         \x20 at: [0, 10, 0]\n\
+         \x20 radius: 0.5\n\
+         \x20 steps: 3\n\
+         \x20 intensity: [1, 1, 1]\n",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    let lights = &scene.world.lights;
+    assert_eq!(lights.len(), 2);
+    let rect = area_light_at(lights[0].as_ref());
+    assert_eq!(rect.position, point(0.0, 3.0, 4.0));
+    assert_eq!(rect.sample_count(), 8);
+    assert!(!rect.jitter);
+    let sphere = area_light_at(lights[1].as_ref());
+    assert_eq!(sphere.sample_count(), 9);
+    assert!(sphere.jitter);
+
+    let (line, message) = parse_error("- add: light\n  type: rect\n  at: [0, 0, 0]\n");
+    assert_eq!(line, 3);
+    assert_eq!(message, "unknown key: at");
+    let (line, message) = parse_error(
+        "- add: light\n  type: disk\n  at: [0, 0, 0]\n  normal: [0, 1, 0]\n  radius: 1\n  steps: 0\n  intensity: [1, 1, 1]\n",
+    );
+    assert_eq!(line, 6);
+    assert_eq!(message, "steps must be positive");
+}
+
+#[test]
+fn load_spot_and_directional_lights() {
+    let input = format!(
+        "{}\n\
+         - add: light\n\
+         \x20 type: directional\n\
+         \x20 direction: [0, -2, 0]\n\
+         \x20 intensity: [1, 1, 0.9]\n\
+         - add: light\n\
+         \x20 type: spot\n\
+         \x20 at: [0, 5, 0]\n\
+         \x20 direction: [0, -1, 0]\n\
+         \x20 angle: 0.5\n\
+         \x20 falloff: 2\n\
+         \x20 intensity: [1, 1, 1]\n",
+        CAMERA
+    );
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    let lights = &scene.world.lights;
+    let sun = lights[0]
+        .as_any()
+        .downcast_ref::<DirectionalLight>()
+        .unwrap();
+    assert_eq!(sun.direction, vector(0.0, -1.0, 0.0));
+    let spot = lights[1].as_any().downcast_ref::<SpotLight>().unwrap();
+    assert_eq!(spot.position, point(0.0, 5.0, 0.0));
+    assert_eq!(spot.angle, 0.5);
+    assert_eq!(spot.falloff, 2.0);
+
+    let (line, message) = parse_error("- add: light\n  type: laser\n  intensity: [1, 1, 1]\n");
+    assert_eq!(line, 2);
+    assert_eq!(message, "unknown light type: laser");
+}
+
+#[test]
+fn report_errors_with_lines() {
+    let (line, message) = parse_error("- add: light\n  at: [1, 2]\n  intensity: [1, 1, 1]\n");
+    assert_eq!(line, 2);
+    assert_eq!(message, "expected three numbers");
+
+    let (line, message) = parse_error("- add: sphere\n  material: nope\n");
+    assert_eq!(line, 2);
+    assert_eq!(message, "undefined name: nope");
+
+    let (line, message) = parse_error("- add: teapot\n");
+    assert_eq!(line, 1);
+    assert_eq!(message, "undefined name: teapot");
+
+    let (line, message) =
+        parse_error("- add: sphere\n  material:\n    color: [1, 1, 1]\n    shiny: 1\n");
+    assert_eq!(line, 4);
+    assert_eq!(message, "unknown material property: shiny");
+
+    let (line, message) = parse_error("- add: cube\n  transform:\n    - [rotate-x, 1, 2]\n");
+    assert_eq!(line, 3);
+    assert_eq!(message, "rotate-x takes 1 arguments, got 2");
+
+    let (line, _) = parse_error("- add: cube\n  transform: [[scale, 1, 1, x]]\n");
+    assert_eq!(line, 2);
+
+    let (line, message) = parse_error("- define: a\n  value: [a]\n- add: cube\n  transform: a\n");
+    assert_eq!(line, 4);
+    assert_eq!(message, "a transform must be a sequence");
+
+    let (line, message) = parse_error("- define: a\n  value: [a]\n- add: cube\n  transform: [a]\n");
+    assert_eq!(line, 2);
+    assert_eq!(message, "definitions nested too deeply");
+
+    let (line, message) = parse_error("- add: cube\n  transform:\n    - [scale, 0, 1, 1]\n");
+    assert_eq!(line, 3);
+    assert_eq!(message, "the transform is not invertible");
+
+    let input = "- define: flat\n  value:\n    - [scale, 1, 0, 1]\n- add: sphere\n  material:\n    pattern:\n      type: stripes\n      colors: [[1, 1, 1], [0, 0, 0]]\n      transform: [flat]\n";
+    let (line, message) = parse_error(input);
+    assert_eq!(line, 3);
+    assert_eq!(message, "the transform is not invertible");
+
+    let input =
+        "- add: sphere\n  material:\n    bump: { amplitude: 1, transform: [[scale, 0, 0, 0]] }\n";
+    let (line, message) = parse_error(input);
+    assert_eq!(line, 3);
+    assert_eq!(message, "the transform is not invertible");
+
+    let input = "- add: sphere\n  end-transform: [[scale, 1, 1, 0]]\n";
+    let (line, message) = parse_error(input);
+    assert_eq!(line, 2);
+    assert_eq!(message, "the transform is not invertible");
+
+    let camera = CAMERA.replace("up: [0, 1, 0]", "up: [0, 0, 2]");
+    let (line, message) = parse_error(&camera);
+    assert_eq!(line, 1);
+    assert_eq!(
+        message,
+        "the camera needs distinct from and to, and up not parallel to the view"
+    );
+
+    let (line, message) = parse_error("- add: cube\n");
+    assert_eq!(line, 1);
+    assert_eq!(message, "the scene has no camera");
+
+    let (line, _) = parse_error("- add: cube\n - add: sphere\n");
+    assert_eq!(line, 2);
+
+    match parse_scene("- add: obj\n  file: missing.obj\n", "dir/test.yml") {
+        Err(err) => assert!(err.to_string().starts_with("dir/test.yml:2: missing.obj: ")),
+        Ok(_) => panic!("the scene parsed fine"),
+    }
+}
+
+#[test]
+fn load_example_scenes() {
+    let scene = ray_tracer::load_scene("scenes/reflection.yml").unwrap();
+    assert_eq!(scene.camera.hres(), 1280);
+    assert_eq!(scene.world.shapes.len(), 4);
+    assert_eq!(scene.world.lights.len(), 1);
+
+    let scene = ray_tracer::load_scene("scenes/soft-shadows.yml").unwrap();
+    assert_eq!(scene.world.shapes.len(), 3);
+    assert_eq!(
+        area_light_at(scene.world.lights[0].as_ref()).sample_count(),
+        64
+    );
+}
+
+#[test]
+fn load_camera_lens() {
+    let scene = parse_scene(CAMERA, "test.yml").unwrap();
+    assert_eq!(scene.camera.aperture(), 0.0);
+
+    // The camera focuses on the point it looks at unless told otherwise
+    let input = CAMERA.to_string() + "  aperture: 0.1\n";
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert_eq!(scene.camera.aperture(), 0.1);
+    assert_eq!(scene.camera.focal_distance(), 5.0);
+
+    let input = CAMERA.to_string() + "  aperture: 0.1\n  focal-distance: 2.5\n";
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert_eq!(scene.camera.focal_distance(), 2.5);
+
+    let (line, message) = parse_error(&(CAMERA.to_string() + "  aperture: -0.1\n"));
+    assert_eq!(line, 8);
+    assert_eq!(message, "aperture must be non-negative");
+
+    let (line, message) = parse_error(&(CAMERA.to_string() + "  focal-distance: 0\n"));
+    assert_eq!(line, 8);
+    assert_eq!(message, "focal-distance must be positive");
+}
+
+#[test]
+fn load_camera_projections() {
+    let scene = parse_scene(CAMERA, "test.yml").unwrap();
+    assert_eq!(scene.camera.projection(), Projection::Perspective);
+
+    let input = CAMERA.to_string() + "  projection: fisheye\n";
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert_eq!(scene.camera.projection(), Projection::Fisheye);
+    assert_eq!(scene.camera.field_of_view(), PI / 2.0);
+
+    // The projections with no field of view don't need one
+    let camera = "- add: camera\n  width: 20\n  height: 10\n  from: [0, 0, -5]\n  to: [0, 0, 0]\n  up: [0, 1, 0]\n";
+    let input = camera.to_string() + "  projection: equirectangular\n";
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert_eq!(scene.camera.projection(), Projection::Equirectangular);
+
+    let input = camera.to_string() + "  projection: orthographic\n  view-width: 8\n";
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert_eq!(
+        scene.camera.projection(),
+        Projection::Orthographic { width: 8.0 }
+    );
+
+    let (line, message) = parse_error(&(camera.to_string() + "  projection: fisheye\n"));
+    assert_eq!(line, 1);
+    assert_eq!(message, "missing key: field-of-view");
+
+    let (line, message) = parse_error(&(camera.to_string() + "  projection: orthographic\n"));
+    assert_eq!(line, 1);
+    assert_eq!(message, "missing key: view-width");
+
+    let input = camera.to_string() + "  projection: orthographic\n  view-width: 0\n";
+    let (line, message) = parse_error(&input);
+    assert_eq!(line, 8);
+    assert_eq!(message, "view-width must be positive");
+
+    let (line, message) = parse_error(&(CAMERA.to_string() + "  projection: cylindrical\n"));
+    assert_eq!(line, 8);
+    assert_eq!(message, "unknown projection: cylindrical");
+}
+
+#[test]
+fn load_motion() {
+    let input = CAMERA.to_string()
+        + "  shutter: [0.25, 0.75]\n- add: sphere\n  transform: [[translate, 1, 0, 0]]\n  end-transform: [[translate, 3, 0, 0]]\n- add: cube\n";
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    assert_eq!(scene.camera.shutter(), (0.25, 0.75));
+    let s = &scene.world.shapes[0];
+    assert_eq!(s.transform_at(0.0), translation(1.0, 0.0, 0.0));
+    assert_eq!(s.transform_at(0.5), translation(2.0, 0.0, 0.0));
+    assert_eq!(s.transform_at(1.0), translation(3.0, 0.0, 0.0));
+    let c = &scene.world.shapes[1];
+    assert_eq!(c.transform_at(1.0), Matrix::one());
+
+    // The groups move their children
+    let input = CAMERA.to_string()
+        + "- add: group\n  end-transform: [[translate, 0, 2, 0]]\n  children:\n    - add: sphere\n";
+    let scene = parse_scene(&input, "test.yml").unwrap();
+    let r = Ray::new(point(0.0, 2.0, -5.0), vector(0.0, 0.0, 1.0)).with_time(1.0);
+    assert_eq!(scene.world.intersect(&r).len(), 2);
+
+    let (line, message) = parse_error(&(CAMERA.to_string() + "  shutter: [1, 0]\n"));
+    assert_eq!(line, 8);
+    assert_eq!(message, "shutter must be the opening and the closing time");
+
+    let (line, message) = parse_error(&(CAMERA.to_string() + "  shutter: [1]\n"));
+    assert_eq!(line, 8);
+    assert_eq!(message, "shutter must be the opening and the closing time");
+}
@@ -0,0 +1,49 @@
+use ray_tracer::{
+    feq, intersect, point, smooth_triangle, vector, Intersection, Intersections, Ray, Shape,
+};
+
+fn default_smooth_triangle() -> Box<dyn Shape> {
+    smooth_triangle(
+        point(0.0, 1.0, 0.0),
+        point(-1.0, 0.0, 0.0),
+        point(1.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+        vector(-1.0, 0.0, 0.0),
+        vector(1.0, 0.0, 0.0),
+    )
+}
+
+#[test]
+fn intersection_stores_uv() {
+    let t = default_smooth_triangle();
+    let r = Ray::new(point(-0.2, 0.3, -2.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(t.as_ref(), &r);
+    assert_eq!(xs.len(), 1);
+    assert!(feq(xs[0].u(), 0.45));
+    assert!(feq(xs[0].v(), 0.25));
+}
+
+#[test]
+fn interpolate_normal_from_uv() {
+    let t = default_smooth_triangle();
+    let i = Intersection::new_uv(1.0, t.as_ref(), 0.45, 0.25);
+    let n = t.normal_at_hit(point(0.0, 0.0, 0.0), &i);
+    assert_eq!(n, vector(-0.5547, 0.83205, 0.0));
+}
+
+#[test]
+fn interpolate_normal_from_point() {
+    let t = default_smooth_triangle();
+    let n = t.normal_at(point(-0.2, 0.3, 0.0));
+    assert_eq!(n, vector(-0.5547, 0.83205, 0.0));
+}
+
+#[test]
+fn compute_properties_with_smooth_triangle() {
+    let t = default_smooth_triangle();
+    let i = Intersection::new_uv(1.0, t.as_ref(), 0.45, 0.25);
+    let r = Ray::new(point(-0.2, 0.3, -2.0), vector(0.0, 0.0, 1.0));
+    let xs = Intersections::from_vector(vec![i]);
+    let p = i.properties(&r, &xs);
+    assert_eq!(p.normalv, vector(-0.5547, 0.83205, 0.0));
+}
@@ -0,0 +1,124 @@
+use ray_tracer::{
+    color, feq, pack_stereo, point, vector, view_transform, Camera, Canvas, Eye, StereoMode,
+    StereoPacking, StereoRig, World,
+};
+
+use std::f64::consts::PI;
+
+fn rig() -> StereoRig {
+    let mut c = Camera::new(201, 101, PI / 2.0);
+    c.set_transform(view_transform(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    StereoRig::new(c, 0.1)
+}
+
+#[test]
+fn construct_parallel_eyes() {
+    let rig = rig();
+    assert_eq!(rig.mode(), StereoMode::Parallel);
+    let l = rig.eye(Eye::Left).ray_for_pixel(100, 50);
+    let r = rig.eye(Eye::Right).ray_for_pixel(100, 50);
+    assert_eq!(l.origin(), point(-0.05, 0.0, -5.0));
+    assert_eq!(r.origin(), point(0.05, 0.0, -5.0));
+    assert_eq!(l.direction(), vector(0.0, 0.0, 1.0));
+    assert_eq!(r.direction(), vector(0.0, 0.0, 1.0));
+}
+
+#[test]
+fn construct_toe_in_eyes() {
+    let mut rig = rig();
+    rig.set_mode(StereoMode::ToeIn { convergence: 5.0 });
+    let l = rig.eye(Eye::Left).ray_for_pixel(100, 50);
+    let r = rig.eye(Eye::Right).ray_for_pixel(100, 50);
+    assert_eq!(l.origin(), point(-0.05, 0.0, -5.0));
+    assert_eq!(r.origin(), point(0.05, 0.0, -5.0));
+    assert_eq!(l.direction(), vector(0.05, 0.0, 5.0).normalized());
+    assert_eq!(r.direction(), vector(-0.05, 0.0, 5.0).normalized());
+
+    // The turned image plane skews the rays near the edges vertically
+    let c = rig.camera().ray_for_pixel(0, 0).direction();
+    let l = rig.eye(Eye::Left).ray_for_pixel(0, 0).direction();
+    assert!((l.y() / l.z() - c.y() / c.z()).abs() > 1e-4);
+}
+
+#[test]
+fn construct_off_axis_eyes() {
+    let mut rig = rig();
+    rig.set_mode(StereoMode::OffAxis { convergence: 5.0 });
+    let left = rig.eye(Eye::Left);
+    let right = rig.eye(Eye::Right);
+    let shift = 0.05 / 5.0 / rig.camera().pixel_size();
+    assert!(feq(left.shift().0, shift) && feq(right.shift().0, -shift));
+
+    // The rays through the middle of the image meet at the convergence
+    // distance, just like with the toe-in
+    let l = left.ray_for_pixel(100, 50);
+    let r = right.ray_for_pixel(100, 50);
+    assert_eq!(l.origin(), point(-0.05, 0.0, -5.0));
+    assert_eq!(r.origin(), point(0.05, 0.0, -5.0));
+    assert_eq!(l.direction(), vector(0.05, 0.0, 5.0).normalized());
+    assert_eq!(r.direction(), vector(-0.05, 0.0, 5.0).normalized());
+
+    // But the image planes stay parallel, so there is no vertical skew
+    let c = rig.camera().ray_for_pixel(0, 0).direction();
+    let l = left.ray_for_pixel(0, 0).direction();
+    assert!((l.y() / l.z() - c.y() / c.z()).abs() < 1e-9);
+}
+
+#[test]
+fn pack_stereo_pairs() {
+    let mut left = Canvas::new(2, 3);
+    let mut right = Canvas::new(2, 3);
+    left.set(1, 2, &color(1.0, 0.0, 0.0));
+    right.set(0, 1, &color(0.0, 1.0, 0.0));
+
+    let img = pack_stereo(&left, &right, StereoPacking::SideBySide);
+    assert_eq!((img.width(), img.height()), (4, 3));
+    assert_eq!(img.at(1, 2), color(1.0, 0.0, 0.0));
+    assert_eq!(img.at(2, 1), color(0.0, 1.0, 0.0));
+
+    let img = pack_stereo(&left, &right, StereoPacking::TopBottom);
+    assert_eq!((img.width(), img.height()), (2, 6));
+    assert_eq!(img.at(1, 2), color(1.0, 0.0, 0.0));
+    assert_eq!(img.at(0, 4), color(0.0, 1.0, 0.0));
+}
+
+#[test]
+#[should_panic]
+fn pack_stereo_pairs_of_different_sizes() {
+    pack_stereo(
+        &Canvas::new(2, 3),
+        &Canvas::new(3, 2),
+        StereoPacking::SideBySide,
+    );
+}
+
+#[test]
+fn render_stereo_pairs() {
+    let w = World::default();
+    let mut c = Camera::new(11, 11, PI / 2.0);
+    c.set_transform(view_transform(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    let mut rig = StereoRig::new(c, 0.5);
+    rig.set_mode(StereoMode::OffAxis { convergence: 5.0 });
+
+    let (left, right) = rig.render(&w);
+    let packed = rig.render_packed(&w, StereoPacking::SideBySide);
+    assert_eq!((packed.width(), packed.height()), (22, 11));
+    for y in 0..11 {
+        for x in 0..11 {
+            assert_eq!(packed.at(x, y), left.at(x, y));
+            assert_eq!(packed.at(x + 11, y), right.at(x, y));
+        }
+    }
+
+    // The eyes see the sphere from different sides
+    let differ = (0..11).any(|x| left.at(x, 5) != right.at(x, 5));
+    assert!(differ);
+}
@@ -0,0 +1,169 @@
+use ray_tracer::{
+    color, cube_uv, image_pattern_unit, point, spherical_uv, Canvas, CubeFace, Texture,
+    TextureWrap, Tuple, UvMapping,
+};
+
+use std::f64::consts::FRAC_1_SQRT_2;
+use std::sync::Arc;
+
+const BLACK: Tuple = color(0.0, 0.0, 0.0);
+const WHITE: Tuple = color(1.0, 1.0, 1.0);
+
+fn checkers_2x2() -> Canvas {
+    let mut c = Canvas::new(2, 2);
+    c.set(0, 0, &WHITE);
+    c.set(1, 1, &WHITE);
+    c
+}
+
+#[test]
+fn map_points_spherically() {
+    let td = [
+        (point(0.0, 0.0, -1.0), 0.0, 0.5),
+        (point(1.0, 0.0, 0.0), 0.25, 0.5),
+        (point(0.0, 0.0, 1.0), 0.5, 0.5),
+        (point(-1.0, 0.0, 0.0), 0.75, 0.5),
+        (point(0.0, 1.0, 0.0), 0.5, 1.0),
+        (point(0.0, -1.0, 0.0), 0.5, 0.0),
+        (point(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), 0.25, 0.75),
+        (point(0.0, 0.0, -3.0), 0.0, 0.5),
+    ];
+    for (pt, u, v) in td.iter() {
+        let (pu, pv) = spherical_uv(*pt);
+        assert!((pu - u).abs() < 1e-9 && (pv - v).abs() < 1e-9, "{:?}", pt);
+        assert_eq!(UvMapping::Spherical.uv(*pt), (pu, pv));
+    }
+}
+
+#[test]
+fn map_points_on_planes_and_cylinders() {
+    assert_eq!(UvMapping::Planar.uv(point(0.25, 0.0, 0.5)), (0.25, 0.5));
+    assert_eq!(UvMapping::Planar.uv(point(-1.25, 3.0, 2.5)), (-1.25, 2.5));
+
+    let td = [
+        (point(0.0, 0.0, -1.0), 0.0, 0.0),
+        (point(0.0, 0.5, -1.0), 0.0, 0.5),
+        (point(FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2), 0.125, 0.5),
+        (point(1.0, -0.25, 0.0), 0.25, -0.25),
+        (point(0.0, 1.5, 1.0), 0.5, 1.5),
+    ];
+    for (pt, u, v) in td.iter() {
+        let (pu, pv) = UvMapping::Cylindrical.uv(*pt);
+        assert!((pu - u).abs() < 1e-9 && (pv - v).abs() < 1e-9, "{:?}", pt);
+    }
+}
+
+#[test]
+fn map_points_on_cube_faces() {
+    let td = [
+        (point(-1.0, 0.5, -0.25), CubeFace::Left),
+        (point(1.1, -0.75, 0.8), CubeFace::Right),
+        (point(0.1, 0.6, 0.9), CubeFace::Front),
+        (point(-0.7, 0.0, -2.0), CubeFace::Back),
+        (point(0.5, 1.0, 0.9), CubeFace::Up),
+        (point(-0.2, -1.3, 1.1), CubeFace::Down),
+    ];
+    for (pt, face) in td.iter() {
+        assert_eq!(cube_uv(*pt).0, *face);
+    }
+
+    let td = [
+        (point(-0.5, 0.5, 1.0), CubeFace::Front),
+        (point(0.5, 0.5, -1.0), CubeFace::Back),
+        (point(-1.0, 0.5, -0.5), CubeFace::Left),
+        (point(1.0, 0.5, 0.5), CubeFace::Right),
+        (point(-0.5, 1.0, -0.5), CubeFace::Up),
+        (point(-0.5, -1.0, 0.5), CubeFace::Down),
+    ];
+    for (pt, face) in td.iter() {
+        assert_eq!(cube_uv(*pt), (*face, 0.25, 0.75));
+    }
+
+    // The points off the cube are projected onto it
+    assert_eq!(
+        cube_uv(point(-1.0, 1.0, 4.0)),
+        (CubeFace::Front, 0.375, 0.625)
+    );
+}
+
+#[test]
+fn sample_textures_bilinearly() {
+    let t = Texture::new(checkers_2x2(), TextureWrap::Wrap);
+
+    // The centers of the pixels
+    assert_eq!(t.sample(0.25, 0.75), WHITE);
+    assert_eq!(t.sample(0.75, 0.75), BLACK);
+    assert_eq!(t.sample(0.25, 0.25), BLACK);
+    assert_eq!(t.sample(0.75, 0.25), WHITE);
+
+    // Half way between the pixels
+    assert_eq!(t.sample(0.5, 0.75), color(0.5, 0.5, 0.5));
+    assert_eq!(t.sample(0.5, 0.5), color(0.5, 0.5, 0.5));
+    assert_eq!(t.sample(0.375, 0.75), color(0.75, 0.75, 0.75));
+}
+
+#[test]
+fn wrap_and_clamp_textures() {
+    let wrapped = Texture::new(checkers_2x2(), TextureWrap::Wrap);
+    let clamped = Texture::new(checkers_2x2(), TextureWrap::Clamp);
+
+    // The edges blend with the opposite side or extend the edge pixels
+    assert_eq!(wrapped.sample(0.0, 0.75), color(0.5, 0.5, 0.5));
+    assert_eq!(clamped.sample(0.0, 0.75), WHITE);
+
+    // The image repeats itself or the edge pixels extend indefinitely
+    assert_eq!(wrapped.sample(2.25, -0.25), WHITE);
+    assert_eq!(wrapped.sample(2.75, -0.25), BLACK);
+    assert_eq!(wrapped.sample(-0.75, 0.75), WHITE);
+    assert_eq!(clamped.sample(2.25, -0.25), WHITE);
+    assert_eq!(clamped.sample(-3.0, 5.0), WHITE);
+    assert_eq!(clamped.sample(5.0, 5.0), BLACK);
+}
+
+#[test]
+fn sample_cube_maps() {
+    // One pixel per face in the cross layout
+    let faces = [
+        (CubeFace::Left, color(1.0, 0.0, 0.0)),
+        (CubeFace::Right, color(0.0, 1.0, 0.0)),
+        (CubeFace::Front, color(0.0, 0.0, 1.0)),
+        (CubeFace::Back, color(1.0, 1.0, 0.0)),
+        (CubeFace::Up, color(0.0, 1.0, 1.0)),
+        (CubeFace::Down, color(1.0, 0.0, 1.0)),
+    ];
+    let mut c = Canvas::new(4, 3);
+    for (face, col) in faces.iter() {
+        let (x, y) = face.cell();
+        c.set(x, y, col);
+    }
+
+    let p = image_pattern_unit(c, UvMapping::Cube, TextureWrap::Wrap);
+    let td = [
+        (point(-1.0, 0.9, -0.9), CubeFace::Left),
+        (point(1.0, -0.9, 0.9), CubeFace::Right),
+        (point(0.9, 0.9, 1.0), CubeFace::Front),
+        (point(-0.9, -0.9, -1.0), CubeFace::Back),
+        (point(0.9, 1.0, -0.9), CubeFace::Up),
+        (point(-0.9, -1.0, 0.9), CubeFace::Down),
+    ];
+    for (pt, face) in td.iter() {
+        let expected = faces.iter().find(|f| f.0 == *face).unwrap().1;
+        assert_eq!(p.shape_color_at(*pt), expected);
+    }
+}
+
+#[test]
+fn compare_image_patterns() {
+    let image = Arc::new(checkers_2x2());
+    let p1 = image_pattern_unit(image.clone(), UvMapping::Planar, TextureWrap::Wrap);
+    let p2 = image_pattern_unit(image.clone(), UvMapping::Planar, TextureWrap::Wrap);
+    let p3 = image_pattern_unit(image, UvMapping::Spherical, TextureWrap::Wrap);
+    let p4 = image_pattern_unit(checkers_2x2(), UvMapping::Planar, TextureWrap::Wrap);
+    assert!(p1 == p2);
+    assert!(p1 == p1.clone());
+    assert!(p1 != p3);
+    assert!(p1 != p4);
+
+    assert_eq!(p1.shape_color_at(point(0.25, 0.0, 0.75)), WHITE);
+    assert_eq!(p1.shape_color_at(point(1.75, 0.0, 0.75)), BLACK);
+}
@@ -0,0 +1,59 @@
+use ray_tracer::{feq, intersect, point, triangle, vector, Ray, Shape, Triangle};
+
+fn default_triangle() -> Box<dyn Shape> {
+    triangle(
+        point(0.0, 1.0, 0.0),
+        point(-1.0, 0.0, 0.0),
+        point(1.0, 0.0, 0.0),
+    )
+}
+
+#[test]
+fn construct_triangle() {
+    let t = Triangle::new(
+        point(0.0, 1.0, 0.0),
+        point(-1.0, 0.0, 0.0),
+        point(1.0, 0.0, 0.0),
+    );
+    assert_eq!(t.p1(), point(0.0, 1.0, 0.0));
+    assert_eq!(t.p2(), point(-1.0, 0.0, 0.0));
+    assert_eq!(t.p3(), point(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn compute_triangle_normal() {
+    let t = default_triangle();
+    let n1 = t.normal_at(point(0.0, 0.5, 0.0));
+    let n2 = t.normal_at(point(-0.5, 0.75, 0.0));
+    let n3 = t.normal_at(point(0.5, 0.25, 0.0));
+    assert_eq!(n1, vector(0.0, 0.0, -1.0));
+    assert_eq!(n2, vector(0.0, 0.0, -1.0));
+    assert_eq!(n3, vector(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn intersect_ray_and_triangle() {
+    let t = default_triangle();
+
+    // parallel to the triangle
+    let r = Ray::new(point(0.0, -1.0, -2.0), vector(0.0, 1.0, 0.0));
+    assert_eq!(intersect(t.as_ref(), &r).len(), 0);
+
+    // misses the p1-p3 edge
+    let r = Ray::new(point(1.0, 1.0, -2.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(intersect(t.as_ref(), &r).len(), 0);
+
+    // misses the p1-p2 edge
+    let r = Ray::new(point(-1.0, 1.0, -2.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(intersect(t.as_ref(), &r).len(), 0);
+
+    // misses the p2-p3 edge
+    let r = Ray::new(point(0.0, -1.0, -2.0), vector(0.0, 0.0, 1.0));
+    assert_eq!(intersect(t.as_ref(), &r).len(), 0);
+
+    // strikes the triangle
+    let r = Ray::new(point(0.0, 0.5, -2.0), vector(0.0, 0.0, 1.0));
+    let xs = intersect(t.as_ref(), &r);
+    assert_eq!(xs.len(), 1);
+    assert!(feq(xs[0].t(), 2.0));
+}
@@ -1,6 +1,7 @@
 use ray_tracer::{
-    color, dummy_pattern, feq, plane, point, point_light, sphere, sphere_unit, translation, vector,
-    Intersection, Intersections, Material, Ray, World,
+    color, dummy_pattern, feq, plane, plane_unit, point, point_light, sphere, sphere_glass,
+    sphere_unit, translation, vector, AreaLight, Background, Intersection, Intersections, Material,
+    Ray, World,
 };
 
 use std::f64::consts::{FRAC_1_SQRT_2, SQRT_2};
@@ -49,6 +50,30 @@ fn shade_ray_world_miss() {
     assert_eq!(c, color(0.0, 0.0, 0.0));
 }
 
+#[test]
+fn shade_ray_world_miss_with_background() {
+    let mut w = World::default();
+    w.background = Background::Gradient {
+        bottom: color(0.0, 0.0, 0.0),
+        top: color(0.2, 0.4, 1.0),
+    };
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 1.0, 0.0));
+    assert_eq!(w.color_at(&r, 5), color(0.2, 0.4, 1.0));
+
+    // The reflections see the background too
+    let mut m = Material::new();
+    m.color = color(0.0, 0.0, 0.0);
+    m.ambient = 0.0;
+    m.diffuse = 0.0;
+    m.specular = 0.0;
+    m.reflective = 0.5;
+    w.shapes.push(plane(translation(0.0, -1.0, 0.0)));
+    w.shapes[2].set_material(&m);
+    let r = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -1.0, 0.0));
+    assert_eq!(w.color_at(&r, 5), color(0.1, 0.2, 0.5));
+    assert_eq!(w.color_at(&r, 0), color(0.0, 0.0, 0.0));
+}
+
 #[test]
 fn shade_ray_world_hit() {
     let w = World::default();
@@ -79,9 +104,9 @@ fn check_point_in_shadow() {
     let p2 = point(-20.0, 20.0, -20.0);
     let p3 = point(-2.0, 2.0, -2.0);
 
-    assert!(w.is_shadowed(&w.lights[0], p1));
-    assert!(!w.is_shadowed(&w.lights[0], p2));
-    assert!(!w.is_shadowed(&w.lights[0], p3));
+    assert!(w.is_shadowed(w.lights[0].as_ref(), p1));
+    assert!(!w.is_shadowed(w.lights[0].as_ref(), p2));
+    assert!(!w.is_shadowed(w.lights[0].as_ref(), p3));
 }
 
 #[test]
@@ -283,3 +308,183 @@ fn shade_hit_with_reflective_and_transparent_material() {
     let c = w.shade_hit(&p, 5);
     assert_eq!(c, color(0.93391, 0.69643, 0.69243));
 }
+
+#[test]
+fn compute_point_light_visibility() {
+    let w = World::default();
+    let l = w.lights[0].as_ref();
+    for (p, expected) in [
+        (point(0.0, 1.0001, 0.0), 1.0),
+        (point(-1.0001, 0.0, 0.0), 1.0),
+        (point(0.0, 0.0, -1.0001), 1.0),
+        (point(0.0, 0.0, 1.0001), 0.0),
+        (point(1.0001, 0.0, 0.0), 0.0),
+        (point(0.0, -1.0001, 0.0), 0.0),
+        (point(0.0, 0.0, 0.0), 0.0),
+    ] {
+        assert_eq!(w.light_visibility(l, p), expected);
+    }
+}
+
+#[test]
+fn compute_area_light_visibility() {
+    let w = World::default();
+    let mut l = AreaLight::rect(
+        point(-0.5, -0.5, -5.0),
+        vector(1.0, 0.0, 0.0),
+        2,
+        vector(0.0, 1.0, 0.0),
+        2,
+        color(1.0, 1.0, 1.0),
+    );
+    l.jitter = false;
+    for (p, expected) in [
+        (point(0.0, 0.0, 2.0), 0.0),
+        (point(1.0, -1.0, 2.0), 0.25),
+        (point(1.5, 0.0, 2.0), 0.5),
+        (point(1.25, 1.25, 3.0), 0.75),
+        (point(0.0, 0.0, -2.0), 1.0),
+    ] {
+        assert_eq!(w.light_visibility(&l, p), expected);
+    }
+}
+
+#[test]
+fn cast_colored_shadows() {
+    let mut w = World::empty();
+    w.transparent_shadows = true;
+    w.lights
+        .push(point_light(point(0.0, 10.0, 0.0), color(1.0, 1.0, 1.0)));
+    let mut glass = plane(translation(0.0, 1.0, 0.0));
+    glass.material_mut().transparency = 0.5;
+    glass.material_mut().color = color(1.0, 0.5, 0.0);
+    w.shapes.push(glass);
+
+    let l = w.lights[0].as_ref();
+    let p = point(0.0, 0.0, 0.0);
+    assert_eq!(w.light_transmittance(l, p), color(0.5, 0.25, 0.0));
+    assert_eq!(w.light_visibility(l, p), 1.0);
+    assert!(!w.is_shadowed(l, p));
+
+    // Every crossing filters the light
+    let mut glass = sphere(translation(0.0, 3.0, 0.0));
+    glass.material_mut().transparency = 0.8;
+    w.shapes.push(glass);
+    assert_eq!(
+        w.light_transmittance(w.lights[0].as_ref(), p),
+        color(0.32, 0.16, 0.0)
+    );
+
+    w.transparent_shadows = false;
+    assert_eq!(
+        w.light_transmittance(w.lights[0].as_ref(), p),
+        color(0.0, 0.0, 0.0)
+    );
+    assert!(w.is_shadowed(w.lights[0].as_ref(), p));
+}
+
+#[test]
+fn ignore_shapes_not_casting_shadows() {
+    let mut w = World::default();
+    let p = point(10.0, -10.0, 10.0);
+    assert!(w.is_shadowed(w.lights[0].as_ref(), p));
+    for s in w.shapes.iter_mut() {
+        s.material_mut().casts_shadow = false;
+    }
+    assert!(!w.is_shadowed(w.lights[0].as_ref(), p));
+    assert_eq!(
+        w.light_transmittance(w.lights[0].as_ref(), p),
+        color(1.0, 1.0, 1.0)
+    );
+}
+
+#[test]
+fn find_any_hit_in_world() {
+    let mut w = World::default();
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert!(w.any_hit(&r, f64::INFINITY, &|_| true));
+    assert!(w.any_hit(&r, 4.1, &|_| true));
+    assert!(!w.any_hit(&r, 4.0, &|_| true));
+    assert!(!w.any_hit(&r, f64::INFINITY, &|m| m.reflective > 0.0));
+
+    // The hits behind the origin of the ray don't count
+    let r = Ray::new(point(0.0, 0.0, 5.0), vector(0.0, 0.0, 1.0));
+    assert!(!w.any_hit(&r, f64::INFINITY, &|_| true));
+
+    w.shapes[0].material_mut().casts_shadow = false;
+    let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    assert!(w.any_hit(&r, f64::INFINITY, &|m| m.casts_shadow));
+    assert!(!w.any_hit(&r, 4.4, &|m| m.casts_shadow));
+}
+
+#[test]
+fn shade_hit_through_glass() {
+    let mut w = World::empty();
+    w.transparent_shadows = true;
+    w.lights
+        .push(point_light(point(0.0, 10.0, 0.0), color(1.0, 1.0, 1.0)));
+    w.shapes.push(plane_unit());
+    let mut glass = sphere_glass();
+    glass.transform(translation(0.0, 3.0, 0.0));
+    w.shapes.push(glass);
+    let r = Ray::new(point(0.0, 1.0, -5.0), vector(0.0, -1.0, 5.0).normalized());
+    let xs = w.intersect(&r);
+    let lit = w.shade_hit(&xs.at(0).properties(&r, &xs), 0);
+
+    // A glass sphere lets all of the light through
+    let mut w = World::empty();
+    w.lights
+        .push(point_light(point(0.0, 10.0, 0.0), color(1.0, 1.0, 1.0)));
+    w.shapes.push(plane_unit());
+    let xs = w.intersect(&r);
+    assert_eq!(lit, w.shade_hit(&xs.at(0).properties(&r, &xs), 0));
+}
+
+#[test]
+fn shade_hit_shadows_the_same_light_samples() {
+    let mut w = World::empty();
+    w.lights.push(Box::new(AreaLight::rect(
+        point(-1.0, 5.0, -1.0),
+        vector(2.0, 0.0, 0.0),
+        4,
+        vector(0.0, 0.0, 2.0),
+        4,
+        color(1.0, 1.0, 1.0),
+    )));
+    w.shapes.push(plane_unit());
+    w.shapes.push(sphere(translation(1.0, 2.5, 0.0)));
+    let r = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -1.0, 1.0).normalized());
+    let xs = w.intersect(&r);
+    let props = xs.at(0).properties(&r, &xs);
+
+    // The jittered samples taken at the point are the ones that get shaded
+    // and tested for shadows
+    let l = w.lights[0].as_ref();
+    let samples = l.samples(&props.point);
+    let transmittances: Vec<_> = samples
+        .iter()
+        .map(|s| {
+            let r = Ray::new(props.over_point, s.direction);
+            match w.any_hit(&r, s.distance, &|m| m.casts_shadow) {
+                true => color(0.0, 0.0, 0.0),
+                false => color(1.0, 1.0, 1.0),
+            }
+        })
+        .collect();
+    let lit = transmittances.iter().filter(|t| t.r() > 0.0).count();
+    assert!(lit > 0 && lit < samples.len());
+
+    let surface = props
+        .shape
+        .material()
+        .color_at(props.shape, &props.point, 0.0);
+    let expected = props.shape.material().lighting_with_samples(
+        &surface,
+        l,
+        &samples,
+        &transmittances,
+        &props.eyev,
+        &props.normalv,
+    );
+    assert_eq!(w.shade_hit(&props, 0), expected);
+}
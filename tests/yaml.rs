@@ -0,0 +1,105 @@
+use ray_tracer::{parse_yaml, YamlNode, YamlValue};
+
+fn scalar(node: &YamlNode) -> &str {
+    node.as_str().unwrap()
+}
+
+#[test]
+fn parse_block_collections() {
+    let input = "# a scene\n\
+                 - add: camera\n\
+                 \x20 width: 100 # pixels\n\
+                 \x20 from: [ -6, 6, -10 ]\n\
+                 \n\
+                 - define: tr\n\
+                 \x20 value:\n\
+                 \x20   - [ translate, 1, 2, 3 ]\n\
+                 \x20   - [ scale, 0.5, 0.5, 0.5 ]\n\
+                 - plain\n";
+    let doc = parse_yaml(input).unwrap();
+    let items = doc.as_sequence().unwrap();
+    assert_eq!(items.len(), 3);
+
+    assert_eq!(items[0].line, 2);
+    assert_eq!(scalar(items[0].get("add").unwrap()), "camera");
+    assert_eq!(scalar(items[0].get("width").unwrap()), "100");
+    let from = items[0].get("from").unwrap();
+    assert_eq!(from.line, 4);
+    let from: Vec<&str> = from.as_sequence().unwrap().iter().map(scalar).collect();
+    assert_eq!(from, vec!["-6", "6", "-10"]);
+
+    let value = items[1].get("value").unwrap().as_sequence().unwrap();
+    assert_eq!(value.len(), 2);
+    assert_eq!(value[1].line, 9);
+    assert_eq!(scalar(&value[1].as_sequence().unwrap()[0]), "scale");
+
+    assert_eq!(scalar(&items[2]), "plain");
+}
+
+#[test]
+fn parse_sequences_at_key_indentation() {
+    let doc = parse_yaml("transform:\n- a\n- b\nnext: 1\n").unwrap();
+    let t = doc.get("transform").unwrap().as_sequence().unwrap();
+    assert_eq!(t.len(), 2);
+    assert_eq!(scalar(doc.get("next").unwrap()), "1");
+}
+
+#[test]
+fn parse_nested_mappings() {
+    let input = "material:\n  pattern:\n    type: stripes\n    colors:\n      - [1, 1, 1]\n      - [0, 0, 0]\n  diffuse: 0.7\nempty:\n";
+    let doc = parse_yaml(input).unwrap();
+    let m = doc.get("material").unwrap();
+    assert_eq!(scalar(m.get("diffuse").unwrap()), "0.7");
+    let p = m.get("pattern").unwrap();
+    assert_eq!(scalar(p.get("type").unwrap()), "stripes");
+    assert_eq!(p.get("colors").unwrap().as_sequence().unwrap().len(), 2);
+    assert_eq!(scalar(doc.get("empty").unwrap()), "");
+}
+
+#[test]
+fn parse_flow_collections() {
+    let input = "a: { type: solid, color: [1, 0.5, 0] }\nb: [ [x, 1],\n     [y, 2] ]\nc: \"quoted # not a comment\"\nd: 'it: works'\ne: it's\n";
+    let doc = parse_yaml(input).unwrap();
+
+    let a = doc.get("a").unwrap();
+    assert_eq!(scalar(a.get("type").unwrap()), "solid");
+    assert_eq!(a.get("color").unwrap().as_sequence().unwrap().len(), 3);
+
+    let b = doc.get("b").unwrap().as_sequence().unwrap();
+    assert_eq!(b.len(), 2);
+    assert_eq!(scalar(&b[1].as_sequence().unwrap()[0]), "y");
+    assert_eq!(doc.get("c").unwrap().line, 4);
+
+    assert_eq!(scalar(doc.get("c").unwrap()), "quoted # not a comment");
+    assert_eq!(scalar(doc.get("d").unwrap()), "it: works");
+    assert_eq!(scalar(doc.get("e").unwrap()), "it's");
+}
+
+#[test]
+fn parse_empty_document() {
+    let doc = parse_yaml("# nothing here\n\n").unwrap();
+    assert_eq!(doc.value, YamlValue::Mapping(Vec::new()));
+}
+
+#[test]
+fn report_errors_with_lines() {
+    let err = parse_yaml("a: 1\nb: [1, 2\n").unwrap_err();
+    assert_eq!(err.line, 2);
+
+    let err = parse_yaml("a: 1\n    b: 2\n").unwrap_err();
+    assert_eq!(err.line, 2);
+
+    let err = parse_yaml("a: 1\na: 2\n").unwrap_err();
+    assert_eq!(err.line, 2);
+    assert_eq!(err.message, "duplicate key: a");
+
+    let err = parse_yaml("a:\n\t- 1\n").unwrap_err();
+    assert_eq!(err.line, 2);
+
+    let err = parse_yaml("- a\nb: 1\n").unwrap_err();
+    assert_eq!(err.line, 2);
+    assert_eq!(
+        err.to_string(),
+        "line 2: unexpected content after the document"
+    );
+}